@@ -0,0 +1,572 @@
+use crate::prelude::*;
+
+use std::collections::BTreeMap;
+
+mod gamepad;
+pub use gamepad::*;
+
+/// Resource path user overrides are stored at. Overrides are applied on top of the default bindings,
+/// and can then be further overridden by config values of the form `input.<action-name> = "KeyW ArrowUp"`.
+pub const USER_BINDINGS_PATH: &str = "config/input-bindings.json";
+
+
+macro_rules! declare_actions {
+	($($action:ident => ($name:literal, $label:literal)),* $(,)?) => {
+		#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+		pub enum Action {
+			$($action),*
+		}
+
+		impl Action {
+			pub const ALL: &'static [Action] = &[$(Action::$action),*];
+
+			/// Name used to refer to this action in config files.
+			pub fn config_name(self) -> &'static str {
+				match self {
+					$(Action::$action => $name),*
+				}
+			}
+
+			/// Name shown to the player in menus.
+			pub fn label(self) -> &'static str {
+				match self {
+					$(Action::$action => $label),*
+				}
+			}
+		}
+	}
+}
+
+declare_actions! {
+	MoveForward => ("move-forward", "Move Forward"),
+	MoveBack => ("move-back", "Move Back"),
+	StrafeLeft => ("strafe-left", "Strafe Left"),
+	StrafeRight => ("strafe-right", "Strafe Right"),
+
+	TurnLeft => ("turn-left", "Turn Left"),
+	TurnRight => ("turn-right", "Turn Right"),
+	LookUp => ("look-up", "Look Up"),
+	LookDown => ("look-down", "Look Down"),
+
+	Sprint => ("sprint", "Sprint"),
+	Sneak => ("sneak", "Sneak"),
+
+	Interact => ("interact", "Interact"),
+	UseItem => ("use-item", "Use Item"),
+	Examine => ("examine", "Examine"),
+	Take => ("take", "Take"),
+	Light => ("light", "Light"),
+	Pray => ("pray", "Pray"),
+	NextItem => ("next-item", "Next Item"),
+	PreviousItem => ("previous-item", "Previous Item"),
+	Ward => ("ward", "Salt Ward"),
+	Mark => ("mark", "Chalk Mark"),
+	Map => ("map", "Map"),
+	ToggleFreeCam => ("toggle-free-cam", "Toggle Free Camera"),
+
+	Pause => ("pause", "Pause"),
+	MenuConfirm => ("menu-confirm", "Menu Confirm"),
+	MenuUp => ("menu-up", "Menu Up"),
+	MenuDown => ("menu-down", "Menu Down"),
+}
+
+impl Action {
+	fn bit(self) -> u32 {
+		1 << (self as u32)
+	}
+
+	pub fn from_config_name(name: &str) -> Option<Action> {
+		Action::ALL.iter().copied()
+			.find(|action| action.config_name() == name)
+	}
+
+	/// Menu actions are only read while menus are open, so they may share bindings with gameplay actions.
+	pub fn is_menu_action(self) -> bool {
+		matches!(self, Action::MenuConfirm | Action::MenuUp | Action::MenuDown)
+	}
+}
+
+
+/// Compact set of actions, cheap enough to store per tick.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct ActionSet(u32);
+
+impl ActionSet {
+	pub fn empty() -> ActionSet {
+		ActionSet(0)
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.0 == 0
+	}
+
+	pub fn contains(&self, action: Action) -> bool {
+		self.0 & action.bit() != 0
+	}
+
+	pub fn insert(&mut self, action: Action) {
+		self.0 |= action.bit();
+	}
+
+	pub fn remove(&mut self, action: Action) {
+		self.0 &= !action.bit();
+	}
+
+	pub fn union(&self, other: ActionSet) -> ActionSet {
+		ActionSet(self.0 | other.0)
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item=Action> + use<> {
+		let set = *self;
+		Action::ALL.iter().copied()
+			.filter(move |&action| set.contains(action))
+	}
+}
+
+
+
+macro_rules! declare_keys {
+	($($key:ident),* $(,)?) => {
+		/// Every key that can be bound to an action.
+		#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+		pub enum Key {
+			$($key),*
+		}
+
+		impl Key {
+			pub const ALL: &'static [Key] = &[$(Key::$key),*];
+
+			pub fn name(self) -> &'static str {
+				match self {
+					$(Key::$key => stringify!($key)),*
+				}
+			}
+
+			fn is_down(self, input: &input::System) -> bool {
+				match self {
+					$(Key::$key => input.button_down(input::keys::$key)),*
+				}
+			}
+
+			fn is_just_down(self, input: &input::System) -> bool {
+				match self {
+					$(Key::$key => input.button_just_down(input::keys::$key)),*
+				}
+			}
+		}
+	}
+}
+
+declare_keys! {
+	KeyA, KeyB, KeyC, KeyD, KeyE, KeyF, KeyG, KeyH, KeyI, KeyJ, KeyK, KeyL, KeyM,
+	KeyN, KeyO, KeyP, KeyQ, KeyR, KeyS, KeyT, KeyU, KeyV, KeyW, KeyX, KeyY, KeyZ,
+
+	Digit0, Digit1, Digit2, Digit3, Digit4, Digit5, Digit6, Digit7, Digit8, Digit9,
+	Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9,
+
+	ArrowUp, ArrowDown, ArrowLeft, ArrowRight,
+
+	Space, Enter, Tab, Backspace, Escape,
+	Shift, Control, Alt,
+
+	Comma, Period, Slash, Semicolon, Quote, BracketLeft, BracketRight, Minus, Equal,
+}
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MouseBinding {
+	Left,
+	Right,
+	Middle,
+}
+
+impl MouseBinding {
+	pub const ALL: &'static [MouseBinding] = &[MouseBinding::Left, MouseBinding::Right, MouseBinding::Middle];
+
+	fn to_mouse_button(self) -> input::MouseButton {
+		match self {
+			MouseBinding::Left => input::MouseButton::Left,
+			MouseBinding::Right => input::MouseButton::Right,
+			MouseBinding::Middle => input::MouseButton::Middle,
+		}
+	}
+}
+
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Binding {
+	Key(Key),
+	Mouse(MouseBinding),
+	Gamepad(GamepadButton),
+}
+
+impl Binding {
+	pub fn name(&self) -> Cow<'static, str> {
+		match self {
+			Binding::Key(key) => key.name().into(),
+			Binding::Mouse(button) => format!("Mouse{button:?}").into(),
+			Binding::Gamepad(button) => button.name().into(),
+		}
+	}
+
+	pub fn from_name(name: &str) -> Option<Binding> {
+		if let Some(key) = Key::ALL.iter().find(|key| key.name() == name) {
+			return Some(Binding::Key(*key));
+		}
+
+		if let Some(button) = GamepadButton::ALL.iter().find(|button| button.name() == name) {
+			return Some(Binding::Gamepad(*button));
+		}
+
+		MouseBinding::ALL.iter()
+			.find(|button| name.strip_prefix("Mouse") == Some(format!("{button:?}").as_str()))
+			.map(|button| Binding::Mouse(*button))
+	}
+
+	/// Returns the first binding that was pressed this frame, if any. Used for rebinding.
+	pub fn any_just_down(input: &input::System, gamepad: &GamepadInput) -> Option<Binding> {
+		let key = Key::ALL.iter()
+			.find(|key| key.is_just_down(input))
+			.map(|&key| Binding::Key(key));
+
+		let mouse_button = || MouseBinding::ALL.iter()
+			.find(|button| input.button_just_down(button.to_mouse_button()))
+			.map(|&button| Binding::Mouse(button));
+
+		let gamepad_button = || GamepadButton::ALL.iter()
+			.find(|button| gamepad.is_just_down(**button))
+			.map(|&button| Binding::Gamepad(button));
+
+		key.or_else(mouse_button)
+			.or_else(gamepad_button)
+	}
+
+	pub fn is_down(&self, input: &input::System, gamepad: &GamepadInput) -> bool {
+		match *self {
+			Binding::Key(key) => key.is_down(input),
+			Binding::Mouse(button) => input.button_down(button.to_mouse_button()),
+			Binding::Gamepad(button) => gamepad.is_down(button),
+		}
+	}
+
+	pub fn is_just_down(&self, input: &input::System, gamepad: &GamepadInput) -> bool {
+		match *self {
+			Binding::Key(key) => key.is_just_down(input),
+			Binding::Mouse(button) => input.button_just_down(button.to_mouse_button()),
+			Binding::Gamepad(button) => gamepad.is_just_down(button),
+		}
+	}
+}
+
+
+
+#[derive(Debug, Clone)]
+pub struct InputBindings {
+	bindings: HashMap<Action, SmallVec<[Binding; 2]>>,
+}
+
+impl InputBindings {
+	pub fn defaults() -> InputBindings {
+		use Binding::{Key as K, Mouse as M, Gamepad as G};
+
+		let default_bindings: &[(Action, &[Binding])] = &[
+			(Action::MoveForward, &[K(Key::KeyW)]),
+			(Action::MoveBack, &[K(Key::KeyS)]),
+			(Action::StrafeLeft, &[K(Key::KeyA)]),
+			(Action::StrafeRight, &[K(Key::KeyD)]),
+
+			(Action::TurnLeft, &[K(Key::ArrowLeft), K(Key::Numpad4)]),
+			(Action::TurnRight, &[K(Key::ArrowRight), K(Key::Numpad6)]),
+			(Action::LookUp, &[K(Key::ArrowUp), K(Key::Numpad8)]),
+			(Action::LookDown, &[K(Key::ArrowDown), K(Key::Numpad2)]),
+
+			(Action::Sprint, &[K(Key::Shift), G(GamepadButton::LeftStick)]),
+			(Action::Sneak, &[K(Key::Alt), G(GamepadButton::LeftBumper)]),
+
+			(Action::Interact, &[M(MouseBinding::Left), K(Key::KeyF), G(GamepadButton::South)]),
+			(Action::UseItem, &[K(Key::KeyE), G(GamepadButton::West)]),
+			(Action::Examine, &[M(MouseBinding::Right), K(Key::KeyX), G(GamepadButton::RightBumper)]),
+			(Action::Take, &[K(Key::KeyG), G(GamepadButton::DPadUp)]),
+			(Action::Light, &[K(Key::KeyT), G(GamepadButton::DPadDown)]),
+			(Action::Pray, &[K(Key::KeyC), G(GamepadButton::RightStick)]),
+			(Action::NextItem, &[K(Key::Tab), G(GamepadButton::DPadRight)]),
+			(Action::PreviousItem, &[K(Key::KeyQ), G(GamepadButton::DPadLeft)]),
+			(Action::Ward, &[K(Key::KeyR), G(GamepadButton::East)]),
+			(Action::Mark, &[K(Key::KeyB)]),
+			(Action::Map, &[K(Key::KeyM)]),
+
+			(Action::ToggleFreeCam, &[K(Key::KeyV), G(GamepadButton::North)]),
+
+			(Action::Pause, &[K(Key::Escape), G(GamepadButton::Start)]),
+			(Action::MenuConfirm, &[K(Key::Space), G(GamepadButton::South)]),
+			(Action::MenuUp, &[G(GamepadButton::DPadUp)]),
+			(Action::MenuDown, &[G(GamepadButton::DPadDown)]),
+		];
+
+		let bindings = default_bindings.iter()
+			.map(|&(action, bindings)| (action, bindings.into()))
+			.collect();
+
+		InputBindings { bindings }
+	}
+
+	/// Default bindings, overridden by the user bindings file and then by config.
+	pub fn load(cfg: &toybox::cfg::Config, vfs: &vfs::Vfs) -> InputBindings {
+		let mut input_bindings = InputBindings::defaults();
+
+		match vfs.load_json_resource(USER_BINDINGS_PATH) {
+			Ok(user_bindings) => input_bindings.apply_user_bindings(user_bindings),
+			Err(err) => log::info!("No user input bindings loaded: {err}"),
+		}
+
+		input_bindings.apply_config_overrides(|key| cfg.get_string(key));
+		input_bindings
+	}
+
+	pub fn save(&self, vfs: &vfs::Vfs) -> anyhow::Result<()> {
+		vfs.save_json_resource(USER_BINDINGS_PATH, &self.to_user_bindings())
+			.with_context(|| format!("Failed to save input bindings to '{USER_BINDINGS_PATH}'"))?;
+
+		Ok(())
+	}
+
+	/// Bindings as they're stored in the user bindings file - binding names keyed by action config name.
+	pub fn to_user_bindings(&self) -> BTreeMap<String, Vec<String>> {
+		Action::ALL.iter()
+			.map(|&action| {
+				let binding_names = self.bindings_for(action).iter()
+					.map(|binding| binding.name().into_owned())
+					.collect();
+
+				(action.config_name().to_string(), binding_names)
+			})
+			.collect()
+	}
+
+	/// Replaces the bindings of every action in `user_bindings`. Unknown actions and bindings are skipped.
+	pub fn apply_user_bindings(&mut self, user_bindings: BTreeMap<String, Vec<String>>) {
+		for (action_name, binding_names) in user_bindings {
+			let Some(action) = Action::from_config_name(&action_name) else {
+				log::warn!("Unknown action '{action_name}' in '{USER_BINDINGS_PATH}'");
+				continue
+			};
+
+			self.set_bindings(action, binding_names.iter().filter_map(|name| parse_binding(name)));
+		}
+	}
+
+	/// Replaces the bindings of actions that have an `input.<action-name>` value, as looked up by `get_string`.
+	pub fn apply_config_overrides(&mut self, get_string: impl Fn(&str) -> Option<String>) {
+		for &action in Action::ALL {
+			if let Some(value) = get_string(&format!("input.{}", action.config_name())) {
+				self.set_bindings(action, parse_binding_list(&value));
+			}
+		}
+	}
+
+	/// Every binding that triggers more than one action in the same context, along with the actions it triggers.
+	pub fn conflicts(&self) -> Vec<(Binding, SmallVec<[Action; 2]>)> {
+		let mut conflicts: Vec<(Binding, SmallVec<[Action; 2]>)> = Vec::new();
+
+		for &action in Action::ALL {
+			for &binding in self.bindings_for(action) {
+				let existing = conflicts.iter_mut()
+					.find(|(other, actions)| *other == binding && actions[0].is_menu_action() == action.is_menu_action());
+
+				match existing {
+					Some((_, actions)) => actions.push(action),
+					None => conflicts.push((binding, [action].into_iter().collect())),
+				}
+			}
+		}
+
+		conflicts.retain(|(_, actions)| actions.len() > 1);
+		conflicts
+	}
+
+	pub fn bindings_for(&self, action: Action) -> &[Binding] {
+		self.bindings.get(&action)
+			.map_or(&[], |bindings| bindings.as_slice())
+	}
+
+	pub fn set_bindings(&mut self, action: Action, bindings: impl IntoIterator<Item=Binding>) {
+		self.bindings.insert(action, bindings.into_iter().collect());
+	}
+
+	pub fn is_down(&self, input: &input::System, gamepad: &GamepadInput, action: Action) -> bool {
+		self.bindings_for(action).iter().any(|binding| binding.is_down(input, gamepad))
+	}
+
+	pub fn is_just_down(&self, input: &input::System, gamepad: &GamepadInput, action: Action) -> bool {
+		self.bindings_for(action).iter().any(|binding| binding.is_just_down(input, gamepad))
+	}
+}
+
+fn parse_binding(name: &str) -> Option<Binding> {
+	let binding = Binding::from_name(name);
+	if binding.is_none() {
+		log::warn!("Unknown input binding '{name}'");
+	}
+
+	binding
+}
+
+fn parse_binding_list(list: &str) -> SmallVec<[Binding; 2]> {
+	list.split(|c: char| c == ',' || c.is_whitespace())
+		.filter(|name| !name.is_empty())
+		.filter_map(parse_binding)
+		.collect()
+}
+
+
+
+/// Which actions are held and which were pressed this frame, plus analog movement and look input.
+#[derive(Debug)]
+pub struct ActionState {
+	held: ActionSet,
+	pressed: ActionSet,
+
+	move_axis: Vec2,
+	look_axis: Vec2,
+	mouse_look_delta: Vec2,
+}
+
+impl ActionState {
+	pub fn new() -> ActionState {
+		ActionState {
+			held: ActionSet::empty(),
+			pressed: ActionSet::empty(),
+
+			move_axis: Vec2::zero(),
+			look_axis: Vec2::zero(),
+			mouse_look_delta: Vec2::zero(),
+		}
+	}
+
+	pub fn update(&mut self, input: &input::System, gamepad: &GamepadInput, bindings: &InputBindings) {
+		self.held = ActionSet::empty();
+		self.pressed = ActionSet::empty();
+
+		for &action in Action::ALL {
+			if bindings.is_down(input, gamepad, action) {
+				self.held.insert(action);
+			}
+
+			if bindings.is_just_down(input, gamepad, action) {
+				self.pressed.insert(action);
+			}
+		}
+
+		self.move_axis = gamepad.move_axis();
+		self.look_axis = gamepad.look_axis();
+		self.mouse_look_delta = input.mouse_delta_radians().unwrap_or(Vec2::zero());
+	}
+
+	pub fn is_down(&self, action: Action) -> bool {
+		self.held.contains(action)
+	}
+
+	pub fn just_down(&self, action: Action) -> bool {
+		self.pressed.contains(action)
+	}
+
+	pub fn held(&self) -> ActionSet {
+		self.held
+	}
+
+	pub fn pressed(&self) -> ActionSet {
+		self.pressed
+	}
+
+	/// Analog movement in [-1, 1]. +x strafes right, +y moves forward.
+	pub fn move_axis(&self) -> Vec2 {
+		self.move_axis
+	}
+
+	/// Analog look rate, relative to the keyboard turn speed. +x turns right, +y looks up.
+	pub fn look_axis(&self) -> Vec2 {
+		self.look_axis
+	}
+
+	/// Mouse movement this frame in radians.
+	pub fn mouse_look_delta(&self) -> Vec2 {
+		self.mouse_look_delta
+	}
+}
+
+
+
+#[test]
+fn binding_lists_parse_names_and_skip_unknown() {
+	let bindings = parse_binding_list("KeyW, ArrowUp  MouseLeft,NotAKey");
+	assert_eq!(bindings.as_slice(), [
+		Binding::Key(Key::KeyW),
+		Binding::Key(Key::ArrowUp),
+		Binding::Mouse(MouseBinding::Left),
+	]);
+
+	assert!(parse_binding_list("").is_empty());
+	assert!(parse_binding_list(" ,, ").is_empty());
+
+	for &button in GamepadButton::ALL {
+		assert_eq!(parse_binding_list(button.name()).as_slice(), [Binding::Gamepad(button)]);
+	}
+}
+
+#[test]
+fn default_bindings_only_conflict_with_menu_actions_separately() {
+	let mut bindings = InputBindings::defaults();
+	assert!(bindings.conflicts().is_empty(), "{:?}", bindings.conflicts());
+
+	// Gameplay and menu actions can share bindings
+	assert!(bindings.bindings_for(Action::Interact).contains(&Binding::Gamepad(GamepadButton::South)));
+	assert!(bindings.bindings_for(Action::MenuConfirm).contains(&Binding::Gamepad(GamepadButton::South)));
+
+	// Two gameplay actions can't
+	bindings.set_bindings(Action::Sprint, [Binding::Key(Key::KeyW)]);
+	let conflicts = bindings.conflicts();
+	assert_eq!(conflicts.len(), 1);
+	assert_eq!(conflicts[0].0, Binding::Key(Key::KeyW));
+	assert_eq!(conflicts[0].1.as_slice(), [Action::MoveForward, Action::Sprint]);
+}
+
+#[test]
+fn user_bindings_round_trip() {
+	let mut bindings = InputBindings::defaults();
+	bindings.set_bindings(Action::Mark, [Binding::Key(Key::KeyK), Binding::Gamepad(GamepadButton::North)]);
+	bindings.set_bindings(Action::Map, []);
+
+	let saved = serde_json::to_string(&bindings.to_user_bindings()).unwrap();
+
+	let mut loaded = InputBindings::defaults();
+	loaded.apply_user_bindings(serde_json::from_str(&saved).unwrap());
+
+	for &action in Action::ALL {
+		assert_eq!(loaded.bindings_for(action), bindings.bindings_for(action), "{action:?}");
+	}
+
+	// Unknown actions are skipped rather than failing the whole file
+	let mut loaded = InputBindings::defaults();
+	loaded.apply_user_bindings([
+		("not-an-action".to_string(), vec!["KeyZ".to_string()]),
+		("sneak".to_string(), vec!["KeyZ".to_string()]),
+	].into_iter().collect());
+
+	assert_eq!(loaded.bindings_for(Action::Sneak), [Binding::Key(Key::KeyZ)]);
+	assert_eq!(loaded.bindings_for(Action::Sprint), InputBindings::defaults().bindings_for(Action::Sprint));
+}
+
+#[test]
+fn config_overrides_replace_bindings() {
+	let mut bindings = InputBindings::defaults();
+	bindings.apply_config_overrides(|key| match key {
+		"input.move-forward" => Some("ArrowUp KeyI".to_string()),
+		"input.map" => Some(String::new()),
+		_ => None,
+	});
+
+	assert_eq!(bindings.bindings_for(Action::MoveForward), [Binding::Key(Key::ArrowUp), Binding::Key(Key::KeyI)]);
+	assert!(bindings.bindings_for(Action::Map).is_empty());
+	assert_eq!(bindings.bindings_for(Action::MoveBack), [Binding::Key(Key::KeyS)]);
+}
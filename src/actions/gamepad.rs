@@ -0,0 +1,251 @@
+use crate::prelude::*;
+
+use gilrs::{Gilrs, GamepadId, EventType};
+
+
+pub const DEFAULT_DEAD_ZONE: f32 = 0.15;
+pub const DEFAULT_RESPONSE_EXPONENT: f32 = 2.0;
+pub const DEFAULT_LOOK_SENSITIVITY: f32 = 1.5;
+
+
+macro_rules! declare_gamepad_buttons {
+	($($button:ident => $gilrs_button:ident),* $(,)?) => {
+		#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+		pub enum GamepadButton {
+			$($button),*
+		}
+
+		impl GamepadButton {
+			pub const ALL: &'static [GamepadButton] = &[$(GamepadButton::$button),*];
+
+			pub fn name(self) -> &'static str {
+				match self {
+					$(GamepadButton::$button => concat!("Pad", stringify!($button))),*
+				}
+			}
+
+			fn to_gilrs(self) -> gilrs::Button {
+				match self {
+					$(GamepadButton::$button => gilrs::Button::$gilrs_button),*
+				}
+			}
+		}
+	}
+}
+
+declare_gamepad_buttons! {
+	South => South,
+	East => East,
+	North => North,
+	West => West,
+
+	LeftBumper => LeftTrigger,
+	RightBumper => RightTrigger,
+	LeftTrigger => LeftTrigger2,
+	RightTrigger => RightTrigger2,
+
+	Select => Select,
+	Start => Start,
+
+	LeftStick => LeftThumb,
+	RightStick => RightThumb,
+
+	DPadUp => DPadUp,
+	DPadDown => DPadDown,
+	DPadLeft => DPadLeft,
+	DPadRight => DPadRight,
+}
+
+impl GamepadButton {
+	fn bit(self) -> u32 {
+		1 << (self as u32)
+	}
+}
+
+
+#[derive(Debug, Copy, Clone)]
+pub struct GamepadSettings {
+	/// Stick deflection below which input is ignored.
+	pub dead_zone: f32,
+
+	/// Stick deflection past the dead zone is raised to this power, for finer control near the center.
+	pub response_exponent: f32,
+
+	/// Multiplier on the keyboard turn speed at full deflection.
+	pub look_sensitivity: f32,
+}
+
+impl GamepadSettings {
+	pub fn load(cfg: &toybox::cfg::Config) -> GamepadSettings {
+		let get_float = |key: &str, default: f32| {
+			cfg.get_string(key)
+				.and_then(|value| value.parse().ok())
+				.unwrap_or(default)
+		};
+
+		GamepadSettings {
+			dead_zone: get_float("input.gamepad.dead-zone", DEFAULT_DEAD_ZONE).clamp(0.0, 0.95),
+			response_exponent: get_float("input.gamepad.response-exponent", DEFAULT_RESPONSE_EXPONENT).max(0.1),
+			look_sensitivity: get_float("input.gamepad.look-sensitivity", DEFAULT_LOOK_SENSITIVITY),
+		}
+	}
+
+	/// Applies radial dead zone and response curve to a raw stick value.
+	pub fn shape_stick(&self, raw: Vec2) -> Vec2 {
+		let magnitude = raw.length();
+		if magnitude <= self.dead_zone {
+			return Vec2::zero();
+		}
+
+		let rescaled = ((magnitude - self.dead_zone) / (1.0 - self.dead_zone)).min(1.0);
+		raw / magnitude * rescaled.powf(self.response_exponent)
+	}
+}
+
+
+/// Tracks state of whichever gamepad was most recently used.
+pub struct GamepadInput {
+	gilrs: Option<Gilrs>,
+	active_gamepad: Option<GamepadId>,
+
+	held: u32,
+	prev_held: u32,
+
+	left_stick: Vec2,
+	right_stick: Vec2,
+
+	pub settings: GamepadSettings,
+}
+
+impl GamepadInput {
+	pub fn new(cfg: &toybox::cfg::Config) -> GamepadInput {
+		let gilrs = match Gilrs::new() {
+			Ok(gilrs) => Some(gilrs),
+			Err(err) => {
+				log::warn!("Gamepad support unavailable: {err}");
+				None
+			}
+		};
+
+		GamepadInput {
+			gilrs,
+			active_gamepad: None,
+
+			held: 0,
+			prev_held: 0,
+
+			left_stick: Vec2::zero(),
+			right_stick: Vec2::zero(),
+
+			settings: GamepadSettings::load(cfg),
+		}
+	}
+
+	pub fn update(&mut self) {
+		self.prev_held = self.held;
+		self.held = 0;
+		self.left_stick = Vec2::zero();
+		self.right_stick = Vec2::zero();
+
+		let Some(gilrs) = &mut self.gilrs else { return };
+
+		while let Some(gilrs::Event { id, event, .. }) = gilrs.next_event() {
+			match event {
+				EventType::Connected => {
+					log::info!("Gamepad connected: {}", gilrs.gamepad(id).name());
+					self.active_gamepad.get_or_insert(id);
+				}
+
+				EventType::Disconnected => {
+					log::info!("Gamepad disconnected: {}", gilrs.gamepad(id).name());
+					if self.active_gamepad == Some(id) {
+						self.active_gamepad = None;
+					}
+				}
+
+				EventType::ButtonPressed(..) => {
+					self.active_gamepad = Some(id);
+				}
+
+				_ => {}
+			}
+		}
+
+		let Some(gamepad) = self.active_gamepad.map(|id| gilrs.gamepad(id)) else { return };
+
+		for &button in GamepadButton::ALL {
+			if gamepad.is_pressed(button.to_gilrs()) {
+				self.held |= button.bit();
+			}
+		}
+
+		self.left_stick = Vec2::new(gamepad.value(gilrs::Axis::LeftStickX), gamepad.value(gilrs::Axis::LeftStickY));
+		self.right_stick = Vec2::new(gamepad.value(gilrs::Axis::RightStickX), gamepad.value(gilrs::Axis::RightStickY));
+	}
+
+	pub fn is_down(&self, button: GamepadButton) -> bool {
+		self.held & button.bit() != 0
+	}
+
+	pub fn is_just_down(&self, button: GamepadButton) -> bool {
+		self.held & !self.prev_held & button.bit() != 0
+	}
+
+	/// Left stick after dead zone and response curve. +y is forward.
+	pub fn move_axis(&self) -> Vec2 {
+		self.settings.shape_stick(self.left_stick)
+	}
+
+	/// Right stick after dead zone, response curve and sensitivity. +x turns right, +y looks up.
+	pub fn look_axis(&self) -> Vec2 {
+		self.settings.shape_stick(self.right_stick) * self.settings.look_sensitivity
+	}
+}
+
+
+
+#[cfg(test)]
+fn test_settings() -> GamepadSettings {
+	GamepadSettings {
+		dead_zone: DEFAULT_DEAD_ZONE,
+		response_exponent: DEFAULT_RESPONSE_EXPONENT,
+		look_sensitivity: DEFAULT_LOOK_SENSITIVITY,
+	}
+}
+
+#[test]
+fn stick_inside_dead_zone_is_ignored() {
+	let settings = test_settings();
+
+	assert_eq!(settings.shape_stick(Vec2::zero()), Vec2::zero());
+	assert_eq!(settings.shape_stick(Vec2::new(0.1, 0.1)), Vec2::zero());
+	assert_eq!(settings.shape_stick(Vec2::new(0.0, -DEFAULT_DEAD_ZONE)), Vec2::zero());
+
+	// Just past the dead zone starts from zero rather than jumping
+	let just_past = settings.shape_stick(Vec2::new(DEFAULT_DEAD_ZONE + 0.01, 0.0));
+	assert!(just_past.x > 0.0 && just_past.x < 0.01);
+}
+
+#[test]
+fn stick_response_curve_stays_in_range() {
+	let settings = test_settings();
+
+	for step in 0..=20 {
+		let magnitude = step as f32 / 20.0;
+
+		for direction in [Vec2::new(1.0, 0.0), Vec2::new(0.0, -1.0), Vec2::new(0.6, 0.8)] {
+			let shaped = settings.shape_stick(direction * magnitude);
+			assert!(shaped.length() <= 1.0 + 1e-5, "{shaped:?} too long at {magnitude}");
+			assert!(shaped.length() <= magnitude + 1e-5, "curve shouldn't amplify small deflections");
+
+			if shaped.length() > 0.0 {
+				assert!(shaped.normalize().dot(direction) > 0.999, "direction changed");
+			}
+		}
+	}
+
+	// Full deflection is full output, and overshooting hardware doesn't go past it
+	assert!((settings.shape_stick(Vec2::new(1.0, 0.0)).x - 1.0).abs() < 1e-5);
+	assert!((settings.shape_stick(Vec2::new(1.2, 0.0)).length() - 1.0).abs() < 1e-5);
+}
+
@@ -40,6 +40,14 @@ impl MyAudioSystem {
 	pub fn trigger(&self) {
 		self.control.trigger_sfx.store(true, Ordering::Relaxed);
 	}
+
+	/// Scales the whole mix, e.g., while fading between worlds. 1.0 is full volume.
+	/// Providers smooth out changes in volume, so this can be set once per frame.
+	/// Worlds don't own any audio yet, so there is nothing to crossfade - world transitions just dip the whole mix.
+	pub fn set_fade(&self, level: f32) {
+		let fade_volume = linear_to_db(level.clamp(DC_OFFSET, 1.0));
+		self.control.fade_volume.store(fade_volume, Ordering::Relaxed);
+	}
 }
 
 
@@ -48,6 +56,9 @@ struct Control {
 
 	music_volume: AtomicF32,
 	sfx_volume: AtomicF32,
+
+	// Added to both music and sfx volume.
+	fade_volume: AtomicF32,
 }
 
 impl Control {
@@ -58,6 +69,7 @@ impl Control {
 			trigger_sfx: AtomicBool::new(false),
 			music_volume: AtomicF32::new(initial_volume),
 			sfx_volume: AtomicF32::new(initial_volume),
+			fade_volume: AtomicF32::new(0.0),
 		}
 	}
 }
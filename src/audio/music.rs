@@ -30,7 +30,7 @@ impl MusicProvider {
 	}
 
 	pub fn update(&mut self, ctl: &Control) {
-		self.target_volume = ctl.music_volume.load(Ordering::Relaxed);
+		self.target_volume = ctl.music_volume.load(Ordering::Relaxed) + ctl.fade_volume.load(Ordering::Relaxed);
 	}
 
 	pub fn fill(&mut self, _buffer: &mut [f32]) {
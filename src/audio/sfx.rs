@@ -36,7 +36,7 @@ impl SfxProvider {
 			self.env_phase = 0.0;
 		}
 
-		self.target_volume = ctl.sfx_volume.load(Ordering::Relaxed);
+		self.target_volume = ctl.sfx_volume.load(Ordering::Relaxed) + ctl.fade_volume.load(Ordering::Relaxed);
 	}
 
 	pub fn fill(&mut self, buffer: &mut [f32]) {
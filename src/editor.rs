@@ -1,6 +1,6 @@
 use crate::prelude::*;
 
-use model::{SourceModel, Placement, ObjectId, VertexId, WallId, RoomId};
+use model::{SourceModel, Placement, ObjectId, TriggerId, VertexId, WallId, RoomId};
 
 mod viewport;
 use viewport::{Viewport, ViewportItemFlags};
@@ -28,6 +28,7 @@ pub enum Item {
 
 	PlayerSpawn,
 	Object(ObjectId),
+	Trigger(TriggerId),
 }
 
 impl Item {
@@ -45,6 +46,9 @@ impl Item {
 			Item::Object(object_id) => world.objects.get(object_id)
 				.map(|obj| obj.placement.room_id)
 				.unwrap(),
+			Item::Trigger(trigger_id) => world.triggers.get(trigger_id)
+				.map(|trigger| trigger.room_id)
+				.unwrap(),
 		}
 	}
 }
@@ -135,12 +139,17 @@ fn validate_model(state: &mut State, model: &mut SourceModel) {
 fn validate_item(model: &SourceModel, maybe_item: &mut Option<Item>) {
 	let geometry = &model.world.geometry;
 	let objects = &model.world.objects;
+	let triggers = &model.world.triggers;
 
 	match maybe_item {
 		&mut Some(Item::Object(object_id)) if !objects.contains_key(object_id) => {
 			*maybe_item = None;
 		}
 
+		&mut Some(Item::Trigger(trigger_id)) if !triggers.contains_key(trigger_id) => {
+			*maybe_item = None;
+		}
+
 		&mut Some(Item::Wall(wall_id)) if !wall_id.is_valid(geometry) => {
 			*maybe_item = None;
 		}
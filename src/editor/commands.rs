@@ -1,5 +1,5 @@
 // use crate::prelude::*;
-use model::{SourceModel, Placement, RoomDef, Object, ObjectId, VertexId, WallId, RoomId, FogParameters};
+use model::{SourceModel, Placement, RoomDef, Object, ObjectId, Trigger, TriggerId, VertexId, WallId, RoomId, FogParameters};
 use super::*;
 
 #[derive(Debug, Clone)]
@@ -18,6 +18,7 @@ pub enum EditorWorldEditCmd {
 	SetCeilingColor(RoomId, Color),
 	SetCeilingHeight(RoomId, f32),
 	SetFloorColor(RoomId, Color),
+	SetRoomBloodDrain(RoomId, f32),
 
 	SetWallColor(WallId, Color),
 	SetHorizontalWallOffset(WallId, f32),
@@ -46,6 +47,10 @@ pub enum EditorWorldEditCmd {
 
 	SetObjectName(ObjectId, String),
 	EditObject(ObjectId, EditObjectCallback),
+
+	AddTrigger(Trigger),
+	RemoveTrigger(TriggerId),
+	SetTrigger(TriggerId, Trigger),
 }
 
 
@@ -195,6 +200,18 @@ fn handle_world_edit_cmd(_state: &mut InnerState, transaction: &mut Transaction<
 					transaction.submit();
 				}
 
+				Item::Trigger(trigger_id) => {
+					transaction.describe(format!("Move {trigger_id:?}"));
+					transaction.update_world(|_, world| {
+						let trigger = world.triggers.get_mut(trigger_id)
+							.with_context(|| format!("Trying to move non-existent {trigger_id:?}"))?;
+
+						trigger.shape.translate(delta);
+						Ok(())
+					})?;
+					transaction.submit();
+				}
+
 				Item::PlayerSpawn => {
 					todo!()
 				}
@@ -228,6 +245,15 @@ fn handle_world_edit_cmd(_state: &mut InnerState, transaction: &mut Transaction<
 			transaction.submit();
 		}
 
+		EditorWorldEditCmd::SetRoomBloodDrain(room_id, blood_drain) => {
+			transaction.describe(format!("Set {room_id:?} blood drain"));
+			transaction.update_room(room_id, |_, room| {
+				room.blood_drain = blood_drain;
+				Ok(())
+			})?;
+			transaction.submit();
+		}
+
 		EditorWorldEditCmd::SetWallColor(wall_id, color) => {
 			transaction.describe(format!("Set {wall_id:?} color"));
 			transaction.update_wall(wall_id, |_, wall| {
@@ -418,6 +444,30 @@ fn handle_world_edit_cmd(_state: &mut InnerState, transaction: &mut Transaction<
 			transaction.update_object(object_id, func.0)?;
 			transaction.submit();
 		}
+
+		EditorWorldEditCmd::AddTrigger(trigger) => {
+			transaction.describe("New Trigger");
+			transaction.update_world(|_, world| { world.triggers.insert(trigger); Ok(()) })?;
+			transaction.submit();
+		}
+
+		EditorWorldEditCmd::RemoveTrigger(trigger_id) => {
+			transaction.describe(format!("Remove {trigger_id:?}"));
+			transaction.update_world(|_, world| { world.triggers.remove(trigger_id); Ok(()) })?;
+			transaction.submit();
+		}
+
+		EditorWorldEditCmd::SetTrigger(trigger_id, trigger) => {
+			transaction.describe(format!("Edit {trigger_id:?}"));
+			transaction.update_world(|_, world| {
+				let target = world.triggers.get_mut(trigger_id)
+					.with_context(|| format!("Trying to edit non-existent {trigger_id:?}"))?;
+
+				*target = trigger;
+				Ok(())
+			})?;
+			transaction.submit();
+		}
 	}
 
 	Ok(())
@@ -13,6 +13,11 @@ pub fn do_inspector(ui: &mut egui::Ui, ctx: &mut Context) {
 
 	ui.separator();
 
+	ui.heading("Triggers");
+	draw_trigger_list(ui, ctx);
+
+	ui.separator();
+
 	ui.heading("Inspector");
 	draw_item_inspector(ui, ctx);
 }
@@ -80,6 +85,7 @@ fn draw_object_list(ui: &mut egui::Ui, ctx: &mut Context) {
 				name: "Debug Object".to_string(),
 				placement: ctx.source_player_placement,
 				info: model::ObjectInfo::Debug,
+				verbs: Vec::new(),
 			};
 
 			ctx.message_bus.emit(EditorWorldEditCmd::AddObject(object));
@@ -93,6 +99,7 @@ fn draw_object_list(ui: &mut egui::Ui, ctx: &mut Context) {
 					target_world: "world2".into(),
 					target_object: "ladder".into(),
 				},
+				verbs: Vec::new(),
 			};
 
 			ctx.message_bus.emit(EditorWorldEditCmd::AddObject(object));
@@ -107,7 +114,65 @@ fn draw_object_list(ui: &mut egui::Ui, ctx: &mut Context) {
 					height: 0.5,
 					power: 1.0,
 					radius: 1.0,
+					starts_lit: true,
+					animation: LightAnimation::Steady,
 				}),
+				verbs: Vec::new(),
+			};
+
+			ctx.message_bus.emit(EditorWorldEditCmd::AddObject(object));
+		}
+
+		if ui.button("Chest").clicked() {
+			let object = model::Object {
+				name: "chest".to_string(),
+				placement: ctx.source_player_placement,
+				info: model::ObjectInfo::Chest {
+					content: Vec::new(),
+					salt: 0,
+				},
+				verbs: Vec::new(),
+			};
+
+			ctx.message_bus.emit(EditorWorldEditCmd::AddObject(object));
+		}
+
+		if ui.button("Door").clicked() {
+			let object = model::Object {
+				name: "door".to_string(),
+				placement: ctx.source_player_placement,
+				info: model::ObjectInfo::Door {
+					key_item: None,
+					blood_cost: 0,
+				},
+				verbs: Vec::new(),
+			};
+
+			ctx.message_bus.emit(EditorWorldEditCmd::AddObject(object));
+		}
+
+		if ui.button("Altar").clicked() {
+			let object = model::Object {
+				name: "altar".to_string(),
+				placement: ctx.source_player_placement,
+				info: model::ObjectInfo::Altar {
+					ritual: model::Ritual::default(),
+					waypoint: None,
+				},
+				verbs: Vec::new(),
+			};
+
+			ctx.message_bus.emit(EditorWorldEditCmd::AddObject(object));
+		}
+
+		if ui.button("Npc").clicked() {
+			let object = model::Object {
+				name: "npc".to_string(),
+				placement: ctx.source_player_placement,
+				info: model::ObjectInfo::Npc {
+					dialogue: DialogueId::default(),
+				},
+				verbs: Vec::new(),
 			};
 
 			ctx.message_bus.emit(EditorWorldEditCmd::AddObject(object));
@@ -130,6 +195,46 @@ fn draw_object_list(ui: &mut egui::Ui, ctx: &mut Context) {
 		});
 }
 
+fn draw_trigger_list(ui: &mut egui::Ui, ctx: &mut Context) {
+	ui.horizontal(|ui| {
+		let placement = ctx.source_player_placement;
+
+		if ui.button("Circle").clicked() {
+			let shape = TriggerShape::Circle { center: placement.position, radius: 0.5 };
+			ctx.message_bus.emit(EditorWorldEditCmd::AddTrigger(Trigger::new("trigger", placement.room_id, shape)));
+		}
+
+		if ui.button("Square").clicked() {
+			let points = [(-0.5, -0.5), (0.5, -0.5), (0.5, 0.5), (-0.5, 0.5)]
+				.map(|(x, y)| placement.position + Vec2::new(x, y))
+				.to_vec();
+
+			let shape = TriggerShape::Polygon { points };
+			ctx.message_bus.emit(EditorWorldEditCmd::AddTrigger(Trigger::new("trigger", placement.room_id, shape)));
+		}
+
+		if ui.button("Room").clicked() {
+			ctx.message_bus.emit(EditorWorldEditCmd::AddTrigger(Trigger::new("trigger", placement.room_id, TriggerShape::Room)));
+		}
+	});
+
+	egui::ScrollArea::vertical()
+		.id_salt("trigger_list")
+		.show(ui, |ui| {
+			for (trigger_id, trigger) in ctx.model.world.triggers.iter() {
+				let is_selected = ctx.state.selection == Some(Item::Trigger(trigger_id));
+				let response = ui.selectable_label(is_selected, match trigger.name.is_empty() {
+					true => "<no name>",
+					false => trigger.name.as_str(),
+				});
+
+				if response.clicked() {
+					ctx.state.selection = Some(Item::Trigger(trigger_id));
+				}
+			}
+		});
+}
+
 fn draw_item_inspector(ui: &mut egui::Ui, ctx: &mut Context) {
 	ui.spacing_mut().slider_width = 200.0;
 
@@ -156,6 +261,10 @@ fn draw_item_inspector(ui: &mut egui::Ui, ctx: &mut Context) {
 			draw_object_inspector(ui, ctx, object_id);
 		}
 
+		Some(Item::Trigger(trigger_id)) => {
+			draw_trigger_inspector(ui, ctx, trigger_id);
+		}
+
 		_ => {
 			ui.label("<unimplemented>");
 		}
@@ -200,6 +309,17 @@ fn draw_room_inspector(ui: &mut egui::Ui, Context{model, message_bus, ..}: &mut
 			message_bus.emit(EditorWorldEditCmd::SetFloorColor(room_id, floor_color));
 		}
 	});
+
+	ui.separator();
+
+	ui.horizontal(|ui| {
+		ui.label("Blood Drain");
+
+		let mut blood_drain = room.blood_drain;
+		if ui.add(Slider::new(&mut blood_drain, 0.0..=20.0).step_by(0.1)).changed() {
+			message_bus.emit(EditorWorldEditCmd::SetRoomBloodDrain(room_id, blood_drain));
+		}
+	});
 }
 
 fn draw_wall_inspector(ui: &mut egui::Ui, Context{model, message_bus, ..}: &mut Context, wall_id: WallId) {
@@ -283,7 +403,136 @@ fn draw_object_inspector(ui: &mut egui::Ui, Context{model, message_bus, ..}: &mu
 			}
 		}
 
-		&ObjectInfo::Light(LightObject{color, height, power, radius}) => {
+		&ObjectInfo::Chest{ref content, salt} => {
+			ui.separator();
+
+			ui.horizontal(|ui| {
+				ui.label("Content");
+
+				let content_str = content.iter().map(ItemId::as_str).collect::<Vec<_>>().join(", ");
+				let mut content_str = Cow::from(content_str);
+				if ui.text_edit_singleline(&mut content_str).changed() {
+					let new_content: Vec<ItemId> = content_str.split(',')
+						.map(str::trim)
+						.filter(|item| !item.is_empty())
+						.map(ItemId::from)
+						.collect();
+
+					message_bus.emit(EditorWorldEditCmd::edit_object(object_id, move |_, object| {
+						if let ObjectInfo::Chest{content, ..} = &mut object.info {
+							*content = new_content;
+						}
+					}));
+				}
+			});
+
+			ui.horizontal(|ui| {
+				ui.label("Salt");
+
+				let mut new_salt = salt;
+				if ui.add(egui::DragValue::new(&mut new_salt)).changed() {
+					message_bus.emit(EditorWorldEditCmd::edit_object(object_id, move |_, object| {
+						if let ObjectInfo::Chest{salt, ..} = &mut object.info {
+							*salt = new_salt;
+						}
+					}));
+				}
+			});
+		}
+
+		&ObjectInfo::Door{ref key_item, blood_cost} => {
+			ui.separator();
+
+			ui.horizontal(|ui| {
+				ui.label("Key Item");
+
+				let mut key_item_str = Cow::from(key_item.as_ref().map(ItemId::as_str).unwrap_or(""));
+				if ui.text_edit_singleline(&mut key_item_str).changed() {
+					let new_key_item = Some(key_item_str.trim())
+						.filter(|item| !item.is_empty())
+						.map(ItemId::from);
+
+					message_bus.emit(EditorWorldEditCmd::edit_object(object_id, move |_, object| {
+						if let ObjectInfo::Door{key_item, ..} = &mut object.info {
+							*key_item = new_key_item;
+						}
+					}));
+				}
+			});
+
+			ui.horizontal(|ui| {
+				ui.label("Blood Cost");
+
+				let mut new_blood_cost = blood_cost;
+				if ui.add(egui::DragValue::new(&mut new_blood_cost)).changed() {
+					message_bus.emit(EditorWorldEditCmd::edit_object(object_id, move |_, object| {
+						if let ObjectInfo::Door{blood_cost, ..} = &mut object.info {
+							*blood_cost = new_blood_cost;
+						}
+					}));
+				}
+			});
+		}
+
+		ObjectInfo::Npc{dialogue} => {
+			ui.separator();
+
+			ui.horizontal(|ui| {
+				ui.label("Dialogue");
+
+				let mut dialogue_str = Cow::from(dialogue.as_str());
+				if ui.text_edit_singleline(&mut dialogue_str).changed() {
+					let new_dialogue = DialogueId::from(dialogue_str.trim());
+
+					message_bus.emit(EditorWorldEditCmd::edit_object(object_id, move |_, object| {
+						if let ObjectInfo::Npc{dialogue} = &mut object.info {
+							*dialogue = new_dialogue;
+						}
+					}));
+				}
+			});
+
+			if model.dialogues.get(dialogue).is_none() {
+				ui.colored_label(egui::Color32::YELLOW, format!("Unknown dialogue '{dialogue}'"));
+			}
+		}
+
+		ObjectInfo::Altar{ritual, waypoint} => {
+			ui.separator();
+
+			ui.horizontal(|ui| {
+				ui.label("Waypoint");
+
+				let mut waypoint_str = Cow::from(waypoint.as_deref().unwrap_or_default());
+				if ui.text_edit_singleline(&mut waypoint_str).changed() {
+					let waypoint_str = waypoint_str.trim();
+					let new_waypoint = (!waypoint_str.is_empty()).then(|| waypoint_str.to_string());
+
+					message_bus.emit(EditorWorldEditCmd::edit_object(object_id, move |_, object| {
+						if let ObjectInfo::Altar{waypoint, ..} = &mut object.info {
+							*waypoint = new_waypoint;
+						}
+					}));
+				}
+			});
+
+			if waypoint.is_none() {
+				ui.label("No waypoint - can't be travelled to");
+			}
+
+			let mut ritual = ritual.clone();
+			if draw_ritual_editor(ui, &mut ritual) {
+				message_bus.emit(EditorWorldEditCmd::edit_object(object_id, move |_, object| {
+					if let ObjectInfo::Altar{ritual: object_ritual, ..} = &mut object.info {
+						*object_ritual = ritual;
+					}
+				}));
+			}
+		}
+
+		ObjectInfo::Light(light) => {
+			let &LightObject{color, height, power, radius, starts_lit, ..} = light;
+
 			ui.separator();
 
 			ui.horizontal(|ui| {
@@ -337,8 +586,475 @@ fn draw_object_inspector(ui: &mut egui::Ui, Context{model, message_bus, ..}: &mu
 					}));
 				}
 			});
+
+			let mut new_starts_lit = starts_lit;
+			if ui.checkbox(&mut new_starts_lit, "Starts lit").changed() {
+				message_bus.emit(EditorWorldEditCmd::edit_object(object_id, move |_, object| {
+					if let Some(LightObject{starts_lit, ..}) = object.as_light_mut() {
+						*starts_lit = new_starts_lit;
+					}
+				}));
+			}
+
+			let mut animation = light.animation.clone();
+			if draw_light_animation_editor(ui, &mut animation) {
+				message_bus.emit(EditorWorldEditCmd::edit_object(object_id, move |_, object| {
+					if let Some(light) = object.as_light_mut() {
+						light.animation = animation;
+					}
+				}));
+			}
 		}
 
 		_ => {}
 	}
+
+	ui.separator();
+	ui.label("Verbs");
+
+	let mut verbs = object.verbs.clone();
+	let changed = ui.push_id("verbs", |ui| verb_list_widget(ui, &mut verbs)).inner;
+	if changed {
+		message_bus.emit(EditorWorldEditCmd::edit_object(object_id, move |_, object| {
+			object.verbs = verbs;
+		}));
+	}
+}
+
+fn verb_list_widget(ui: &mut egui::Ui, verbs: &mut Vec<ObjectVerb>) -> bool {
+	let mut changed = false;
+	let mut verb_to_remove = None;
+
+	for (verb_index, object_verb) in verbs.iter_mut().enumerate() {
+		ui.push_id(verb_index, |ui| {
+			ui.horizontal(|ui| {
+				egui::ComboBox::from_id_salt("verb")
+					.selected_text(object_verb.verb.label())
+					.show_ui(ui, |ui| {
+						for &verb in Verb::ALL {
+							changed |= ui.selectable_value(&mut object_verb.verb, verb, verb.label()).changed();
+						}
+					});
+
+				let mut prompt = object_verb.prompt.clone().unwrap_or_default();
+				if ui.add(egui::TextEdit::singleline(&mut prompt).hint_text("Prompt")).changed() {
+					object_verb.prompt = Some(prompt.trim())
+						.filter(|prompt| !prompt.is_empty())
+						.map(String::from);
+
+					changed = true;
+				}
+
+				if ui.button("x").clicked() {
+					verb_to_remove = Some(verb_index);
+				}
+			});
+
+			ui.indent("verb", |ui| {
+				ui.horizontal(|ui| {
+					ui.label("Required Flag");
+					changed |= required_flag_widget(ui, &mut object_verb.condition);
+				});
+
+				changed |= effect_list_widget(ui, &mut object_verb.effects);
+			});
+		});
+	}
+
+	if let Some(verb_index) = verb_to_remove {
+		verbs.remove(verb_index);
+		changed = true;
+	}
+
+	if ui.button("Add Verb").clicked() {
+		verbs.push(ObjectVerb::new(Verb::Examine));
+		changed = true;
+	}
+
+	changed
+}
+
+/// Edits simple flag conditions. Anything more complicated is just displayed.
+fn required_flag_widget(ui: &mut egui::Ui, condition: &mut Option<Condition>) -> bool {
+	if let Some(condition) = condition
+		&& !matches!(condition, Condition::Flag(_))
+	{
+		ui.label(format!("{condition:?}"));
+		return false
+	}
+
+	let mut flag = match condition {
+		Some(Condition::Flag(flag)) => flag.clone(),
+		_ => String::new(),
+	};
+
+	if !ui.text_edit_singleline(&mut flag).changed() {
+		return false
+	}
+
+	*condition = Some(flag.trim())
+		.filter(|flag| !flag.is_empty())
+		.map(|flag| Condition::Flag(flag.to_string()));
+
+	true
+}
+
+
+fn draw_ritual_editor(ui: &mut egui::Ui, ritual: &mut Ritual) -> bool {
+	let mut changed = false;
+
+	if ritual.is_empty() {
+		ui.label("No ritual - restores blood");
+	}
+
+	ui.label("Offering");
+
+	egui::Grid::new("ritual_offering").show(ui, |ui| {
+		ui.label("Items");
+		changed |= item_list_widget(ui, &mut ritual.offering.items);
+		ui.end_row();
+
+		ui.label("Blood");
+		changed |= ui.add(egui::DragValue::new(&mut ritual.offering.blood)).changed();
+		ui.end_row();
+
+		ui.label("Salt");
+		changed |= ui.add(egui::DragValue::new(&mut ritual.offering.salt)).changed();
+		ui.end_row();
+	});
+
+	changed |= ui.checkbox(&mut ritual.repeatable, "Repeatable").changed();
+
+	ui.separator();
+	ui.label("Choices");
+
+	let mut choice_to_remove = None;
+
+	for (choice_index, choice) in ritual.choices.iter_mut().enumerate() {
+		ui.push_id(("ritual_choice", choice_index), |ui| {
+			ui.horizontal(|ui| {
+				ui.label("Prompt");
+				changed |= ui.text_edit_singleline(&mut choice.prompt).changed();
+
+				if ui.button("Remove").clicked() {
+					choice_to_remove = Some(choice_index);
+				}
+			});
+
+			let mut option_to_remove = None;
+
+			ui.indent("options", |ui| {
+				for (option_index, option) in choice.options.iter_mut().enumerate() {
+					ui.push_id(option_index, |ui| {
+						ui.horizontal(|ui| {
+							ui.label("Option");
+							changed |= ui.text_edit_singleline(&mut option.label).changed();
+
+							if ui.button("Remove").clicked() {
+								option_to_remove = Some(option_index);
+							}
+						});
+
+						ui.indent("effects", |ui| {
+							changed |= effect_list_widget(ui, &mut option.effects);
+						});
+					});
+				}
+
+				if ui.button("Add Option").clicked() {
+					choice.options.push(RitualOption::default());
+					changed = true;
+				}
+			});
+
+			if let Some(option_index) = option_to_remove {
+				choice.options.remove(option_index);
+				changed = true;
+			}
+		});
+	}
+
+	if let Some(choice_index) = choice_to_remove {
+		ritual.choices.remove(choice_index);
+		changed = true;
+	}
+
+	if ui.button("Add Choice").clicked() {
+		ritual.choices.push(RitualChoice::default());
+		changed = true;
+	}
+
+	ui.separator();
+	ui.label("Effects on completion");
+
+	ui.push_id("ritual_effects", |ui| {
+		changed |= effect_list_widget(ui, &mut ritual.effects);
+	});
+
+	changed
+}
+
+fn draw_light_animation_editor(ui: &mut egui::Ui, animation: &mut LightAnimation) -> bool {
+	let mut changed = false;
+
+	ui.horizontal(|ui| {
+		ui.label("Animation");
+
+		let mut kind_name = animation.kind_name();
+
+		egui::ComboBox::from_id_salt("light_animation")
+			.selected_text(kind_name)
+			.show_ui(ui, |ui| {
+				for &name in LightAnimation::KIND_NAMES {
+					ui.selectable_value(&mut kind_name, name, name);
+				}
+			});
+
+		if kind_name != animation.kind_name() {
+			*animation = LightAnimation::from_kind_name(kind_name).unwrap();
+			changed = true;
+		}
+	});
+
+	egui::Grid::new("light_animation_params").show(ui, |ui| {
+		match animation {
+			LightAnimation::Steady => {}
+
+			LightAnimation::Flicker{amount, speed} => {
+				ui.label("Amount");
+				changed |= ui.add(Slider::new(amount, 0.0..=1.0)).changed();
+				ui.end_row();
+
+				ui.label("Speed");
+				changed |= ui.add(Slider::new(speed, 0.1..=50.0).logarithmic(true)).changed();
+				ui.end_row();
+			}
+
+			LightAnimation::Pulse{period, min_power} => {
+				ui.label("Period");
+				changed |= ui.add(Slider::new(period, 0.1..=20.0).logarithmic(true)).changed();
+				ui.end_row();
+
+				ui.label("Min Power");
+				changed |= ui.add(Slider::new(min_power, 0.0..=1.0)).changed();
+				ui.end_row();
+			}
+
+			LightAnimation::ColorCycle{colors, period} => {
+				ui.label("Period");
+				changed |= ui.add(Slider::new(period, 0.1..=20.0).logarithmic(true)).changed();
+				ui.end_row();
+
+				let mut color_to_remove = None;
+
+				for (index, color) in colors.iter_mut().enumerate() {
+					ui.label(format!("Color {index}"));
+					ui.horizontal(|ui| {
+						changed |= ui.color_edit_button_rgb(color.as_mut()).changed();
+
+						if ui.button("Remove").clicked() {
+							color_to_remove = Some(index);
+						}
+					});
+					ui.end_row();
+				}
+
+				if let Some(index) = color_to_remove {
+					colors.remove(index);
+					changed = true;
+				}
+
+				if ui.button("Add Color").clicked() {
+					colors.push(Color::white());
+					changed = true;
+				}
+				ui.end_row();
+			}
+		}
+	});
+
+	changed
+}
+
+fn item_list_widget(ui: &mut egui::Ui, items: &mut Vec<ItemId>) -> bool {
+	let items_str = items.iter().map(ItemId::as_str).collect::<Vec<_>>().join(", ");
+	let mut items_str = Cow::from(items_str);
+
+	if !ui.text_edit_singleline(&mut items_str).changed() {
+		return false
+	}
+
+	*items = items_str.split(',')
+		.map(str::trim)
+		.filter(|item| !item.is_empty())
+		.map(ItemId::from)
+		.collect();
+
+	true
+}
+
+fn effect_list_widget(ui: &mut egui::Ui, effects: &mut Vec<Effect>) -> bool {
+	let mut changed = false;
+	let mut effect_to_remove = None;
+
+	for (effect_index, effect) in effects.iter_mut().enumerate() {
+		ui.push_id(effect_index, |ui| {
+			ui.horizontal(|ui| {
+				let mut kind_name = effect.kind_name();
+
+				egui::ComboBox::from_id_salt("kind")
+					.selected_text(kind_name)
+					.show_ui(ui, |ui| {
+						for &name in Effect::KIND_NAMES {
+							ui.selectable_value(&mut kind_name, name, name);
+						}
+					});
+
+				if kind_name != effect.kind_name() {
+					*effect = Effect::from_kind_name(kind_name).unwrap();
+					changed = true;
+				}
+
+				match effect {
+					Effect::ShowText{text} => {
+						changed |= ui.text_edit_singleline(text).changed();
+					}
+
+					Effect::ShowDialog{dialogue} => {
+						changed |= ui.text_edit_singleline(&mut dialogue.0).changed();
+					}
+
+					Effect::SetFlag{name, value} => {
+						changed |= ui.text_edit_singleline(name).changed();
+						changed |= ui.checkbox(value, "").changed();
+					}
+
+					Effect::AddCounter{name, amount} => {
+						changed |= ui.text_edit_singleline(name).changed();
+						changed |= ui.add(egui::DragValue::new(amount)).changed();
+					}
+
+					Effect::GiveItem{item, count} | Effect::TakeItem{item, count} => {
+						changed |= ui.text_edit_singleline(&mut item.0).changed();
+						changed |= ui.add(egui::DragValue::new(count).range(1..=99)).changed();
+					}
+
+					Effect::AddResource{resource, amount} => {
+						changed |= ui.selectable_value(resource, Resource::Blood, "Blood").changed();
+						changed |= ui.selectable_value(resource, Resource::Salt, "Salt").changed();
+						changed |= ui.add(egui::DragValue::new(amount)).changed();
+					}
+
+					Effect::OpenObject{object_name}
+					| Effect::ToggleObject{object_name}
+					| Effect::ToggleLight{object_name}
+					| Effect::Teleport{object_name}
+					| Effect::SpawnWorm{object_name} => {
+						changed |= ui.text_edit_singleline(object_name).changed();
+					}
+
+					Effect::TransitionWorld{world_name, object_name} => {
+						changed |= ui.text_edit_singleline(world_name).changed();
+						changed |= ui.text_edit_singleline(object_name).changed();
+					}
+
+					Effect::SetFog{object_name, fog} => {
+						changed |= ui.text_edit_singleline(object_name).changed();
+						changed |= ui.color_edit_button_rgb(fog.color.as_mut()).changed();
+						changed |= ui.add(egui::DragValue::new(&mut fog.distance).prefix("dist ")).changed();
+					}
+
+					Effect::ResetFog{object_name} => {
+						changed |= ui.text_edit_singleline(object_name).changed();
+					}
+
+					Effect::PlaySound{sound} => {
+						changed |= ui.text_edit_singleline(sound).changed();
+					}
+				}
+
+				if ui.button("x").clicked() {
+					effect_to_remove = Some(effect_index);
+				}
+			});
+		});
+	}
+
+	if let Some(effect_index) = effect_to_remove {
+		effects.remove(effect_index);
+		changed = true;
+	}
+
+	if ui.button("Add Effect").clicked() {
+		effects.push(Effect::ShowText { text: String::new() });
+		changed = true;
+	}
+
+	changed
+}
+
+
+fn draw_trigger_inspector(ui: &mut egui::Ui, Context{model, message_bus, runtime_model, ..}: &mut Context, trigger_id: TriggerId) {
+	let Some(trigger) = model.world.triggers.get(trigger_id) else {
+		return
+	};
+
+	ui.label(format!("{trigger_id:?} - \"{}\"", trigger.name));
+
+	if runtime_model.triggers.is_inside(trigger_id) {
+		ui.colored_label(egui::Color32::LIGHT_GREEN, "Player inside");
+	}
+
+	let mut trigger = trigger.clone();
+	let mut changed = false;
+
+	egui::Grid::new("trigger_settings").show(ui, |ui| {
+		ui.label("Name");
+		changed |= ui.text_edit_singleline(&mut trigger.name).changed();
+		ui.end_row();
+
+		ui.label("Shape");
+		match &mut trigger.shape {
+			TriggerShape::Room => {
+				ui.label("Whole room");
+			}
+
+			TriggerShape::Circle{radius, ..} => {
+				changed |= ui.add(Slider::new(radius, 0.05..=5.0).logarithmic(true).text("radius")).changed();
+			}
+
+			TriggerShape::Polygon{points} => {
+				ui.label(format!("{} points", points.len()));
+			}
+		}
+		ui.end_row();
+
+		ui.label("Required Flag");
+		changed |= required_flag_widget(ui, &mut trigger.condition);
+		ui.end_row();
+
+		ui.label("Stay Interval");
+		changed |= ui.add(egui::DragValue::new(&mut trigger.stay_interval).range(0.0..=60.0).speed(0.1).suffix("s")).changed();
+		ui.end_row();
+	});
+
+	for (label, effects) in [("On Enter", &mut trigger.on_enter), ("On Stay", &mut trigger.on_stay), ("On Exit", &mut trigger.on_exit)] {
+		ui.separator();
+		ui.label(label);
+
+		ui.push_id(label, |ui| {
+			changed |= effect_list_widget(ui, effects);
+		});
+	}
+
+	ui.separator();
+
+	if ui.button("Delete Trigger").clicked() {
+		message_bus.emit(EditorWorldEditCmd::RemoveTrigger(trigger_id));
+		return
+	}
+
+	if changed {
+		message_bus.emit(EditorWorldEditCmd::SetTrigger(trigger_id, trigger));
+	}
 }
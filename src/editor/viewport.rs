@@ -9,6 +9,7 @@ enum ViewportItemShape {
 
 	PlayerIndicator(Mat2x3),
 	ObjectIndicator(Mat2x3),
+	Circle(Vec2, f32),
 
 	Text(String, Vec2),
 }
@@ -57,6 +58,9 @@ impl ViewportItemShape {
 
 				((target_pos - pos).length() - radius).max(0.0)
 			}
+
+			// Only the edge is interactive, so that things inside can still be selected.
+			&ViewportItemShape::Circle(center, radius) => ((target_pos - center).length() - radius).abs(),
 		}
 	}
 }
@@ -339,6 +343,44 @@ impl<'c> Viewport<'c> {
 		}
 	}
 
+	pub fn add_trigger(&mut self, trigger: &model::Trigger, item: impl Into<Option<Item>>, color: impl Into<Color>, flags: ViewportItemFlags) {
+		let transforms = self.items.iter()
+			.filter(|vpitem| vpitem.item == Some(Item::Room(trigger.room_id)))
+			.map(|vpitem| vpitem.room_to_world)
+			.collect::<Vec<_>>();
+
+		let item = item.into();
+		let color = color.into();
+
+		for room_to_world in transforms {
+			let mut push_shape = |shape| self.items.push(ViewportItem {
+				shape,
+				item,
+				color,
+				room_to_world,
+				flags,
+			});
+
+			match &trigger.shape {
+				model::TriggerShape::Room => {
+					let center = self.world.geometry.room_bounds(trigger.room_id).center();
+					push_shape(ViewportItemShape::Text(trigger.name.clone(), room_to_world * center));
+				}
+
+				&model::TriggerShape::Circle{center, radius} => {
+					push_shape(ViewportItemShape::Circle(room_to_world * center, radius));
+				}
+
+				model::TriggerShape::Polygon{points} => {
+					for (index, &start) in points.iter().enumerate() {
+						let end = points[(index + 1) % points.len()];
+						push_shape(ViewportItemShape::Line(room_to_world * start, room_to_world * end));
+					}
+				}
+			}
+		}
+	}
+
 	pub fn build(mut self) -> egui::Response {
 		self.paint_background();
 
@@ -631,6 +673,13 @@ impl Viewport<'_> {
 					}
 				}
 
+				Item::Trigger(trigger_id) => {
+					if ui.button("Delete").clicked() {
+						self.message_bus.emit(EditorWorldEditCmd::RemoveTrigger(trigger_id));
+						ui.close_menu();
+					}
+				}
+
 				_ => todo!(),
 			}
 		});
@@ -747,6 +796,18 @@ impl Viewport<'_> {
 					self.painter.line_segment([center_widget, center_widget + right_widget / 2.0], (1.0, egui::Color32::LIGHT_RED));
 					self.painter.line_segment([center_widget, center_widget + forward_widget / 2.0], (1.0, egui::Color32::LIGHT_GREEN));
 				}
+
+				&ViewportItemShape::Circle(center, radius) => {
+					let stroke_thickness = match item_hovered || item_selected {
+						false => 1.0,
+						true => 3.0,
+					};
+
+					let center_widget = self.viewport_metrics.world_to_widget_position(center);
+					let radius_widget = self.viewport_metrics.world_to_widget_scalar(radius);
+
+					self.painter.circle_stroke(center_widget, radius_widget, (stroke_thickness, color));
+				}
 			}
 		}
 	}
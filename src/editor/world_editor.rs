@@ -30,6 +30,7 @@ pub fn do_world_editor(ui: &mut egui::Ui, ctx: &mut Context) {
 
 const PLAYER_SPAWN_COLOR: Color = Color::rgb(1.0, 0.3, 0.1);
 const OBJECT_COLOR: Color = Color::rgb(0.3, 0.8, 0.5);
+const TRIGGER_COLOR: Color = Color::rgb(0.8, 0.4, 0.9);
 
 
 fn draw_room_selector(ui: &mut egui::Ui, Context{model, state, ..}: &mut Context) {
@@ -89,6 +90,10 @@ fn draw_focused_room_viewport(ui: &mut egui::Ui, context: &mut Context) -> egui:
 		viewport.add_object(object.placement, Item::Object(object_id), OBJECT_COLOR, ViewportItemFlags::BASIC_INTERACTIONS | settings_flags);
 	}
 
+	for (trigger_id, trigger) in world.triggers.iter() {
+		viewport.add_trigger(trigger, Item::Trigger(trigger_id), TRIGGER_COLOR, ViewportItemFlags::BASIC_INTERACTIONS | settings_flags);
+	}
+
 	viewport.add_player_indicator(world.player_spawn, Item::PlayerSpawn, PLAYER_SPAWN_COLOR, settings_flags);
 	viewport.add_player_indicator(source_player_placement, None, Color::grey(0.8), settings_flags);
 
@@ -130,6 +135,10 @@ fn draw_all_room_viewport(ui: &mut egui::Ui, context: &mut Context) -> egui::Res
 		viewport.add_object(object.placement, Item::Object(object_id), OBJECT_COLOR, ViewportItemFlags::BASIC_INTERACTIONS);
 	}
 
+	for (trigger_id, trigger) in world.triggers.iter() {
+		viewport.add_trigger(trigger, Item::Trigger(trigger_id), TRIGGER_COLOR, ViewportItemFlags::BASIC_INTERACTIONS);
+	}
+
 	viewport.add_player_indicator(world.player_spawn, Item::PlayerSpawn, PLAYER_SPAWN_COLOR, ViewportItemFlags::empty());
 	viewport.add_player_indicator(source_player_placement, None, Color::grey(0.8), ViewportItemFlags::empty());
 
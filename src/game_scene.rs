@@ -1,5 +1,30 @@
 use crate::prelude::*;
 
+/// How long it takes to fade out of one world, and then into the next.
+const WORLD_TRANSITION_FADE_TIME: f32 = 0.5;
+
+enum WorldTransition {
+	FadingOut {
+		world: model::World,
+		target_object: Option<String>,
+		time: f32,
+	},
+
+	FadingIn {
+		time: f32,
+	},
+}
+
+impl WorldTransition {
+	/// 0.0 when the world is fully visible, 1.0 when fully faded out.
+	fn fade_amount(&self) -> f32 {
+		match *self {
+			WorldTransition::FadingOut{time, ..} => (time / WORLD_TRANSITION_FADE_TIME).min(1.0),
+			WorldTransition::FadingIn{time} => 1.0 - (time / WORLD_TRANSITION_FADE_TIME).min(1.0),
+		}
+	}
+}
+
 pub struct GameScene {
 	fog_shader: gfx::ShaderHandle,
 	hdr_to_ldr_shader: gfx::ShaderHandle,
@@ -30,8 +55,22 @@ pub struct GameScene {
 	time: f32,
 	height_offset: f32,
 
+	tick_accumulator: f32,
+	pending_input: model::TickInput,
+	player_event_sub: Subscription<model::PlayerEvent>,
+	sound_event_sub: Subscription<model::SoundEvent>,
+
+	recorder: Option<model::ReplayRecorder>,
+	playback: Option<model::ReplayPlayback>,
+
+	// Replays only cover a single world, so recording stops when the world changes.
+	// Kept until 'record-stop' so that it can still be saved.
+	interrupted_recording: Option<model::Replay>,
+
 	editor_state: editor::State,
 	force_game_controls: bool,
+
+	world_transition: Option<WorldTransition>,
 }
 
 impl GameScene {
@@ -59,6 +98,8 @@ impl GameScene {
 			upsample_chain.push(image);
 		}
 
+		let model = model::Model::new(&world, rand::random(), ctx.bus);
+
 		// let toy_renderer = {
 		// 	let project_path = resource_manager.resource_path("toys/basic.toy")?;
 		// 	let project_data = std::fs::read(&project_path)?;
@@ -74,8 +115,6 @@ impl GameScene {
 		// 	toy_renderer
 		// };
 
-		let processed_world = model::ProcessedWorld::new(&world, &ctx.bus);
-
 		Ok(GameScene {
 			fog_shader: resource_manager.load_compute_shader("shaders/fog.cs.glsl"),
 			hdr_to_ldr_shader: resource_manager.load_compute_shader("shaders/hdr_to_ldr.cs.glsl"),
@@ -95,55 +134,165 @@ impl GameScene {
 
 			// toy_renderer,
 			// sprites: Sprites::new(&mut ctx.gfx)?,
-			world_view: view::WorldView::new(&mut ctx.gfx, &processed_world, ctx.bus.clone())?,
+			world_view: view::WorldView::new(&mut ctx.gfx, &model.processed_world, ctx.bus.clone())?,
 			hud_view: view::HudView::new(&mut ctx.gfx, ctx.bus.clone())?,
 			debug_painter: ui::UiPainter::new(&mut ctx.gfx, gfx::FrameStage::Ui(10)),
 
-			model: model::Model {
-				player: model::Player {
-					placement: processed_world.to_processed_placement(world.player_spawn),
-					pitch: 0.0,
-
-					step_accumulator: 0.0,
-
-					blood: 100,
-					salt: 100,
-
-					free_pos: Vec3::zero(),
-					free_cam: false,
-
-					hack_height_change: None,
-				},
-
-				interactions: model::Interactions::new(ctx.bus),
-				environment: model::EnvironmentModel::new(&processed_world, ctx.bus),
-				hud: model::HudModel::new(ctx.bus),
-				processed_world,
-
-				progress: model::ProgressModel::default(),
-			},
+			model,
 
 			source_model: model::SourceModel {
 				world,
+				items: model::ItemDefs::load(ctx.vfs),
+				dialogues: model::DialogueDefs::load(ctx.vfs),
 			},
 
 			time: 0.0,
 			height_offset: 0.0,
 
+			tick_accumulator: 0.0,
+			pending_input: model::TickInput::default(),
+			player_event_sub: ctx.bus.subscribe(),
+			sound_event_sub: ctx.bus.subscribe(),
+
+			recorder: None,
+			playback: None,
+			interrupted_recording: None,
+
 			editor_state: editor::State::new(ctx.bus),
 			force_game_controls: false,
+
+			world_transition: None,
 		})
 	}
 
-	pub fn switch_world(&mut self, ctx: &mut Context<'_>, new_world: model::World) {
-		// TODO(pat.m): player placement has to be resolved against the processed world - but that doesn't exist yet.
-		self.model.player.placement = new_world.player_spawn;
+	/// Moves the player into `new_world`, arriving at `target_object` if given or the world spawn otherwise.
+	/// Inventory and progress are carried over.
+	pub fn switch_world(&mut self, ctx: &mut Context<'_>, new_world: model::World, target_object: Option<&str>) {
+		// TODO(pat.m): record world changes in replays, so recordings can carry on through them
+		if let Some(recorder) = self.recorder.take() {
+			log::warn!("Recording stopped by change to world '{}' - use 'record-stop' to save what was recorded", new_world.name);
+			self.interrupted_recording = Some(recorder.finish(self.model.player.placement));
+		}
+
+		if self.playback.take().is_some() {
+			log::warn!("Replay playback stopped by change to world '{}'", new_world.name);
+		}
+
+		self.model.change_world(&new_world, target_object, ctx.bus);
 		self.source_model.world = new_world;
 		ctx.bus.emit(model::WorldChangedEvent);
 
+		self.height_offset = 0.0;
+
+		self.editor_state.reset();
+	}
+
+	/// Fades out, switches to `world` and then fades back in.
+	pub fn begin_world_transition(&mut self, world: model::World, target_object: Option<String>) {
+		if self.world_transition.is_some() {
+			log::warn!("Ignoring transition to '{}' - already transitioning", world.name);
+			return
+		}
+
+		self.world_transition = Some(WorldTransition::FadingOut { world, target_object, time: 0.0 });
+	}
+
+	fn update_world_transition(&mut self, ctx: &mut Context<'_>) {
+		let Some(transition) = self.world_transition.take() else { return };
+
+		self.world_transition = match transition {
+			WorldTransition::FadingOut{world, target_object, time} => {
+				let time = time + ctx.delta_time;
+				if time >= WORLD_TRANSITION_FADE_TIME {
+					self.switch_world(ctx, world, target_object.as_deref());
+					Some(WorldTransition::FadingIn { time: 0.0 })
+				} else {
+					Some(WorldTransition::FadingOut { world, target_object, time })
+				}
+			}
+
+			WorldTransition::FadingIn{time} => {
+				let time = time + ctx.delta_time;
+				(time < WORLD_TRANSITION_FADE_TIME).then_some(WorldTransition::FadingIn { time })
+			}
+		};
+
+		let fade_amount = self.world_transition.as_ref().map_or(0.0, WorldTransition::fade_amount);
+		ctx.audio.set_fade(1.0 - fade_amount);
+	}
+
+	/// Starts a new campaign in `world`, forgetting everything from the current one.
+	pub fn start_new_game(&mut self, ctx: &mut Context<'_>, world: model::World) {
+		self.restart_simulation(ctx, world, rand::random());
+		self.world_transition = None;
+
+		self.recorder = None;
+		self.playback = None;
+	}
+
+	/// Throws away all simulation state and starts again from the world spawn.
+	fn restart_simulation(&mut self, ctx: &mut Context<'_>, world: model::World, seed: u64) {
+		self.source_model.world = world;
+		self.model = model::Model::new(&self.source_model.world, seed, ctx.bus);
+		ctx.bus.emit(model::WorldChangedEvent);
+
+		self.tick_accumulator = 0.0;
+		self.pending_input = model::TickInput::default();
+		self.height_offset = 0.0;
+
 		self.editor_state.reset();
 	}
 
+	pub fn load_save(&mut self, ctx: &mut Context<'_>, world: model::World, save: &model::SaveGame) {
+		self.restart_simulation(ctx, world, rand::random());
+		save.apply_to_model(&mut self.model);
+
+		self.recorder = None;
+		self.playback = None;
+	}
+
+	pub fn world_name(&self) -> &str {
+		&self.source_model.world.name
+	}
+
+	pub fn save_game(&self, vfs: &vfs::Vfs, slot: &str) -> anyhow::Result<()> {
+		model::SaveGame::from_model(&self.source_model, &self.model)
+			.save(vfs, slot)
+	}
+
+	pub fn start_replay(&mut self, ctx: &mut Context<'_>, replay: model::Replay) {
+		self.restart_simulation(ctx, replay.world.clone(), replay.seed);
+		self.source_model = replay.source_model();
+
+		self.recorder = None;
+		self.playback = Some(model::ReplayPlayback::new(replay));
+	}
+
+	/// Restarts the current world from its spawn, so that the replay can be played back from a known state.
+	pub fn start_recording(&mut self, ctx: &mut Context<'_>) {
+		let world = self.source_model.world.clone();
+		let seed = rand::random();
+
+		if self.recorder.is_some() {
+			log::warn!("Discarding recording in progress");
+		}
+
+		log::info!("Restarting '{}' from spawn for recording - progress in this session is lost", world.name);
+		self.restart_simulation(ctx, world, seed);
+
+		self.playback = None;
+		self.interrupted_recording = None;
+		self.recorder = Some(model::ReplayRecorder::new(&self.source_model, seed));
+	}
+
+	/// Finishes the current recording, or returns the one cut short by a world change.
+	pub fn stop_recording(&mut self) -> Option<model::Replay> {
+		match self.recorder.take() {
+			Some(recorder) => Some(recorder.finish(self.model.player.placement)),
+			None => self.interrupted_recording.take(),
+		}
+	}
+
 	pub fn update(&mut self, ctx: &mut Context<'_>) {
 		if ctx.input.button_just_down(input::keys::F2) {
 			self.force_game_controls = !self.force_game_controls;
@@ -160,42 +309,66 @@ impl GameScene {
 			log::error!("{err:?}");
 		}
 
-		let model::Model { processed_world, player, progress, interactions, environment, hud, .. } = &mut self.model;
-		let source_world = &self.source_model.world;
+		self.update_world_transition(ctx);
 
-		processed_world.update(source_world, &progress, ctx.bus);
+		let gameplay_input_enabled = (!ctx.show_editor && !ctx.console.is_visible() || self.force_game_controls)
+			&& self.world_transition.is_none();
+		if gameplay_input_enabled {
+			self.pending_input.accumulate(ctx.actions);
+		} else {
+			self.pending_input = model::TickInput::default();
+		}
 
-		// TODO(pat.m): needs to happen somewhere else, but has to happen after processed world update
-		{
-			// Make sure player doesn't suddenly end up in a room that no longer exists.
-			if !player.placement.room_id.is_valid(processed_world.geometry()) {
-				player.placement = processed_world.to_processed_placement(source_world.player_spawn);
+		self.tick_accumulator = (self.tick_accumulator + ctx.delta_time).min(model::SIM_DT * model::MAX_TICKS_PER_FRAME as f32);
+
+		while self.tick_accumulator >= model::SIM_DT {
+			self.tick_accumulator -= model::SIM_DT;
+
+			let input = match &mut self.playback {
+				Some(playback) => playback.next_input().unwrap_or_default(),
+				None => self.pending_input,
+			};
+
+			self.model.step(&self.source_model, &input, ctx.bus);
+			self.pending_input.consume_transient();
+
+			if let Some(recorder) = &mut self.recorder {
+				recorder.record(&input);
+			}
+
+			if let Some(playback) = &self.playback
+				&& playback.is_finished()
+			{
+				playback.verify(self.model.player.placement);
+				self.playback = None;
 			}
-		}
 
-		if !ctx.show_editor && !ctx.console.is_visible() || self.force_game_controls {
-			player.handle_input(ctx, &processed_world, &hud);
-			interactions.update(&player, &processed_world, ctx.bus);
+			// TODO(pat.m): figure out another way to do this
+			if let Some(height_change) = self.model.player.hack_height_change {
+				self.height_offset += height_change;
+			}
 		}
 
-		hud.update(ctx.bus);
+		if ctx.bus.any(&self.player_event_sub) {
+			ctx.audio.trigger();
+		}
 
-		environment.update(processed_world, ctx.bus);
+		for model::SoundEvent::Play(sound) in ctx.bus.poll(&self.sound_event_sub) {
+			// TODO(pat.m): play the actual sound once sfx can load samples
+			log::info!("Play sound '{sound}'");
+			ctx.audio.trigger();
+		}
 
 		// self.sprites.set_billboard_orientation(Vec3::from_y(1.0), Vec3::from_y_angle(player.placement.yaw));
 	}
 
 	pub fn draw(&mut self, ctx: &mut Context<'_>) {
-		let Context{gfx, ui_shared, delta_time, ..} = ctx;
+		let Context{gfx, ui_shared, input_bindings, delta_time, ..} = ctx;
 
 		self.time += *delta_time;
 
 		let player = &self.model.player;
 
-		if let Some(height_change) = player.hack_height_change {
-			self.height_offset += height_change;
-		}
-
 		if self.height_offset.abs() > 0.02 {
 			self.height_offset -= self.height_offset.signum() * (self.height_offset.abs()/5.0).max(0.02);
 		} else {
@@ -214,15 +387,15 @@ impl GameScene {
 
 		let inverse_projection = projection.inverse();
 
-		gfx.frame_encoder.backbuffer_color(self.model.processed_world.fog.color);
+		gfx.frame_encoder.backbuffer_color(self.model.environment.fog.color);
 		gfx.frame_encoder.bind_global_ubo(0, &[projection_view, inverse_projection]);
 		gfx.frame_encoder.bind_global_sampled_image(0, gfx::BlankImage::White, gfx::CommonSampler::Nearest);
 
 		let mut main_group = gfx.frame_encoder.command_group(gfx::FrameStage::Main);
 		main_group.bind_rendertargets(&[self.hdr_color_rt, self.depth_rt]);
 
-		self.world_view.draw(gfx, &self.model.processed_world, player.placement);
-		self.hud_view.draw(gfx, ui_shared, &self.model);
+		self.world_view.draw(gfx, &self.model.processed_world, &self.model.lights, &self.model.worms, &self.model.progress.state.markers, player.placement);
+		self.hud_view.draw(gfx, ui_shared, input_bindings, &self.source_model, &self.model);
 
 		{
 			let screen_size = gfx.backbuffer_size().to_vec2();
@@ -235,6 +408,10 @@ impl GameScene {
 			let top_left = screen_bounds.shrink(16.0).min_max_corner();
 			painter.text(top_left - Vec2::from_y(16.0), 16, format!("dt: {:.2}ms ({fps:.0}fps)", *delta_time * 1000.0), Color::white());
 
+			if let Some(transition) = &self.world_transition {
+				painter.rect(screen_bounds, Color::black().with_alpha(transition.fade_amount()));
+			}
+
 			self.debug_painter.submit(gfx, ui_shared, screen_bounds);
 		}
 
@@ -416,7 +593,40 @@ impl GameScene {
 			log::info!("World '{world_name}' saved successfully");
 		}
 
+		if let Some(slot) = ctx.console.command("savegame") {
+			let slot = match slot.is_empty() {
+				true => model::AUTOSAVE_SLOT.to_string(),
+				false => slot,
+			};
+
+			self.save_game(ctx.vfs, &slot)?;
+			log::info!("Game saved to slot '{slot}'");
+		}
+
+		if ctx.console.command("record").is_some() {
+			self.start_recording(ctx);
+			log::info!("Recording replay");
+		}
+
+		if let Some(replay_name) = ctx.console.command("record-stop") {
+			let replay_name = match replay_name.is_empty() {
+				true => "latest".to_string(),
+				false => replay_name,
+			};
+
+			let Some(replay) = self.stop_recording() else {
+				anyhow::bail!("'record-stop' called while not recording");
+			};
+
+			replay.save(ctx.vfs, &replay_name)?;
+
+			log::info!("Replay '{replay_name}' saved successfully");
+		}
+
 		model::handle_hud_commands(ctx, &self.model)?;
+		model::handle_progress_commands(ctx, &self.model)?;
+		model::handle_inventory_commands(ctx, &self.model)?;
+		model::handle_worm_commands(ctx, &self.model)?;
 
 		Ok(())
 	}
@@ -1,5 +1,6 @@
 pub mod aabb2_ext;
 
+pub mod actions;
 pub mod audio;
 pub mod ui;
 pub mod sprites;
@@ -20,9 +21,11 @@ pub mod prelude {
 
 	pub use crate::aabb2_ext::*;
 
+	pub use crate::actions::{self, Action};
+
 	pub use crate::audio::MyAudioSystem;
 	pub use crate::game_scene::GameScene;
-	pub use crate::main_menu::{MainMenuScene, MenuCmd, PauseMenuScene};
+	pub use crate::main_menu::{MainMenuScene, MenuCmd, PauseMenuScene, SettingsMenuScene};
 	pub use crate::sprites::Sprites;
 	pub use crate::toy_draw::ToyRenderer;
 	pub use crate::ui::{self, UiLayout};
@@ -59,8 +62,10 @@ fn main() -> anyhow::Result<()> {
 
 
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ActiveScene {
 	MainMenu,
+	Settings,
 
 	Game,
 	PauseMenu,
@@ -71,9 +76,17 @@ struct App {
 
 	main_menu: MainMenuScene,
 	pause_menu: PauseMenuScene,
+	settings_menu: SettingsMenuScene,
 	game_scene: Option<GameScene>,
 
+	// Scene to return to when leaving settings.
+	settings_return_scene: ActiveScene,
+
 	menu_cmd_subscription: Subscription<MenuCmd>,
+	world_transition_subscription: Subscription<model::WorldTransitionEvent>,
+	objective_event_subscription: Subscription<model::ObjectiveEvent>,
+
+	campaign_worlds: model::CampaignWorlds,
 
 	shared: AppShared,
 	frame_start: Instant,
@@ -86,12 +99,19 @@ struct AppShared {
 
 	audio: MyAudioSystem,
 	delta_time: f32,
+
+	input_bindings: actions::InputBindings,
+	gamepad: actions::GamepadInput,
+	actions: actions::ActionState,
 }
 
 
 impl App {
 	fn new(ctx: &mut toybox::Context) -> anyhow::Result<App> {
 		let menu_cmd_subscription = ctx.bus.subscribe();
+		let world_transition_subscription = ctx.bus.subscribe();
+		let objective_event_subscription = ctx.bus.subscribe();
+		let campaign_worlds = model::CampaignWorlds::load(&ctx.vfs);
 
 		let audio = MyAudioSystem::start(&mut ctx.audio)?;
 		let ui_shared = ui::UiShared::new(&mut ctx.gfx)?;
@@ -99,11 +119,17 @@ impl App {
 
 		register_commands(&mut console);
 
+		let input_bindings = actions::InputBindings::load(&ctx.cfg, &ctx.vfs);
+
 		let mut shared = AppShared {
 			console,
 			ui_shared,
 			audio,
 			delta_time: 1.0/60.0,
+
+			input_bindings,
+			gamepad: actions::GamepadInput::new(&ctx.cfg),
+			actions: actions::ActionState::new(),
 		};
 
 		let ctx = &mut Context::new(ctx, &mut shared);
@@ -111,7 +137,16 @@ impl App {
 		let mut active_scene = ActiveScene::MainMenu;
 		let mut game_scene = None;
 
-		if let Some(world_name) = ctx.cfg.get_string("start.load-world") {
+		if let Some(replay_name) = ctx.cfg.get_string("start.replay") {
+			let replay = model::Replay::load(&ctx.vfs, replay_name)?;
+
+			let mut scene = GameScene::new(ctx, replay.world.clone())?;
+			scene.start_replay(ctx, replay);
+
+			active_scene = ActiveScene::Game;
+			game_scene = Some(scene);
+
+		} else if let Some(world_name) = ctx.cfg.get_string("start.load-world") {
 			active_scene = ActiveScene::Game;
 			let world = Self::load_world_or_default(&ctx.vfs, world_name);
 			game_scene = Some(GameScene::new(ctx, world)?);
@@ -121,20 +156,35 @@ impl App {
 			active_scene,
 			main_menu: MainMenuScene::new(ctx)?,
 			pause_menu: PauseMenuScene::new(ctx)?,
+			settings_menu: SettingsMenuScene::new(ctx)?,
 			game_scene,
 
+			settings_return_scene: ActiveScene::MainMenu,
+
 			menu_cmd_subscription,
+			world_transition_subscription,
+			objective_event_subscription,
+
+			campaign_worlds,
 
 			shared,
 			frame_start: Instant::now(),
 		})
 	}
 
+	fn load_world(vfs: &vfs::Vfs, world_name: &str) -> anyhow::Result<model::World> {
+		let path = format!("worlds/{world_name}.world");
+		let mut world: model::World = vfs.load_json_resource(path)?;
+
+		// Saves and ladders refer to worlds by the name they were loaded with.
+		world.name = world_name.to_string();
+		Ok(world)
+	}
+
 	fn load_world_or_default(vfs: &vfs::Vfs, world_name: impl AsRef<str>) -> model::World {
 		let world_name = world_name.as_ref();
-		let path = format!("worlds/{world_name}.world");
 
-		match vfs.load_json_resource(path) {
+		match Self::load_world(vfs, world_name) {
 			Ok(world) => world,
 			Err(err) => {
 				log::error!("Failed to load world '{world_name}', creating empty world. {err}");
@@ -153,6 +203,8 @@ impl toybox::App for App {
 		self.shared.delta_time = delta.as_secs_f32();
 
 		self.shared.console.update(ctx);
+		self.shared.gamepad.update();
+		self.shared.actions.update(&ctx.input, &self.shared.gamepad, &self.shared.input_bindings);
 
 		if let ActiveScene::Game | ActiveScene::PauseMenu = self.active_scene
 			&& self.game_scene.is_none()
@@ -166,10 +218,19 @@ impl toybox::App for App {
 				self.main_menu.update(&mut Context::new(ctx, &mut self.shared));
 			}
 
+			ActiveScene::Settings => {
+				let mut ctx = Context::new(ctx, &mut self.shared);
+				self.settings_menu.update(&mut ctx);
+
+				if let Some(game_scene) = &mut self.game_scene {
+					game_scene.draw(&mut ctx);
+				}
+			}
+
 			ActiveScene::Game => {
 				let game_scene = self.game_scene.as_mut().unwrap();
 
-				if ctx.input.button_just_down(input::keys::Escape) {
+				if self.shared.actions.just_down(Action::Pause) {
 					self.active_scene = ActiveScene::PauseMenu;
 				}
 
@@ -191,11 +252,16 @@ impl toybox::App for App {
 			match menu_msg {
 				MenuCmd::Play(world_name) => {
 					let world = Self::load_world_or_default(&ctx.vfs, world_name);
+
+					if let Err(err) = model::validate_ladder_targets(&world, |world_name| Self::load_world(&ctx.vfs, world_name)) {
+						log::warn!("{err:?}");
+					}
+
 					let ctx = &mut Context::new(ctx, &mut self.shared);
 
 					// Reuse scene if we can, to avoid reloading common stuff
 					if let Some(game_scene) = &mut self.game_scene {
-						game_scene.switch_world(ctx, world)
+						game_scene.start_new_game(ctx, world)
 					} else {
 						self.game_scene = Some(GameScene::new(ctx, world).expect("Failed to initialise GameScene"));
 					}
@@ -209,7 +275,7 @@ impl toybox::App for App {
 
 					// Reuse scene if we can, to avoid reloading common stuff
 					if let Some(game_scene) = &mut self.game_scene {
-						game_scene.switch_world(ctx, world)
+						game_scene.switch_world(ctx, world, None)
 					} else {
 						self.game_scene = Some(GameScene::new(ctx, world).expect("Failed to initialise GameScene"));
 					}
@@ -217,6 +283,45 @@ impl toybox::App for App {
 					self.active_scene = ActiveScene::Game;
 				}
 
+				MenuCmd::PlayReplay(replay_name) => {
+					let replay = match model::Replay::load(&ctx.vfs, &replay_name) {
+						Ok(replay) => replay,
+						Err(err) => {
+							log::error!("{err:?}");
+							continue
+						}
+					};
+
+					let ctx = &mut Context::new(ctx, &mut self.shared);
+
+					if self.game_scene.is_none() {
+						self.game_scene = Some(GameScene::new(ctx, replay.world.clone()).expect("Failed to initialise GameScene"));
+					}
+
+					self.game_scene.as_mut().unwrap().start_replay(ctx, replay);
+					self.active_scene = ActiveScene::Game;
+				}
+
+				MenuCmd::LoadGame(slot) => {
+					let save = match model::SaveGame::load(&ctx.vfs, &slot) {
+						Ok(save) => save,
+						Err(err) => {
+							log::error!("{err:?}");
+							continue
+						}
+					};
+
+					let world = Self::load_world_or_default(&ctx.vfs, &save.world_name);
+					let ctx = &mut Context::new(ctx, &mut self.shared);
+
+					if self.game_scene.is_none() {
+						self.game_scene = Some(GameScene::new(ctx, world.clone()).expect("Failed to initialise GameScene"));
+					}
+
+					self.game_scene.as_mut().unwrap().load_save(ctx, world, &save);
+					self.active_scene = ActiveScene::Game;
+				}
+
 				MenuCmd::Resume => {
 					if self.game_scene.is_some() {
 						self.active_scene = ActiveScene::Game;
@@ -224,7 +329,14 @@ impl toybox::App for App {
 				}
 
 				MenuCmd::QuitToMain => {
-					// TODO(pat.m): confirmation/save
+					// TODO(pat.m): confirmation
+					if let Some(game_scene) = &self.game_scene {
+						match game_scene.save_game(&ctx.vfs, model::AUTOSAVE_SLOT) {
+							Ok(()) => self.main_menu.has_continue = true,
+							Err(err) => log::error!("{err:?}"),
+						}
+					}
+
 					self.game_scene = None;
 					self.active_scene = ActiveScene::MainMenu;
 				}
@@ -234,7 +346,36 @@ impl toybox::App for App {
 					ctx.wants_quit = true;
 				}
 
-				MenuCmd::Settings => {}
+				MenuCmd::Settings => {
+					self.settings_return_scene = self.active_scene;
+					self.active_scene = ActiveScene::Settings;
+				}
+
+				MenuCmd::CloseSettings => {
+					self.active_scene = self.settings_return_scene;
+				}
+			}
+		}
+
+		for transition in ctx.bus.poll_consume(&self.world_transition_subscription) {
+			let Some(game_scene) = &mut self.game_scene else { continue };
+
+			let world = Self::load_world_or_default(&ctx.vfs, &transition.world_name);
+			game_scene.begin_world_transition(world, Some(transition.object_name));
+		}
+
+		for event in ctx.bus.poll(&self.objective_event_subscription) {
+			let model::ObjectiveEvent::WorldCompleted = event else { continue };
+			let Some(game_scene) = &mut self.game_scene else { continue };
+
+			// Move on to the next world, arriving at its spawn
+			match self.campaign_worlds.next_world(game_scene.world_name()) {
+				Some(next_world) => {
+					let world = Self::load_world_or_default(&ctx.vfs, next_world);
+					game_scene.begin_world_transition(world, None);
+				}
+
+				None => log::info!("'{}' has no world after it in the campaign", game_scene.world_name()),
 			}
 		}
 
@@ -266,6 +407,9 @@ pub struct Context<'tb> {
 	pub audio: &'tb MyAudioSystem,
 	pub console: &'tb mut Console,
 	pub ui_shared: &'tb mut ui::UiShared,
+	pub actions: &'tb actions::ActionState,
+	pub input_bindings: &'tb mut actions::InputBindings,
+	pub gamepad: &'tb actions::GamepadInput,
 
 	pub delta_time: f32,
 	pub show_editor: bool,
@@ -274,11 +418,11 @@ pub struct Context<'tb> {
 impl<'tb> Context<'tb> {
 	fn new(tb: &'tb mut toybox::Context, shared: &'tb mut AppShared) -> Self {
 		let toybox::Context { gfx, input, egui, cfg, vfs, bus, show_debug_menu, .. } = tb;
-		let AppShared { audio, console, ui_shared, delta_time } = shared;
+		let AppShared { audio, console, ui_shared, delta_time, actions, input_bindings, gamepad } = shared;
 		let show_editor = *show_debug_menu;
 		let delta_time = *delta_time;
 
-		Self {gfx, input, egui, cfg, vfs, bus, audio, ui_shared, console, delta_time, show_editor}
+		Self {gfx, input, egui, cfg, vfs, bus, audio, ui_shared, console, actions, input_bindings, gamepad, delta_time, show_editor}
 	}
 }
 
@@ -296,4 +440,21 @@ fn register_commands(console: &mut Console) {
 	});
 
 	console.register_command("gen", |ctx, _| ctx.bus.emit(MenuCmd::PlayGeneratedWorld));
+	console.register_command("replay", |ctx, replay_name| {
+		if replay_name.is_empty() {
+			log::error!("'replay' requires replay name argument");
+			return;
+		}
+
+		ctx.bus.emit(MenuCmd::PlayReplay(replay_name.into()));
+	});
+
+	console.register_command("loadgame", |ctx, slot| {
+		let slot = match slot.is_empty() {
+			true => model::AUTOSAVE_SLOT,
+			false => slot,
+		};
+
+		ctx.bus.emit(MenuCmd::LoadGame(slot.into()));
+	});
 }
\ No newline at end of file
@@ -6,12 +6,18 @@ pub const MAIN_MENU_FRAME_STAGE: gfx::FrameStage = gfx::FrameStage::Ui(10);
 
 pub struct MainMenuScene {
 	painter: ui::UiPainter,
+	focus: ui::MenuFocus,
+
+	pub has_continue: bool,
 }
 
 impl MainMenuScene {
 	pub fn new(ctx: &mut Context<'_>) -> anyhow::Result<MainMenuScene> {
 		Ok(MainMenuScene{
 			painter: ui::UiPainter::new(&mut ctx.gfx, MAIN_MENU_FRAME_STAGE),
+			focus: ui::MenuFocus::default(),
+
+			has_continue: model::SaveGame::exists(ctx.vfs, model::AUTOSAVE_SLOT),
 		})
 	}
 
@@ -20,6 +26,8 @@ impl MainMenuScene {
 
 		ctx.input.set_capture_mouse(false);
 
+		self.focus.update(ctx.actions);
+
 		let scale_factor = 0.5;
 
 		let size = ctx.gfx.backbuffer_size().to_vec2() * scale_factor;
@@ -38,14 +46,23 @@ impl MainMenuScene {
 		let mut builder = self.painter.builder(ctx, ui::DumbLayout::new(content_rect.shrink(8.0)));
 		builder.font_size = 16;
 		builder.input_scale_factor = scale_factor;
+		builder.focused_item = self.focus.focused_item;
 
-		if builder.button("Play") || ctx.input.button_just_down(input::keys::Space) {
+		// Confirm with nothing focused is a shortcut for Play
+		let play_shortcut = self.focus.focused_item.is_none() && ctx.actions.just_down(Action::MenuConfirm);
+
+		if self.has_continue && builder.button("Continue") {
+			ctx.audio.trigger();
+			ctx.bus.emit(MenuCmd::LoadGame(model::AUTOSAVE_SLOT.into()));
+		}
+
+		if builder.button("Play") || play_shortcut {
 			ctx.audio.trigger();
 			ctx.bus.emit(MenuCmd::Play("default".into()));
 		}
 
 		if builder.button("Settings") {
-			// ctx.bus.emit(MenuCmd::Settings);
+			ctx.bus.emit(MenuCmd::Settings);
 		}
 
 		{
@@ -63,6 +80,8 @@ impl MainMenuScene {
 			ctx.bus.emit(MenuCmd::QuitToDesktop);
 		}
 
+		self.focus.item_count = builder.item_count();
+
 		self.painter.submit(&mut ctx.gfx, ctx.ui_shared, screen_rect);
 	}
 }
@@ -71,8 +90,11 @@ impl MainMenuScene {
 pub enum MenuCmd {
 	Play(String),
 	PlayGeneratedWorld,
+	PlayReplay(String),
+	LoadGame(String),
 	Resume,
 	Settings,
+	CloseSettings,
 	QuitToMain,
 	QuitToDesktop,
 }
@@ -83,18 +105,22 @@ pub enum MenuCmd {
 
 pub struct PauseMenuScene {
 	painter: ui::UiPainter,
+	focus: ui::MenuFocus,
 }
 
 impl PauseMenuScene {
 	pub fn new(ctx: &mut Context<'_>) -> anyhow::Result<PauseMenuScene> {
 		Ok(PauseMenuScene{
 			painter: ui::UiPainter::new(&mut ctx.gfx, MAIN_MENU_FRAME_STAGE),
+			focus: ui::MenuFocus::default(),
 		})
 	}
 
 	pub fn update(&mut self, ctx: &mut Context<'_>) {
 		ctx.input.set_capture_mouse(false);
 
+		self.focus.update(ctx.actions);
+
 		let scale_factor = 0.5;
 
 		let size = ctx.gfx.backbuffer_size().to_vec2() * scale_factor;
@@ -113,11 +139,16 @@ impl PauseMenuScene {
 
 		let mut builder = self.painter.builder(ctx, ui::DumbLayout::new(content_rect.shrink(8.0)));
 		builder.input_scale_factor = scale_factor;
+		builder.focused_item = self.focus.focused_item;
 
-		if builder.button("Resume") || ctx.input.button_just_down(input::keys::Escape) {
+		if builder.button("Resume") || ctx.actions.just_down(Action::Pause) {
 			ctx.bus.emit(MenuCmd::Resume);
 		}
 
+		if builder.button("Settings") {
+			ctx.bus.emit(MenuCmd::Settings);
+		}
+
 		if builder.button("Quit To Menu") {
 			ctx.bus.emit(MenuCmd::QuitToMain);
 		}
@@ -126,6 +157,239 @@ impl PauseMenuScene {
 			ctx.bus.emit(MenuCmd::QuitToDesktop);
 		}
 
+		self.focus.item_count = builder.item_count();
+
+		self.painter.submit(&mut ctx.gfx, ctx.ui_shared, screen_rect);
+	}
+}
+
+
+
+
+pub struct SettingsMenuScene {
+	painter: ui::UiPainter,
+	focus: ui::MenuFocus,
+
+	// Action and binding slot currently waiting for a key press.
+	rebinding: Option<(Action, usize)>,
+	has_unsaved_changes: bool,
+
+	// Only a page of actions fits on screen at once.
+	page: usize,
+}
+
+const BINDING_SLOTS_PER_ACTION: usize = 3;
+
+// Any more than this are summarised, so they don't push the binding list off screen.
+const MAX_CONFLICTS_SHOWN: usize = 3;
+
+impl SettingsMenuScene {
+	pub fn new(ctx: &mut Context<'_>) -> anyhow::Result<SettingsMenuScene> {
+		Ok(SettingsMenuScene{
+			painter: ui::UiPainter::new(&mut ctx.gfx, MAIN_MENU_FRAME_STAGE),
+			focus: ui::MenuFocus::default(),
+			rebinding: None,
+			has_unsaved_changes: false,
+			page: 0,
+		})
+	}
+
+	pub fn update(&mut self, ctx: &mut Context<'_>) {
+		ctx.input.set_capture_mouse(false);
+
+		self.handle_rebinding(ctx);
+
+		// Menu navigation buttons may be what's being bound
+		if self.rebinding.is_none() {
+			self.focus.update(ctx.actions);
+		}
+
+		let scale_factor = 0.5;
+
+		let size = ctx.gfx.backbuffer_size().to_vec2() * scale_factor;
+		let screen_rect = Aabb2::new(Vec2::zero(), size);
+		let mut content_rect = screen_rect.shrink(8.0); // pad edge
+
+		// Cap size to 400px x 520px
+		{
+			let Vec2{x, y} = content_rect.size() - Vec2::new(400.0, 520.0);
+			content_rect = content_rect.shrink(Vec2::new(x.max(0.0)/2.0, y.max(0.0)/2.0));
+		}
+
+		content_rect = content_rect.floor();
+
+		self.painter.rect(content_rect, Color::grey_a(0.0, 0.8));
+
+		let mut start_rebinding = None;
+		let save_requested;
+		let reset_requested;
+
+		let conflicts = ctx.input_bindings.conflicts();
+
+		{
+			let mut layout = ui::DumbLayout::new(content_rect.shrink(8.0));
+			layout.item_spacing = 2.0;
+
+			let mut builder = self.painter.builder(ctx, layout);
+			builder.input_scale_factor = scale_factor;
+			builder.focused_item = self.focus.focused_item;
+
+			let row_height = builder.font_size as f32 + 4.0;
+			let row_spacing = builder.layout.item_spacing;
+			let label_width = 120.0;
+
+			// Buttons and messages are pinned to the bottom, and the binding list gets whatever is left.
+			let button_row_rect = builder.layout.available_rect.cut_bottom(row_height);
+			builder.layout.available_rect.cut_bottom(8.0);
+
+			let mut messages = Vec::new();
+			if self.rebinding.is_some() {
+				messages.push(("Press a key - Escape to cancel, Backspace to clear".to_string(), Color::grey(0.7)));
+			}
+
+			for (binding, actions) in conflicts.iter().take(MAX_CONFLICTS_SHOWN) {
+				let action_names = actions.iter()
+					.map(|action| action.label())
+					.collect::<Vec<_>>()
+					.join(", ");
+
+				messages.push((format!("{} is bound to {action_names}", binding.name()), Color::rgb(0.8, 0.2, 0.1)));
+			}
+
+			if conflicts.len() > MAX_CONFLICTS_SHOWN {
+				messages.push((format!("...and {} more conflicts", conflicts.len() - MAX_CONFLICTS_SHOWN), Color::rgb(0.8, 0.2, 0.1)));
+			}
+
+			let messages_rect = builder.layout.available_rect.cut_bottom(messages.len() as f32 * (row_height + row_spacing));
+
+			let header_rect = builder.layout.allocate(Vec2::new(f32::INFINITY, row_height));
+			builder.layout.allocate(Vec2::new(f32::INFINITY, 4.0));
+
+			let list_height = builder.layout.available_rect.height();
+			let rows_per_page = (((list_height + row_spacing) / (row_height + row_spacing)).floor() as usize).max(1);
+			let num_pages = Action::ALL.len().div_ceil(rows_per_page);
+			self.page = self.page.min(num_pages - 1);
+
+			let header = match num_pages {
+				1 => "Controls".to_string(),
+				_ => format!("Controls - page {}/{num_pages}", self.page + 1),
+			};
+
+			builder.painter.text(header_rect.min + Vec2::new(8.0, 2.0), builder.font_size, &header, Color::white());
+
+			for &action in Action::ALL.iter().skip(self.page * rows_per_page).take(rows_per_page) {
+				let mut row_rect = builder.layout.allocate(Vec2::new(f32::INFINITY, row_height));
+				let label_rect = row_rect.cut_left(label_width);
+				builder.painter.text(label_rect.min + Vec2::new(8.0, 2.0), builder.font_size, action.label(), Color::grey(0.7));
+
+				let bindings = ctx.input_bindings.bindings_for(action);
+				let mut builder = builder.with_layout(ui::HorizontalLayout::new(row_rect));
+
+				for slot in 0..BINDING_SLOTS_PER_ACTION {
+					let binding_label = match (self.rebinding, bindings.get(slot)) {
+						(Some(rebinding), _) if rebinding == (action, slot) => Cow::from("..."),
+						(_, Some(binding)) => binding.name(),
+						(_, None) => Cow::from("-"),
+					};
+
+					if builder.button(&binding_label) {
+						start_rebinding = Some((action, slot));
+					}
+				}
+			}
+
+			{
+				let mut layout = ui::DumbLayout::new(messages_rect);
+				layout.item_spacing = row_spacing;
+
+				let mut builder = builder.with_layout(layout);
+				for (message, color) in messages.iter() {
+					builder.label(message, *color);
+				}
+			}
+
+			let mut builder = builder.with_layout(ui::HorizontalLayout::new(button_row_rect));
+
+			let save_label = match self.has_unsaved_changes {
+				true => "Save*",
+				false => "Save",
+			};
+
+			save_requested = builder.button(save_label);
+			reset_requested = builder.button("Defaults");
+
+			let mut page_change = 0;
+			if num_pages > 1 {
+				if builder.button("Prev") {
+					page_change = num_pages - 1;
+				}
+
+				if builder.button("Next") {
+					page_change = 1;
+				}
+			}
+
+			if builder.button("Back") {
+				ctx.bus.emit(MenuCmd::CloseSettings);
+			}
+
+			self.focus.item_count = builder.item_count();
+			self.page = (self.page + page_change) % num_pages;
+		}
+
+		if let Some(rebinding) = start_rebinding {
+			self.rebinding = Some(rebinding);
+		}
+
+		if reset_requested {
+			*ctx.input_bindings = actions::InputBindings::defaults();
+			self.has_unsaved_changes = true;
+		}
+
+		if save_requested {
+			match ctx.input_bindings.save(ctx.vfs) {
+				Ok(()) => self.has_unsaved_changes = false,
+				Err(err) => log::error!("{err:?}"),
+			}
+		}
+
 		self.painter.submit(&mut ctx.gfx, ctx.ui_shared, screen_rect);
 	}
-}
\ No newline at end of file
+
+	fn handle_rebinding(&mut self, ctx: &mut Context<'_>) {
+		let Some((action, slot)) = self.rebinding else {
+			if ctx.actions.just_down(Action::Pause) {
+				ctx.bus.emit(MenuCmd::CloseSettings);
+			}
+
+			return
+		};
+
+		let mut bindings: SmallVec<[actions::Binding; 2]> = ctx.input_bindings.bindings_for(action).into();
+
+		if ctx.input.button_just_down(input::keys::Escape) {
+			self.rebinding = None;
+			return
+		}
+
+		if ctx.input.button_just_down(input::keys::Backspace) {
+			if slot < bindings.len() {
+				bindings.remove(slot);
+			}
+		} else if let Some(new_binding) = actions::Binding::any_just_down(&ctx.input, ctx.gamepad) {
+			if slot < bindings.len() {
+				bindings[slot] = new_binding;
+			} else {
+				bindings.push(new_binding);
+			}
+
+			bindings.dedup();
+		} else {
+			return
+		}
+
+		ctx.input_bindings.set_bindings(action, bindings);
+		self.has_unsaved_changes = true;
+		self.rebinding = None;
+	}
+}
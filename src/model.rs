@@ -7,6 +7,25 @@ pub mod progress;
 pub mod interactions;
 pub mod environment;
 pub mod processed_world;
+pub mod simulation;
+pub mod replay;
+pub mod save_game;
+pub mod items;
+pub mod inventory;
+pub mod economy;
+pub mod ritual;
+pub mod dialogue;
+pub mod triggers;
+pub mod effects;
+pub mod campaign;
+pub mod runtime_objects;
+pub mod worm;
+pub mod perception;
+pub mod markers;
+pub mod automap;
+pub mod objectives;
+pub mod lights;
+pub mod travel;
 
 pub use hud::*;
 pub use world::*;
@@ -15,13 +34,43 @@ pub use progress::*;
 pub use interactions::*;
 pub use environment::*;
 pub use processed_world::*;
+pub use simulation::*;
+pub use replay::*;
+pub use save_game::*;
+pub use items::*;
+pub use inventory::*;
+pub use economy::*;
+pub use ritual::*;
+pub use dialogue::*;
+pub use triggers::*;
+pub use effects::*;
+pub use campaign::*;
+pub use runtime_objects::*;
+pub use worm::*;
+pub use perception::*;
+pub use markers::*;
+pub use automap::*;
+pub use objectives::*;
+pub use lights::*;
+pub use travel::*;
 
 /// Runtime Immutable - loaded from resources, edited by editor
 #[derive(Debug)]
 pub struct SourceModel {
 	pub world: World,
-	// TODO(pat.m): item info
-	// TODO(pat.m): any dialogue stuff
+	pub items: ItemDefs,
+	pub dialogues: DialogueDefs,
+}
+
+impl SourceModel {
+	/// Source model with no item or dialogue definitions. Mostly useful for tests.
+	pub fn from_world(world: World) -> SourceModel {
+		SourceModel {
+			world,
+			items: ItemDefs::default(),
+			dialogues: DialogueDefs::default(),
+		}
+	}
 }
 
 #[derive(Debug)]
@@ -35,6 +84,21 @@ pub struct Model {
 	// Keeps track of progression. what doors unlocked, items gathered, etc
 	pub progress: ProgressModel,
 
+	// Progression for worlds other than the current one.
+	pub campaign: Campaign,
+
+	// Items the player is carrying.
+	pub inventory: InventoryModel,
+
+	// Applies changes to blood and salt, hazards and death.
+	pub economy: EconomyModel,
+
+	// Tracks the ritual currently being performed at an altar, if any.
+	pub ritual: RitualModel,
+
+	// Fast travel between attuned altars.
+	pub travel: TravelModel,
+
 
 	// Mutable - runtime state, generated from above on load
 
@@ -50,23 +114,47 @@ pub struct Model {
 	// Derived from ProcessedWorld - interacts with Hud and Player
 	pub interactions: Interactions,
 
+	// Keeps track of which trigger volumes the player is in, and fires their actions.
+	pub triggers: TriggerModel,
+
+	// Things that follow the player around.
+	pub worms: WormModel,
+
+	// Places and rubs out chalk markers. The markers themselves are part of progress.
+	pub markers: MarkerModel,
+
+	// Progress towards the current world's objectives.
+	pub objectives: ObjectiveModel,
+
+	// Animates lights, and fades them as they're lit and snuffed.
+	pub lights: LightModel,
+
+	// Applies effects emitted by interactions, triggers, rituals and dialogue.
+	pub effects: EffectExecutor,
+
 	// Keeps track of state of environmental effects - fog settings, reverb settings, active particle effects etc
 	pub environment: EnvironmentModel,
 
 	// Everything to do with hud ui - active dialog info, stats display, interactability feedback.
 	pub hud: HudModel,
+
+	// Seed used to initialise rng. Recorded in replays so that playback is deterministic.
+	pub seed: u64,
+
+	// All randomness in the simulation must come from here.
+	pub rng: rand::rngs::StdRng,
 }
 
 
 
-#[derive(Debug, Copy, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Location {
 	pub room_id: RoomId,
 	pub position: Vec2,
 }
 
 
-#[derive(Debug, Copy, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Placement {
 	pub room_id: RoomId,
 	pub position: Vec2,
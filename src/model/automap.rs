@@ -0,0 +1,137 @@
+use crate::prelude::*;
+use model::*;
+
+use slotmap::SecondaryMap;
+use std::collections::VecDeque;
+
+
+/// Rooms more than this many connections away from the player aren't shown.
+pub const AUTOMAP_MAX_DEPTH: usize = 8;
+
+
+/// An explored room, placed relative to the room the map was unfolded from.
+#[derive(Debug, Copy, Clone)]
+pub struct AutomapRoom {
+	/// Processed room.
+	pub room_id: RoomId,
+
+	/// Transforms from the room's space to that of the room the map was unfolded from.
+	pub room_to_map: Mat2x3,
+
+	/// Number of connections between this room and the center of the map.
+	pub depth: usize,
+
+	/// Whether the room ends up on top of a closer room - the world isn't euclidean, so this can happen a lot.
+	pub overlapped: bool,
+}
+
+
+/// Lays out every explored room reachable from `center_room` without passing through unexplored rooms,
+/// by following connections outwards the same way rooms are seen through apertures.
+/// Rooms are explored if the player has visited the source room they were processed from.
+pub fn unfold_explored_rooms(world: &ProcessedWorld, progress: &ProgressState, center_room: RoomId, max_depth: usize) -> Vec<AutomapRoom> {
+	let geometry = world.geometry();
+
+	let mut rooms: Vec<AutomapRoom> = Vec::new();
+	let mut room_bounds: Vec<Aabb2> = Vec::new();
+	let mut placed = SecondaryMap::new();
+
+	let mut queue = VecDeque::from([(center_room, Mat2x3::identity(), 0)]);
+	placed.insert(center_room, ());
+
+	// Breadth first, so rooms are placed relative to the closest instance of them
+	while let Some((room_id, room_to_map, depth)) = queue.pop_front() {
+		let bounds = geometry.room_vertices(room_id)
+			.fold(Aabb2::empty(), |bounds, vertex_id| bounds.include_point(room_to_map * geometry.vertices[vertex_id].position));
+
+		// Neighbouring rooms share walls, so only count overlap that goes deeper than that
+		let shrunk_bounds = bounds.shrink(0.05);
+		let overlapped = room_bounds.iter().any(|other| aabbs_overlap(&shrunk_bounds, other));
+
+		rooms.push(AutomapRoom { room_id, room_to_map, depth, overlapped });
+		room_bounds.push(bounds);
+
+		if depth >= max_depth {
+			continue
+		}
+
+		for connection in world.connections_for_room(room_id) {
+			let target_room = connection.target_room;
+			if placed.contains_key(target_room) || !progress.visited_rooms.contains(&world.to_source_room(target_room)) {
+				continue
+			}
+
+			placed.insert(target_room, ());
+			queue.push_back((target_room, room_to_map * connection.target_to_source, depth + 1));
+		}
+	}
+
+	rooms
+}
+
+
+fn aabbs_overlap(a: &Aabb2, b: &Aabb2) -> bool {
+	a.min.x < b.max.x && b.min.x < a.max.x
+		&& a.min.y < b.max.y && b.min.y < a.max.y
+}
+
+
+
+#[test]
+fn automap_only_unfolds_explored_rooms() {
+	let mut world = World::new();
+	world.geometry = WorldGeometry::new_square(1.0);
+
+	// Three rooms in a chain, spread out in world space
+	let first_room = world.geometry.first_room();
+	let second_room = world.geometry.insert_room_from_positions(&[
+		Vec2::new(4.5, -0.5),
+		Vec2::new(4.5,  0.5),
+		Vec2::new(5.5,  0.5),
+		Vec2::new(5.5, -0.5),
+	]);
+	let third_room = world.geometry.insert_room_from_positions(&[
+		Vec2::new(9.5, -0.5),
+		Vec2::new(9.5,  0.5),
+		Vec2::new(10.5,  0.5),
+		Vec2::new(10.5, -0.5),
+	]);
+
+	let wall_with_x = |geometry: &WorldGeometry, room_id: RoomId, x: f32| {
+		geometry.room_walls(room_id)
+			.find(|&wall_id| (geometry.wall_center(wall_id).x - x).abs() < 0.01)
+			.unwrap()
+	};
+
+	let first_wall = wall_with_x(&world.geometry, first_room, 0.5);
+	let second_near_wall = wall_with_x(&world.geometry, second_room, 4.5);
+	let second_far_wall = wall_with_x(&world.geometry, second_room, 5.5);
+	let third_wall = wall_with_x(&world.geometry, third_room, 9.5);
+
+	world.geometry.connect_wall(first_wall, second_near_wall).unwrap();
+	world.geometry.connect_wall(second_far_wall, third_wall).unwrap();
+
+	let processed_world = ProcessedWorld::new(&world, &ProgressModel::new(&MessageBus::new()), &MessageBus::new());
+
+	let mut progress = ProgressState::default();
+	progress.visited_rooms.insert(first_room);
+	progress.visited_rooms.insert(second_room);
+
+	let rooms = unfold_explored_rooms(&processed_world, &progress, first_room, AUTOMAP_MAX_DEPTH);
+	assert_eq!(rooms.len(), 2);
+	assert!(rooms.iter().all(|room| !room.overlapped));
+
+	// The second room is laid out right next to the first, not where it is in world space
+	let second = rooms.iter().find(|room| room.room_id == second_room).unwrap();
+	assert_eq!(second.depth, 1);
+	assert!((second.room_to_map * Vec2::new(5.0, 0.0) - Vec2::new(1.0, 0.0)).length() < 0.01);
+
+	progress.visited_rooms.insert(third_room);
+	let rooms = unfold_explored_rooms(&processed_world, &progress, first_room, AUTOMAP_MAX_DEPTH);
+	let third = rooms.iter().find(|room| room.room_id == third_room).unwrap();
+	assert_eq!(third.depth, 2);
+	assert!((third.room_to_map * Vec2::new(10.0, 0.0) - Vec2::new(2.0, 0.0)).length() < 0.01);
+
+	// Depth limit
+	assert_eq!(unfold_explored_rooms(&processed_world, &progress, first_room, 1).len(), 2);
+}
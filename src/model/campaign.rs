@@ -0,0 +1,275 @@
+use crate::prelude::*;
+use model::*;
+
+use slotmap::SecondaryMap;
+use std::collections::{BTreeMap, BTreeSet};
+
+
+/// Progression that only makes sense within a single world.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct WorldProgress {
+	pub object_states: SecondaryMap<ObjectId, ObjectState>,
+	pub visited_rooms: BTreeSet<RoomId>,
+
+	#[serde(default)]
+	pub markers: Vec<Marker>,
+
+	#[serde(default)]
+	pub completed_objectives: BTreeSet<String>,
+
+	#[serde(default)]
+	pub room_fog: SecondaryMap<RoomId, FogParameters>,
+
+	#[serde(default)]
+	pub runtime_objects: RuntimeObjectsSave,
+}
+
+
+/// Outlives individual worlds, so that leaving a world and coming back restores it as it was left.
+/// Flags, counters, inventory and resources are shared by every world and just stay where they are -
+/// only per-world progress for worlds other than the current one is kept here.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Campaign {
+	pub current_world: String,
+
+	#[serde(default)]
+	pub worlds: BTreeMap<String, WorldProgress>,
+}
+
+impl Campaign {
+	pub fn new(world_name: impl Into<String>) -> Campaign {
+		Campaign {
+			current_world: world_name.into(),
+			worlds: BTreeMap::new(),
+		}
+	}
+
+	/// Stores per-world progress for the current world, and restores whatever was stored for `world_name`.
+	/// Runtime objects live in the processed world rather than progress, so are passed in and returned separately.
+	pub fn switch_world(&mut self, world_name: &str, progress: &mut ProgressState, runtime_objects: RuntimeObjectsSave) -> RuntimeObjectsSave {
+		let leaving = WorldProgress {
+			object_states: std::mem::take(&mut progress.object_states),
+			visited_rooms: std::mem::take(&mut progress.visited_rooms),
+			markers: std::mem::take(&mut progress.markers),
+			completed_objectives: std::mem::take(&mut progress.completed_objectives),
+			room_fog: std::mem::take(&mut progress.room_fog),
+			runtime_objects,
+		};
+
+		let previous_world = std::mem::replace(&mut self.current_world, world_name.to_string());
+		self.worlds.insert(previous_world, leaving);
+
+		let arriving = self.worlds.remove(world_name).unwrap_or_default();
+		progress.object_states = arriving.object_states;
+		progress.visited_rooms = arriving.visited_rooms;
+		progress.markers = arriving.markers;
+		progress.completed_objectives = arriving.completed_objectives;
+		progress.room_fog = arriving.room_fog;
+
+		arriving.runtime_objects
+	}
+}
+
+
+/// Resource path of the order worlds are played in.
+pub const CAMPAIGN_WORLDS_PATH: &str = "campaign.json";
+
+
+/// Order worlds are played in - completing one moves on to the next. Loaded from `campaign.json`.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct CampaignWorlds {
+	pub worlds: Vec<String>,
+}
+
+impl CampaignWorlds {
+	pub fn load(vfs: &vfs::Vfs) -> CampaignWorlds {
+		match vfs.load_json_resource(CAMPAIGN_WORLDS_PATH) {
+			Ok(campaign_worlds) => campaign_worlds,
+			Err(err) => {
+				log::error!("Failed to load campaign worlds from '{CAMPAIGN_WORLDS_PATH}': {err}");
+				CampaignWorlds::default()
+			}
+		}
+	}
+
+	/// None if `world_name` is the last world, or isn't part of the campaign at all.
+	pub fn next_world(&self, world_name: &str) -> Option<&str> {
+		let index = self.worlds.iter().position(|name| name == world_name)?;
+		self.worlds.get(index + 1).map(String::as_str)
+	}
+}
+
+
+/// Follows every ladder reachable from `start_world`, and checks that each leads to a world that loads
+/// and an object that exists in it.
+pub fn validate_ladder_targets(start_world: &World, mut load_world: impl FnMut(&str) -> anyhow::Result<World>) -> anyhow::Result<()> {
+	let mut problems = Vec::new();
+
+	// None for worlds that failed to load.
+	let mut worlds: BTreeMap<String, Option<World>> = BTreeMap::new();
+	worlds.insert(start_world.name.clone(), Some(start_world.clone()));
+
+	let mut pending = vec![start_world.name.clone()];
+
+	while let Some(world_name) = pending.pop() {
+		let Some(Some(world)) = worlds.get(&world_name) else { continue };
+
+		let ladders: Vec<(String, String, String)> = world.objects.values()
+			.filter_map(|object| match &object.info {
+				ObjectInfo::Ladder{target_world, target_object} => Some((object.name.clone(), target_world.clone(), target_object.clone())),
+				_ => None,
+			})
+			.collect();
+
+		for (ladder_name, target_world, target_object) in ladders {
+			let target = worlds.entry(target_world.clone())
+				.or_insert_with(|| {
+					pending.push(target_world.clone());
+
+					load_world(&target_world)
+						.inspect_err(|err| problems.push(format!("World '{target_world}' failed to load: {err}")))
+						.ok()
+				});
+
+			let Some(target) = target else {
+				problems.push(format!("Ladder '{ladder_name}' in '{world_name}' leads to missing world '{target_world}'"));
+				continue
+			};
+
+			if !target.objects.values().any(|object| object.name == target_object) {
+				problems.push(format!("Ladder '{ladder_name}' in '{world_name}' leads to missing object '{target_object}' in '{target_world}'"));
+			}
+		}
+	}
+
+	anyhow::ensure!(problems.is_empty(), "Invalid ladder targets:\n{}", problems.join("\n"));
+
+	Ok(())
+}
+
+
+
+#[cfg(test)]
+fn ladder(name: &str, spawn: Placement, target_world: &str, target_object: &str) -> Object {
+	Object {
+		name: name.into(),
+		placement: spawn,
+		info: ObjectInfo::Ladder { target_world: target_world.into(), target_object: target_object.into() },
+		verbs: Vec::new(),
+	}
+}
+
+#[test]
+fn returning_to_world_restores_object_states() {
+	let mut first_world = World::new();
+	let spawn = first_world.player_spawn;
+	let chest_id = first_world.objects.insert(Object {
+		name: "chest".into(),
+		placement: spawn,
+		info: ObjectInfo::Chest { content: Vec::new(), salt: 0 },
+		verbs: Vec::new(),
+	});
+	let statue_id = first_world.objects.insert(Object {
+		name: "statue".into(),
+		placement: spawn,
+		info: ObjectInfo::Debug,
+		verbs: Vec::new(),
+	});
+	first_world.objects.insert(ladder("down", spawn, "second", "up"));
+
+	let mut second_world = World::new();
+	second_world.name = "second".into();
+	second_world.objects.insert(ladder("up", spawn, "default", "down"));
+
+	let first_source_model = SourceModel::from_world(first_world.clone());
+	let second_source_model = SourceModel::from_world(second_world.clone());
+
+	let bus = MessageBus::new();
+	let mut model = Model::new(&first_world, 0, &bus);
+
+	let moved_placement = Placement { position: spawn.position + Vec2::new(1.0, 0.0), .. spawn };
+	bus.emit(ProgressCmd::SetObjectFlag(chest_id, ObjectFlag::Opened, true));
+	bus.emit(ProgressCmd::SetFlag("opened-chest".into(), true));
+	bus.emit(ObjectCmd::Move(statue_id, moved_placement));
+	bus.emit(ObjectCmd::Spawn(Object { name: "offering".into(), .. first_world.objects[statue_id].clone() }));
+	model.step(&first_source_model, &TickInput::default(), &bus);
+	assert!(model.progress.state.visited_rooms.contains(&spawn.room_id));
+
+	let offering_id = model.processed_world.runtime_objects().find_by_name("offering").unwrap();
+	bus.emit(ProgressCmd::SetObjectFlag(offering_id, ObjectFlag::Opened, true));
+	model.step(&first_source_model, &TickInput::default(), &bus);
+
+	model.change_world(&second_world, Some("up"), &bus);
+	model.step(&second_source_model, &TickInput::default(), &bus);
+
+	assert_eq!(model.campaign.current_world, "second");
+	assert!(model.progress.state.object_states.is_empty());
+	assert!(model.progress.state.flag("opened-chest"));
+
+	model.change_world(&first_world, Some("down"), &bus);
+	model.step(&first_source_model, &TickInput::default(), &bus);
+
+	assert!(model.progress.state.object_state(chest_id).opened);
+	assert!(model.progress.state.visited_rooms.contains(&spawn.room_id));
+	assert!(model.campaign.worlds.contains_key("second"));
+
+	let runtime_objects = model.processed_world.runtime_objects();
+	assert_eq!(runtime_objects.placement(statue_id), Some(moved_placement));
+
+	let offering_id = runtime_objects.find_by_name("offering").unwrap();
+	assert!(model.progress.state.object_state(offering_id).opened);
+}
+
+#[test]
+fn ladder_validation_reports_missing_targets() {
+	let mut first_world = World::new();
+	let spawn = first_world.player_spawn;
+	first_world.objects.insert(ladder("down", spawn, "second", "up"));
+
+	let mut second_world = World::new();
+	second_world.name = "second".into();
+	second_world.objects.insert(ladder("up", spawn, "default", "down"));
+
+	let load_world = |name: &str| -> anyhow::Result<World> { match name {
+		"second" => Ok(second_world.clone()),
+		_ => anyhow::bail!("No world '{name}'"),
+	}};
+
+	validate_ladder_targets(&first_world, load_world).unwrap();
+
+	first_world.objects.insert(ladder("broken", spawn, "second", "nowhere"));
+	first_world.objects.insert(ladder("lost", spawn, "third", "up"));
+
+	let err = validate_ladder_targets(&first_world, load_world).unwrap_err().to_string();
+	assert!(err.contains("missing object 'nowhere'"), "{err}");
+	assert!(err.contains("missing world 'third'"), "{err}");
+}
+
+/// Every world shipped in resource/worlds must only have ladders that lead somewhere.
+#[test]
+fn resource_world_ladders_are_valid() {
+	let world_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("resource/worlds");
+	let Ok(entries) = std::fs::read_dir(&world_dir) else { return };
+
+	let load_world = |name: &str| -> anyhow::Result<World> {
+		let data = std::fs::read_to_string(world_dir.join(format!("{name}.world")))?;
+		let mut world: World = serde_json::from_str(&data)?;
+		world.name = name.to_string();
+		Ok(world)
+	};
+
+	for entry in entries {
+		let path = entry.unwrap().path();
+		if path.extension().is_none_or(|ext| ext != "world") {
+			continue
+		}
+
+		// Some worlds are deliberately broken
+		let name = path.file_stem().unwrap().to_string_lossy();
+		let Ok(world) = load_world(&name) else { continue };
+
+		if let Err(err) = validate_ladder_targets(&world, load_world) {
+			panic!("{}: {err}", path.display());
+		}
+	}
+}
@@ -0,0 +1,304 @@
+use crate::prelude::*;
+use model::*;
+
+use std::collections::BTreeMap;
+
+
+pub const DIALOGUE_DEFS_PATH: &str = "dialogues.json";
+
+/// Every dialogue begins at the node with this name.
+pub const DIALOGUE_START_NODE: &str = "start";
+
+/// Typewriter reveal speed in characters per second.
+pub const DIALOGUE_REVEAL_RATE: f32 = 40.0;
+
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct DialogueId(pub String);
+
+impl DialogueId {
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+}
+
+impl From<&str> for DialogueId {
+	fn from(value: &str) -> Self {
+		DialogueId(value.to_string())
+	}
+}
+
+impl std::fmt::Display for DialogueId {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Dialogue {
+	pub nodes: BTreeMap<String, DialogueNode>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct DialogueNode {
+	#[serde(default)]
+	pub speaker: Option<String>,
+
+	/// Shown one after another, each page waiting for the player to continue.
+	pub pages: Vec<String>,
+
+	/// Applied when the node is entered.
+	#[serde(default)]
+	pub effects: Vec<Effect>,
+
+	/// Shown after the last page. If none are available, dialogue continues to `next`.
+	#[serde(default)]
+	pub choices: Vec<DialogueChoice>,
+
+	/// Node to go to after the last page. Dialogue ends if None.
+	#[serde(default)]
+	pub next: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct DialogueChoice {
+	pub text: String,
+
+	/// Choice is only offered if this passes.
+	#[serde(default)]
+	pub condition: Option<Condition>,
+
+	#[serde(default)]
+	pub effects: Vec<Effect>,
+
+	#[serde(default)]
+	pub next: Option<String>,
+}
+
+/// All known dialogues, keyed by id. Loaded from `dialogues.json`.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct DialogueDefs {
+	pub dialogues: BTreeMap<DialogueId, Dialogue>,
+}
+
+impl DialogueDefs {
+	pub fn load(vfs: &vfs::Vfs) -> DialogueDefs {
+		match vfs.load_json_resource(DIALOGUE_DEFS_PATH) {
+			Ok(dialogue_defs) => dialogue_defs,
+			Err(err) => {
+				log::error!("Failed to load dialogue definitions from '{DIALOGUE_DEFS_PATH}': {err}");
+				DialogueDefs::default()
+			}
+		}
+	}
+
+	pub fn get(&self, dialogue_id: &DialogueId) -> Option<&Dialogue> {
+		self.dialogues.get(dialogue_id)
+	}
+
+	pub fn node(&self, dialogue_id: &DialogueId, node: &str) -> Option<&DialogueNode> {
+		self.get(dialogue_id)?.nodes.get(node)
+	}
+}
+
+
+
+/// Where the player is in a dialogue. Owned by `HudModel`.
+#[derive(Debug, Clone)]
+pub struct ActiveDialogue {
+	pub dialogue_id: DialogueId,
+	pub node: String,
+	pub page: usize,
+
+	/// Number of characters of the current page revealed so far.
+	pub revealed_chars: f32,
+
+	/// Indices into the current node's choices that passed their conditions when it was entered.
+	pub available_choices: Vec<usize>,
+	pub selected_choice: usize,
+}
+
+impl ActiveDialogue {
+	pub fn start(dialogue_id: DialogueId, dialogues: &DialogueDefs, progress: &ProgressState, bus: &MessageBus) -> Option<ActiveDialogue> {
+		if dialogues.node(&dialogue_id, DIALOGUE_START_NODE).is_none() {
+			log::warn!("Dialogue '{dialogue_id}' doesn't exist or has no '{DIALOGUE_START_NODE}' node");
+			return None
+		}
+
+		let mut dialogue = ActiveDialogue {
+			dialogue_id,
+			node: String::new(),
+			page: 0,
+			revealed_chars: 0.0,
+			available_choices: Vec::new(),
+			selected_choice: 0,
+		};
+
+		dialogue.enter(DIALOGUE_START_NODE, dialogues, progress, bus)
+			.then_some(dialogue)
+	}
+
+	pub fn current_node<'d>(&self, dialogues: &'d DialogueDefs) -> Option<&'d DialogueNode> {
+		dialogues.node(&self.dialogue_id, &self.node)
+	}
+
+	pub fn current_page<'d>(&self, dialogues: &'d DialogueDefs) -> &'d str {
+		self.current_node(dialogues)
+			.and_then(|node| node.pages.get(self.page))
+			.map_or("", String::as_str)
+	}
+
+	/// The part of the current page that has been revealed by the typewriter.
+	pub fn revealed_text<'d>(&self, dialogues: &'d DialogueDefs) -> &'d str {
+		let page = self.current_page(dialogues);
+		let end = page.char_indices()
+			.nth(self.revealed_chars as usize)
+			.map_or(page.len(), |(index, _)| index);
+
+		&page[..end]
+	}
+
+	pub fn is_page_revealed(&self, dialogues: &DialogueDefs) -> bool {
+		self.revealed_chars as usize >= self.current_page(dialogues).chars().count()
+	}
+
+	pub fn is_last_page(&self, dialogues: &DialogueDefs) -> bool {
+		self.current_node(dialogues)
+			.is_none_or(|node| self.page + 1 >= node.pages.len())
+	}
+
+	/// Whether choices should currently be presented.
+	pub fn showing_choices(&self, dialogues: &DialogueDefs) -> bool {
+		!self.available_choices.is_empty() && self.is_last_page(dialogues) && self.is_page_revealed(dialogues)
+	}
+
+	pub fn update(&mut self, dt: f32) {
+		self.revealed_chars += DIALOGUE_REVEAL_RATE * dt;
+	}
+
+	pub fn move_selection(&mut self, offset: i32) {
+		let num_choices = self.available_choices.len() as i32;
+		if num_choices > 0 {
+			self.selected_choice = (self.selected_choice as i32 + offset).rem_euclid(num_choices) as usize;
+		}
+	}
+
+	/// Skips the typewriter, turns the page, or takes the selected choice.
+	/// Returns false once the dialogue has ended.
+	pub fn advance(&mut self, dialogues: &DialogueDefs, progress: &ProgressState, bus: &MessageBus) -> bool {
+		let Some(node) = self.current_node(dialogues) else { return false };
+
+		if !self.is_page_revealed(dialogues) {
+			self.revealed_chars = self.current_page(dialogues).chars().count() as f32;
+			return true
+		}
+
+		if !self.is_last_page(dialogues) {
+			self.page += 1;
+			self.revealed_chars = 0.0;
+			return true
+		}
+
+		let next = match self.available_choices.get(self.selected_choice) {
+			Some(&choice_index) => {
+				let choice = &node.choices[choice_index];
+				emit_effects(&choice.effects, bus);
+
+				choice.next.as_deref()
+			}
+
+			None => node.next.as_deref(),
+		};
+
+		match next {
+			Some(next) => self.enter(next, dialogues, progress, bus),
+			None => false,
+		}
+	}
+
+	fn enter(&mut self, node_name: &str, dialogues: &DialogueDefs, progress: &ProgressState, bus: &MessageBus) -> bool {
+		let Some(node) = dialogues.node(&self.dialogue_id, node_name) else {
+			log::warn!("Dialogue '{}' has no node '{node_name}'", self.dialogue_id);
+			return false
+		};
+
+		self.node = node_name.to_string();
+		self.page = 0;
+		self.revealed_chars = 0.0;
+		self.selected_choice = 0;
+
+		// NOTE: effects of this node won't have been applied to progress yet, so can't affect which choices are available.
+		self.available_choices = node.choices.iter().enumerate()
+			.filter(|(_, choice)| choice.condition.as_ref().is_none_or(|condition| progress.check(condition)))
+			.map(|(index, _)| index)
+			.collect();
+
+		emit_effects(&node.effects, bus);
+
+		true
+	}
+}
+
+
+
+#[cfg(test)]
+fn test_dialogues() -> DialogueDefs {
+	let json = r#"{
+		"test": {
+			"nodes": {
+				"start": {
+					"speaker": "Worm",
+					"pages": ["Hello", "Again"],
+					"choices": [
+						{ "text": "Secret", "condition": { "Flag": "knows-secret" }, "next": "secret" },
+						{ "text": "Bye", "effects": [{ "kind": "SetFlag", "name": "said-bye" }] }
+					]
+				},
+				"secret": {
+					"pages": ["Shh"]
+				}
+			}
+		}
+	}"#;
+
+	serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn dialogue_pages_and_conditional_choices() {
+	let dialogues = test_dialogues();
+	let bus = MessageBus::new();
+	let effect_sub = bus.subscribe::<Effect>();
+
+	let mut progress = ProgressState::default();
+	let mut dialogue = ActiveDialogue::start("test".into(), &dialogues, &progress, &bus).unwrap();
+	assert_eq!(dialogue.available_choices, [1]);
+
+	// First advance skips the typewriter, second turns the page
+	assert_eq!(dialogue.revealed_text(&dialogues), "");
+	assert!(dialogue.advance(&dialogues, &progress, &bus));
+	assert_eq!(dialogue.revealed_text(&dialogues), "Hello");
+	assert!(!dialogue.showing_choices(&dialogues));
+
+	assert!(dialogue.advance(&dialogues, &progress, &bus));
+	dialogue.update(1.0);
+	assert_eq!(dialogue.revealed_text(&dialogues), "Again");
+	assert!(dialogue.showing_choices(&dialogues));
+
+	assert!(!dialogue.advance(&dialogues, &progress, &bus));
+	assert!(matches!(bus.poll_consume(&effect_sub).next(), Some(Effect::SetFlag{name, value: true}) if name == "said-bye"));
+
+	// Secret choice only shows up with the flag set
+	progress.flags.insert("knows-secret".into());
+	let mut dialogue = ActiveDialogue::start("test".into(), &dialogues, &progress, &bus).unwrap();
+	assert_eq!(dialogue.available_choices, [0, 1]);
+
+	dialogue.update(10.0);
+	dialogue.advance(&dialogues, &progress, &bus);
+	dialogue.update(10.0);
+	assert!(dialogue.advance(&dialogues, &progress, &bus));
+	assert_eq!(dialogue.node, "secret");
+}
@@ -0,0 +1,209 @@
+use crate::prelude::*;
+use model::*;
+
+
+pub const MAX_BLOOD: u32 = 100;
+
+/// Salt needed to ward off entities.
+pub const WARD_SALT_COST: u32 = 5;
+
+/// How long a ward lasts in seconds.
+pub const WARD_DURATION: f32 = 10.0;
+
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Resource {
+	Blood,
+	Salt,
+}
+
+
+#[derive(Debug, Clone)]
+pub enum ResourceCmd {
+	/// Unconditionally change a resource, clamping at zero.
+	Add(Resource, i32),
+
+	/// Only succeeds if the player has enough. Emits `ResourceEvent::Insufficient` otherwise.
+	Spend(Resource, u32),
+
+	/// Spend salt to ward off entities for a while.
+	Ward,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResourceEvent {
+	Changed {
+		resource: Resource,
+		previous: u32,
+		current: u32,
+	},
+
+	Insufficient {
+		resource: Resource,
+		required: u32,
+	},
+
+	WardStarted,
+	Died,
+}
+
+
+#[derive(Debug)]
+pub struct EconomyModel {
+	// Fractional blood drained by hazardous rooms that hasn't been applied yet.
+	hazard_accumulator: f32,
+
+	resource_cmd_sub: Subscription<ResourceCmd>,
+}
+
+impl EconomyModel {
+	pub fn new(bus: &MessageBus) -> Self {
+		EconomyModel {
+			hazard_accumulator: 0.0,
+			resource_cmd_sub: bus.subscribe(),
+		}
+	}
+
+	pub fn update(&mut self, player: &mut Player, world: &ProcessedWorld, source_world: &World, dt: f32, bus: &MessageBus) {
+		player.ward_time_remaining = (player.ward_time_remaining - dt).max(0.0);
+
+		for cmd in bus.poll_consume(&self.resource_cmd_sub) {
+			match cmd {
+				ResourceCmd::Add(resource, delta) => {
+					change_resource(player, resource, delta, bus);
+				}
+
+				ResourceCmd::Spend(resource, amount) => {
+					if !try_spend(player, resource, amount, bus) {
+						bus.emit(ResourceEvent::Insufficient { resource, required: amount });
+					}
+				}
+
+				ResourceCmd::Ward => {
+					if try_spend(player, Resource::Salt, WARD_SALT_COST, bus) {
+						player.ward_time_remaining = WARD_DURATION;
+						bus.emit(ResourceEvent::WardStarted);
+					} else {
+						bus.emit(ResourceEvent::Insufficient { resource: Resource::Salt, required: WARD_SALT_COST });
+					}
+				}
+			}
+		}
+
+		// Drain blood in hazardous rooms
+		let source_room_id = world.to_source_room(player.placement.room_id);
+		let blood_drain = source_world.geometry.rooms.get(source_room_id)
+			.map_or(0.0, |room| room.blood_drain);
+
+		if blood_drain > 0.0 && !player.free_cam {
+			self.hazard_accumulator += blood_drain * dt;
+
+			let whole_drain = self.hazard_accumulator.floor();
+			if whole_drain >= 1.0 {
+				self.hazard_accumulator -= whole_drain;
+				change_resource(player, Resource::Blood, -(whole_drain as i32), bus);
+			}
+		} else {
+			self.hazard_accumulator = 0.0;
+		}
+
+		if player.blood == 0 {
+			self.respawn(player, world, source_world, bus);
+		}
+	}
+
+	fn respawn(&mut self, player: &mut Player, world: &ProcessedWorld, source_world: &World, bus: &MessageBus) {
+		log::info!("Player died");
+		bus.emit(ResourceEvent::Died);
+		bus.emit(HudCmd::ShowText("Your blood runs dry".into()));
+
+		player.placement = world.to_processed_placement(source_world.player_spawn);
+		player.pitch = 0.0;
+		player.ward_time_remaining = 0.0;
+
+		// Dying costs half your salt
+		change_resource(player, Resource::Salt, -(player.salt as i32 / 2), bus);
+		change_resource(player, Resource::Blood, MAX_BLOOD as i32, bus);
+
+		self.hazard_accumulator = 0.0;
+	}
+}
+
+
+fn resource_mut(player: &mut Player, resource: Resource) -> &mut u32 {
+	match resource {
+		Resource::Blood => &mut player.blood,
+		Resource::Salt => &mut player.salt,
+	}
+}
+
+fn change_resource(player: &mut Player, resource: Resource, delta: i32, bus: &MessageBus) {
+	let value = resource_mut(player, resource);
+	let previous = *value;
+
+	let mut current = previous.saturating_add_signed(delta);
+	if resource == Resource::Blood {
+		current = current.min(MAX_BLOOD);
+	}
+
+	*value = current;
+
+	if current != previous {
+		bus.emit(ResourceEvent::Changed { resource, previous, current });
+	}
+}
+
+fn try_spend(player: &mut Player, resource: Resource, amount: u32, bus: &MessageBus) -> bool {
+	if *resource_mut(player, resource) < amount {
+		return false
+	}
+
+	change_resource(player, resource, -(amount as i32), bus);
+	true
+}
+
+
+
+#[test]
+fn hazardous_room_drains_blood_until_respawn() {
+	let mut world = World::new();
+	let spawn_room = world.player_spawn.room_id;
+	world.geometry.rooms[spawn_room].blood_drain = 50.0;
+
+	let (source_model, bus, mut model) = test_model(&world);
+	let event_sub = bus.subscribe::<ResourceEvent>();
+	model.player.salt = 10;
+
+	// One second at 50 blood/s
+	for _ in 0..60 {
+		model.step(&source_model, &TickInput::default(), &bus);
+	}
+
+	assert!((49..=51).contains(&model.player.blood), "blood was {}", model.player.blood);
+	assert!(bus.poll_consume(&event_sub).all(|event| matches!(event, ResourceEvent::Changed{ resource: Resource::Blood, .. })));
+
+	for _ in 0..90 {
+		model.step(&source_model, &TickInput::default(), &bus);
+	}
+
+	let events: Vec<_> = bus.poll_consume(&event_sub).collect();
+	assert!(events.contains(&ResourceEvent::Died));
+	assert!(model.player.blood > 0);
+	assert_eq!(model.player.salt, 5);
+}
+
+#[test]
+fn ward_requires_salt() {
+	let world = World::new();
+	let (source_model, bus, mut model) = test_model(&world);
+	model.player.salt = WARD_SALT_COST + 1;
+
+	bus.emit(ResourceCmd::Ward);
+	model.step(&source_model, &TickInput::default(), &bus);
+	assert!(model.player.is_warded());
+	assert_eq!(model.player.salt, 1);
+
+	bus.emit(ResourceCmd::Ward);
+	model.step(&source_model, &TickInput::default(), &bus);
+	assert_eq!(model.player.salt, 1);
+}
@@ -0,0 +1,311 @@
+use crate::prelude::*;
+use model::*;
+
+
+/// Something that happens as a result of an interaction, trigger, ritual or dialogue.
+/// Emitted on the bus and applied by `EffectExecutor`.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind")]
+pub enum Effect {
+	ShowText { text: String },
+	ShowDialog { dialogue: DialogueId },
+
+	SetFlag {
+		name: String,
+		#[serde(default = "default_true")]
+		value: bool,
+	},
+
+	AddCounter { name: String, amount: i32 },
+
+	GiveItem {
+		item: ItemId,
+		#[serde(default = "default_count")]
+		count: u32,
+	},
+
+	TakeItem {
+		item: ItemId,
+		#[serde(default = "default_count")]
+		count: u32,
+	},
+
+	AddResource { resource: Resource, amount: i32 },
+
+	/// Opens the object with the given name, e.g., a sealed door.
+	OpenObject { object_name: String },
+
+	/// Opens or closes the object with the given name.
+	ToggleObject { object_name: String },
+
+	ToggleLight { object_name: String },
+
+	/// Moves the player to the object with the given name in the current world.
+	Teleport { object_name: String },
+
+	/// Moves the player to the object with the given name in another world.
+	TransitionWorld { world_name: String, object_name: String },
+
+	/// Spawns a worm at the object with the given name.
+	SpawnWorm { object_name: String },
+
+	/// Overrides the fog in the room the named object is in.
+	SetFog { object_name: String, fog: FogParameters },
+
+	/// Puts the world fog back in the room the named object is in.
+	ResetFog { object_name: String },
+
+	PlaySound { sound: String },
+}
+
+fn default_true() -> bool { true }
+fn default_count() -> u32 { 1 }
+
+impl Effect {
+	pub const KIND_NAMES: &'static [&'static str] = &[
+		"ShowText", "ShowDialog", "SetFlag", "AddCounter", "GiveItem", "TakeItem", "AddResource",
+		"OpenObject", "ToggleObject", "ToggleLight", "Teleport", "TransitionWorld", "SpawnWorm", "SetFog", "ResetFog", "PlaySound",
+	];
+
+	pub fn kind_name(&self) -> &'static str {
+		match self {
+			Effect::ShowText{..} => "ShowText",
+			Effect::ShowDialog{..} => "ShowDialog",
+			Effect::SetFlag{..} => "SetFlag",
+			Effect::AddCounter{..} => "AddCounter",
+			Effect::GiveItem{..} => "GiveItem",
+			Effect::TakeItem{..} => "TakeItem",
+			Effect::AddResource{..} => "AddResource",
+			Effect::OpenObject{..} => "OpenObject",
+			Effect::ToggleObject{..} => "ToggleObject",
+			Effect::ToggleLight{..} => "ToggleLight",
+			Effect::Teleport{..} => "Teleport",
+			Effect::TransitionWorld{..} => "TransitionWorld",
+			Effect::SpawnWorm{..} => "SpawnWorm",
+			Effect::SetFog{..} => "SetFog",
+			Effect::ResetFog{..} => "ResetFog",
+			Effect::PlaySound{..} => "PlaySound",
+		}
+	}
+
+	/// Creates an effect of the given kind with empty parameters. Used by the editor.
+	pub fn from_kind_name(name: &str) -> Option<Effect> {
+		let effect = match name {
+			"ShowText" => Effect::ShowText { text: String::new() },
+			"ShowDialog" => Effect::ShowDialog { dialogue: DialogueId::default() },
+			"SetFlag" => Effect::SetFlag { name: String::new(), value: true },
+			"AddCounter" => Effect::AddCounter { name: String::new(), amount: 1 },
+			"GiveItem" => Effect::GiveItem { item: "".into(), count: 1 },
+			"TakeItem" => Effect::TakeItem { item: "".into(), count: 1 },
+			"AddResource" => Effect::AddResource { resource: Resource::Blood, amount: 0 },
+			"OpenObject" => Effect::OpenObject { object_name: String::new() },
+			"ToggleObject" => Effect::ToggleObject { object_name: String::new() },
+			"ToggleLight" => Effect::ToggleLight { object_name: String::new() },
+			"Teleport" => Effect::Teleport { object_name: String::new() },
+			"TransitionWorld" => Effect::TransitionWorld { world_name: String::new(), object_name: String::new() },
+			"SpawnWorm" => Effect::SpawnWorm { object_name: String::new() },
+			"SetFog" => Effect::SetFog { object_name: String::new(), fog: FogParameters::default() },
+			"ResetFog" => Effect::ResetFog { object_name: String::new() },
+			"PlaySound" => Effect::PlaySound { sound: String::new() },
+			_ => return None,
+		};
+
+		Some(effect)
+	}
+}
+
+
+pub fn emit_effects(effects: &[Effect], bus: &MessageBus) {
+	for effect in effects {
+		bus.emit(effect.clone());
+	}
+}
+
+
+/// Requests a change to another world. Handled outside of the simulation, since it needs to load resources.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldTransitionEvent {
+	pub world_name: String,
+	pub object_name: String,
+}
+
+
+/// Applies all effects emitted during a tick.
+#[derive(Debug)]
+pub struct EffectExecutor {
+	effect_sub: Subscription<Effect>,
+}
+
+impl EffectExecutor {
+	pub fn new(bus: &MessageBus) -> Self {
+		EffectExecutor {
+			effect_sub: bus.subscribe(),
+		}
+	}
+
+	pub fn update(&mut self, player: &mut Player, world: &ProcessedWorld, progress: &ProgressModel, bus: &MessageBus) {
+		for effect in bus.poll_consume(&self.effect_sub) {
+			log::debug!("Applying {effect:?}");
+
+			match effect {
+				Effect::ShowText{text} => bus.emit(HudCmd::ShowText(text)),
+				Effect::ShowDialog{dialogue} => bus.emit(HudCmd::ShowDialog(dialogue)),
+
+				Effect::SetFlag{name, value} => bus.emit(ProgressCmd::SetFlag(name, value)),
+				Effect::AddCounter{name, amount} => bus.emit(ProgressCmd::AddCounter(name, amount)),
+
+				Effect::GiveItem{item, count} => bus.emit(InventoryCmd::Give(item, count)),
+				Effect::TakeItem{item, count} => bus.emit(InventoryCmd::Take(item, count)),
+				Effect::AddResource{resource, amount} => bus.emit(ResourceCmd::Add(resource, amount)),
+
+				Effect::OpenObject{object_name} => {
+					if let Some(object_id) = find_object(world, &object_name) {
+						bus.emit(ProgressCmd::SetObjectFlag(object_id, ObjectFlag::Opened, true));
+					}
+				}
+
+				Effect::ToggleObject{object_name} => {
+					if let Some(object_id) = find_object(world, &object_name) {
+						let opened = progress.state.object_state(object_id).opened;
+						bus.emit(ProgressCmd::SetObjectFlag(object_id, ObjectFlag::Opened, !opened));
+					}
+				}
+
+				Effect::ToggleLight{object_name} => {
+					if let Some(object_id) = find_object(world, &object_name)
+						&& let Some((object, _)) = world.runtime_objects().get(object_id)
+						&& let Some(light) = object.as_light()
+					{
+						bus.emit(toggle_light_cmd(object_id, light, progress.state.object_state(object_id)));
+					}
+				}
+
+				Effect::Teleport{object_name} => {
+					if let Some(target) = find_object(world, &object_name)
+						.and_then(|object_id| world.runtime_objects().placement(object_id))
+					{
+						player.placement = world.to_processed_placement(target);
+					}
+				}
+
+				Effect::TransitionWorld{world_name, object_name} => {
+					bus.emit(WorldTransitionEvent { world_name, object_name });
+				}
+
+				Effect::SpawnWorm{object_name} => {
+					if let Some(placement) = find_object(world, &object_name)
+						.and_then(|object_id| world.runtime_objects().placement(object_id))
+					{
+						bus.emit(WormCmd::Spawn(placement));
+					}
+				}
+
+				Effect::SetFog{object_name, fog} => {
+					if let Some(placement) = find_object(world, &object_name)
+						.and_then(|object_id| world.runtime_objects().placement(object_id))
+					{
+						bus.emit(ProgressCmd::SetRoomFog(placement.room_id, Some(fog)));
+					}
+				}
+
+				Effect::ResetFog{object_name} => {
+					if let Some(placement) = find_object(world, &object_name)
+						.and_then(|object_id| world.runtime_objects().placement(object_id))
+					{
+						bus.emit(ProgressCmd::SetRoomFog(placement.room_id, None));
+					}
+				}
+
+				Effect::PlaySound{sound} => bus.emit(SoundEvent::Play(sound)),
+			}
+		}
+	}
+}
+
+
+// Look in runtime objects, since collected and disabled objects don't exist in the processed world.
+fn find_object(world: &ProcessedWorld, object_name: &str) -> Option<ObjectId> {
+	let object_id = world.runtime_objects().find_by_name(object_name);
+
+	if object_id.is_none() {
+		log::warn!("Effect refers to unknown object '{object_name}'");
+	}
+
+	object_id
+}
+
+
+
+#[test]
+fn effects_round_trip_through_json() {
+	let effects = vec![
+		Effect::SetFlag { name: "flag".into(), value: true },
+		Effect::GiveItem { item: "candle".into(), count: 2 },
+		Effect::TransitionWorld { world_name: "world2".into(), object_name: "ladder".into() },
+		Effect::ResetFog { object_name: "altar".into() },
+	];
+
+	let json = serde_json::to_string(&effects).unwrap();
+	assert_eq!(serde_json::from_str::<Vec<Effect>>(&json).unwrap(), effects);
+
+	// Optional parameters can be left out
+	let effect: Effect = serde_json::from_str(r#"{ "kind": "SetFlag", "name": "flag" }"#).unwrap();
+	assert_eq!(effect, Effect::SetFlag { name: "flag".into(), value: true });
+}
+
+#[test]
+fn teleport_moves_player_to_object() {
+	let mut world = World::new();
+	let spawn = world.player_spawn;
+
+	world.objects.insert(Object {
+		name: "target".into(),
+		placement: Placement { position: spawn.position + Vec2::new(1.0, 0.5), .. spawn },
+		info: ObjectInfo::Debug,
+		verbs: Vec::new(),
+	});
+
+	let (source_model, bus, mut model) = test_model(&world);
+
+	bus.emit(Effect::Teleport { object_name: "target".into() });
+	model.step(&source_model, &TickInput::default(), &bus);
+
+	assert_eq!(model.player.placement.position, spawn.position + Vec2::new(1.0, 0.5));
+}
+
+#[test]
+fn set_fog_only_changes_the_objects_room() {
+	let mut world = World::new();
+	let far_room = world.geometry.insert_room_from_positions(&[
+		Vec2::new(20.0, -1.0),
+		Vec2::new(20.0,  1.0),
+		Vec2::new(22.0,  1.0),
+		Vec2::new(22.0, -1.0),
+	]);
+
+	world.objects.insert(Object {
+		name: "altar".into(),
+		placement: Placement { room_id: far_room, position: Vec2::new(21.0, 0.0), yaw: 0.0 },
+		info: ObjectInfo::Debug,
+		verbs: Vec::new(),
+	});
+
+	let (source_model, bus, mut model) = test_model(&world);
+
+	let thick_fog = FogParameters { distance: 3.0, .. world.fog };
+	bus.emit(Effect::SetFog { object_name: "altar".into(), fog: thick_fog });
+	model.step(&source_model, &TickInput::default(), &bus);
+	model.step(&source_model, &TickInput::default(), &bus);
+
+	assert_eq!(model.environment.fog, world.fog);
+	assert_eq!(model.processed_world.room_fog(far_room), thick_fog);
+
+	bus.emit(Effect::Teleport { object_name: "altar".into() });
+	model.step(&source_model, &TickInput::default(), &bus);
+	assert_eq!(model.environment.fog, thick_fog);
+
+	bus.emit(Effect::ResetFog { object_name: "altar".into() });
+	model.step(&source_model, &TickInput::default(), &bus);
+	model.step(&source_model, &TickInput::default(), &bus);
+	assert_eq!(model.environment.fog, world.fog);
+}
@@ -1,8 +1,16 @@
 use crate::prelude::*;
 use model::*;
 
+/// Sounds requested by the simulation. Played by whoever is presenting it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SoundEvent {
+	Play(String),
+}
+
+
 #[derive(Debug)]
 pub struct EnvironmentModel {
+	/// Fog in the room the player is in.
 	pub fog: FogParameters,
 }
 
@@ -13,17 +21,16 @@ impl EnvironmentModel {
 		}
 	}
 
-	pub fn update(&mut self, world: &ProcessedWorld, _message_bus: &MessageBus) {
-		// TODO(pat.m): respond to requests to change parameters
-		// TODO(pat.m): interpolate fog values
+	pub fn update(&mut self, player: &Player, world: &ProcessedWorld, _message_bus: &MessageBus) {
+		// TODO(pat.m): interpolate fog values, so walking between rooms with different fog isn't so jarring
 
-		self.fog = world.fog;
+		self.fog = world.room_fog(player.placement.room_id);
 	}
 }
 
 
 
-#[derive(Copy, Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct FogParameters {
 	pub color: Color,
 	pub start: f32,
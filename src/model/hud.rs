@@ -5,39 +5,83 @@ use model::*;
 
 #[derive(Debug, Clone)]
 pub enum HudCmd {
-	ShowDialog(()),
+	ShowDialog(DialogueId),
 	ShowText(String),
+
+	/// Reveals the rest of the page, turns the page or takes the selected choice.
+	AdvanceDialog,
 	DismissDialog,
 
-	// TODO(pat.m): why is this here???????
-	TransitionWorld {
-		world_name: String,
-		object_name: String,
-		// transition kind
-	}
+	ShowChoice(HudChoice),
+	MoveChoiceSelection(i32),
+	ConfirmChoice,
+	CancelChoice,
+
+	ToggleMap,
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HudEvent {
+	/// Index into the options of the choice that was just closed.
+	ChoiceMade(usize),
+	ChoiceCancelled,
+
+	DialogueFinished(DialogueId),
 }
 
 
 #[derive(Debug)]
 pub struct HudModel {
-	pub in_dialog: bool,
+	pub dialogue: Option<ActiveDialogue>,
 	pub hud_text: Option<HudText>,
+	pub choice: Option<HudChoice>,
+	pub map_open: bool,
+
+	pub blood_display: ResourceDisplay,
+	pub salt_display: ResourceDisplay,
 
 	hud_cmd: Subscription<HudCmd>,
+	resource_event_sub: Subscription<ResourceEvent>,
 }
 
 impl HudModel {
 	pub fn new(bus: &MessageBus) -> Self {
 		HudModel {
-			in_dialog: false,
+			dialogue: None,
 			hud_text: None,
+			choice: None,
+			map_open: false,
+
+			blood_display: ResourceDisplay::default(),
+			salt_display: ResourceDisplay::default(),
+
 			hud_cmd: bus.subscribe(),
+			resource_event_sub: bus.subscribe(),
 		}
 	}
 
-	pub fn update(&mut self, bus: &MessageBus) {
+	pub fn update(&mut self, player: &Player, progress: &ProgressState, dialogues: &DialogueDefs, dt: f32, bus: &MessageBus) {
+		for event in bus.poll(&self.resource_event_sub) {
+			if let ResourceEvent::Changed { resource, previous, current } = event {
+				let display = match resource {
+					Resource::Blood => &mut self.blood_display,
+					Resource::Salt => &mut self.salt_display,
+				};
+
+				display.start_flash(current > previous);
+			}
+		}
+
+		self.blood_display.update(player.blood, dt);
+		self.salt_display.update(player.salt, dt);
+
+		if let Some(dialogue) = &mut self.dialogue {
+			dialogue.update(dt);
+		}
+
 		if let Some(hud_text) = &mut self.hud_text {
-			hud_text.elapsed_visible_time += 1.0 / 60.0;
+			hud_text.elapsed_visible_time += dt;
 			if hud_text.elapsed_visible_time > HUD_TEXT_SHOW_TIME {
 				self.hud_text = None;
 			}
@@ -45,12 +89,22 @@ impl HudModel {
 
 		for msg in bus.poll_consume(&self.hud_cmd) {
 			match msg {
-				HudCmd::DismissDialog => {
-					self.in_dialog = false;
+				HudCmd::ShowDialog(dialogue_id) => {
+					self.end_dialogue(bus);
+					self.dialogue = ActiveDialogue::start(dialogue_id, dialogues, progress, bus);
+				}
+
+				HudCmd::AdvanceDialog => {
+					let still_active = self.dialogue.as_mut()
+						.is_some_and(|dialogue| dialogue.advance(dialogues, progress, bus));
+
+					if !still_active {
+						self.end_dialogue(bus);
+					}
 				}
 
-				HudCmd::ShowDialog{..} => {
-					self.in_dialog = true;
+				HudCmd::DismissDialog => {
+					self.end_dialogue(bus);
 				}
 
 				HudCmd::ShowText(text) => {
@@ -60,12 +114,53 @@ impl HudModel {
 					});
 				}
 
-				HudCmd::TransitionWorld{world_name, ..} => {
-					bus.emit(MenuCmd::Play(world_name));
+				HudCmd::ShowChoice(choice) => {
+					if self.choice.is_some() {
+						bus.emit(HudEvent::ChoiceCancelled);
+					}
+
+					self.choice = Some(choice);
+				}
+
+				HudCmd::MoveChoiceSelection(offset) => {
+					if let Some(dialogue) = &mut self.dialogue {
+						dialogue.move_selection(offset);
+					} else if let Some(choice) = &mut self.choice {
+						let num_options = choice.options.len() as i32;
+						if num_options > 0 {
+							choice.selected = (choice.selected as i32 + offset).rem_euclid(num_options) as usize;
+						}
+					}
+				}
+
+				HudCmd::ConfirmChoice => {
+					if let Some(choice) = self.choice.take() {
+						bus.emit(HudEvent::ChoiceMade(choice.selected));
+					}
+				}
+
+				HudCmd::CancelChoice => {
+					if self.choice.take().is_some() {
+						bus.emit(HudEvent::ChoiceCancelled);
+					}
+				}
+
+				HudCmd::ToggleMap => {
+					self.map_open = !self.map_open;
 				}
 			}
 		}
 	}
+
+	pub fn in_dialog(&self) -> bool {
+		self.dialogue.is_some()
+	}
+
+	fn end_dialogue(&mut self, bus: &MessageBus) {
+		if let Some(dialogue) = self.dialogue.take() {
+			bus.emit(HudEvent::DialogueFinished(dialogue.dialogue_id));
+		}
+	}
 }
 
 
@@ -82,11 +177,71 @@ pub struct HudText {
 
 
 
+/// A list of options presented to the player. Whoever asked for it listens for `HudEvent`s.
+#[derive(Debug, Clone)]
+pub struct HudChoice {
+	pub prompt: String,
+	pub options: Vec<String>,
+	pub selected: usize,
+}
+
+
+pub const RESOURCE_FLASH_TIME: f32 = 0.6;
+
+/// How quickly displayed resource values catch up with the real ones, in units per second.
+pub const RESOURCE_DISPLAY_RATE: f32 = 40.0;
+
+/// Smoothed view of a player resource, so changes can be animated rather than popping.
+#[derive(Debug, Default)]
+pub struct ResourceDisplay {
+	// None until the first update, so we don't animate up from zero on start or load.
+	pub value: Option<f32>,
+
+	pub flash_time_remaining: f32,
+	pub flash_is_gain: bool,
+}
+
+impl ResourceDisplay {
+	fn start_flash(&mut self, is_gain: bool) {
+		self.flash_time_remaining = RESOURCE_FLASH_TIME;
+		self.flash_is_gain = is_gain;
+	}
+
+	fn update(&mut self, target: u32, dt: f32) {
+		let target = target as f32;
+		let value = self.value.get_or_insert(target);
+
+		let max_step = RESOURCE_DISPLAY_RATE * dt;
+		*value += (target - *value).clamp(-max_step, max_step);
+
+		self.flash_time_remaining = (self.flash_time_remaining - dt).max(0.0);
+	}
+
+	/// The value to show while animating towards the real one.
+	pub fn displayed(&self) -> u32 {
+		self.value.map_or(0, |value| value.round() as u32)
+	}
+
+	/// 0..1 intensity of the current flash.
+	pub fn flash(&self) -> f32 {
+		self.flash_time_remaining / RESOURCE_FLASH_TIME
+	}
+}
+
+
 
 pub fn handle_hud_commands(ctx: &mut Context, _model: &Model) -> anyhow::Result<()> {
 	if let Some(text) = ctx.console.command("hudtext") {
 		ctx.bus.emit(HudCmd::ShowText(text));
 	}
 
+	if let Some(dialogue_id) = ctx.console.command("dialogue") {
+		if dialogue_id.is_empty() {
+			anyhow::bail!("'dialogue' requires dialogue id argument");
+		}
+
+		ctx.bus.emit(HudCmd::ShowDialog(dialogue_id.trim().into()));
+	}
+
 	Ok(())
 }
\ No newline at end of file
@@ -1,10 +1,25 @@
 use crate::prelude::*;
 use model::*;
 
+/// How far along the view ray objects can be targeted from.
+pub const INTERACTION_DISTANCE: f32 = 0.6;
+
+
+/// A verb that can currently be used on the hovered object, and what the hud should say about it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AvailableVerb {
+	pub verb: Verb,
+	pub prompt: String,
+}
+
+
 #[derive(Debug)]
 pub struct Interactions {
 	pub hovered_object: Option<ObjectId>,
 
+	/// Verbs available on `hovered_object`, in the order they should be displayed.
+	pub verbs: SmallVec<[AvailableVerb; 4]>,
+
 	player_cmd_sub: Subscription<PlayerCmd>,
 }
 
@@ -12,54 +27,92 @@ impl Interactions {
 	pub fn new(message_bus: &MessageBus) -> Self {
 		Interactions {
 			hovered_object: None,
+			verbs: SmallVec::new(),
 
 			player_cmd_sub: message_bus.subscribe(),
 		}
 	}
 
-	pub fn update(&mut self, player: &Player, world: &ProcessedWorld, message_bus: &MessageBus) {
-		if message_bus.poll(&self.player_cmd_sub).any(|msg| msg == PlayerCmd::Interact) {
-			if let Some(object_id) = self.hovered_object
-				&& let Some(object) = world.objects.get(object_id)
-			{
-				log::info!("Interact '{}'", object.name);
-
-				match &object.info {
-					ObjectInfo::Debug => {
-						// TODO(pat.m): uuuhhhhhh
-						message_bus.emit(HudCmd::ShowDialog(()));
-					}
-					ObjectInfo::Ladder {target_world, target_object} => {
-						// TODO(pat.m): make this not a hudcmd?????
-						message_bus.emit(HudCmd::TransitionWorld{
-							world_name: target_world.clone(),
-							object_name: target_object.clone(),
-						});
+	pub fn update(&mut self, player: &Player, world: &ProcessedWorld, progress: &ProgressModel,
+		inventory: &Inventory, item_defs: &ItemDefs, message_bus: &MessageBus)
+	{
+		for cmd in message_bus.poll(&self.player_cmd_sub) {
+			let PlayerCmd::Interact(verb) = cmd;
+
+			let Some(object_id) = self.hovered_object else { continue };
+			let Some(object) = world.objects.get(object_id) else { continue };
+
+			if !self.verbs.iter().any(|available| available.verb == verb) {
+				if verb == Verb::UseItem {
+					message_bus.emit(HudCmd::ShowText("You have nothing to use".into()));
+				}
+
+				continue
+			}
+
+			log::info!("{verb:?} '{}'", object.name);
+
+			for object_verb in object.verbs.iter().filter(|object_verb| object_verb.verb == verb) {
+				if object_verb.condition.as_ref().is_none_or(|condition| progress.state.check(condition)) {
+					emit_effects(&object_verb.effects, message_bus);
+				}
+			}
+
+			match verb {
+				Verb::Use => interact(object_id, object, player, progress, item_defs, message_bus),
+				Verb::UseItem => {
+					if let Some(item) = inventory.selected_item() {
+						use_item(object_id, object, item, progress, item_defs, message_bus);
 					}
+				}
 
-					_ => {}
+				Verb::Take => {
+					message_bus.emit(ProgressCmd::SetObjectFlag(object_id, ObjectFlag::Collected, true));
 				}
+
+				Verb::Light => {
+					let object_state = progress.state.object_state(object_id);
+					match object.as_light() {
+						Some(light) => message_bus.emit(toggle_light_cmd(object_id, light, object_state)),
+						None => message_bus.emit(ProgressCmd::SetObjectFlag(object_id, ObjectFlag::Extinguished, !object_state.extinguished)),
+					}
+				}
+
+				Verb::Pray => pray(object_id, object, player, message_bus),
+
+				Verb::Examine => {}
 			}
 		}
 
 		self.hovered_object = None;
+		self.verbs.clear();
 
-		for (object_id, object) in world.objects.iter() {
-			if object.placement.room_id != player.placement.room_id {
-				continue
-			}
+		let view_ray = WorldRay::from_view(player.placement, PLAYER_HEIGHT, player.pitch);
+		let mut nearest_hit: Option<(f32, ObjectId)> = None;
 
-			// TODO(pat.m): processed_world.distance(player.placement, object.placement)
-			let diff = object.placement.position - player.placement.position;
-			let distance = diff.length();
-			let direction = diff / distance;
+		for segment in world.trace_ray(view_ray, INTERACTION_DISTANCE) {
+			for object_id in world.object_ids_for_room(segment.ray.room_id) {
+				let object = &world.objects[object_id];
+				let Some(distance) = intersect_bounds(&segment.ray, object.placement.position, object.interaction_bounds(), segment.length) else {
+					continue
+				};
 
-			// TODO(pat.m): determine distance and angle based on size of object
-			if distance < 0.5 && player.placement.forward().dot(direction) > 0.7071 {
-				self.hovered_object = Some(object_id);
+				let distance = segment.start_distance + distance;
+				if nearest_hit.is_none_or(|(nearest_distance, _)| distance < nearest_distance) {
+					nearest_hit = Some((distance, object_id));
+				}
+			}
+
+			// Anything hit in this room is nearer than anything in the rooms beyond it
+			if nearest_hit.is_some() {
 				break
 			}
 		}
+
+		if let Some((_, object_id)) = nearest_hit {
+			self.hovered_object = Some(object_id);
+			self.verbs = available_verbs(object_id, &world.objects[object_id], progress, inventory, item_defs);
+		}
 	}
 
 	pub fn can_interact(&self) -> bool {
@@ -67,3 +120,322 @@ impl Interactions {
 	}
 }
 
+
+/// Distance along the ray at which it enters the bounds around `center`, if it does so within `max_distance`.
+fn intersect_bounds(ray: &WorldRay, center: Vec2, bounds: InteractionBounds, max_distance: f32) -> Option<f32> {
+	let mut entry = 0.0f32;
+	let mut exit = max_distance;
+
+	// Horizontal - solve |origin + direction*t - center| = radius
+	let offset = ray.origin - center;
+	let a = ray.direction.dot(ray.direction);
+	let c = offset.dot(offset) - bounds.radius * bounds.radius;
+
+	if a > 0.00001 {
+		let b = offset.dot(ray.direction);
+		let discriminant = b*b - a*c;
+		if discriminant < 0.0 {
+			return None
+		}
+
+		let root = discriminant.sqrt();
+		entry = entry.max((-b - root) / a);
+		exit = exit.min((-b + root) / a);
+
+	} else if c > 0.0 {
+		return None
+	}
+
+	// Vertical
+	if ray.rise.abs() > 0.00001 {
+		let bottom = (bounds.bottom - ray.height) / ray.rise;
+		let top = (bounds.top - ray.height) / ray.rise;
+		entry = entry.max(bottom.min(top));
+		exit = exit.min(bottom.max(top));
+
+	} else if ray.height < bounds.bottom || ray.height > bounds.top {
+		return None
+	}
+
+	(entry <= exit).then_some(entry)
+}
+
+
+fn available_verbs(object_id: ObjectId, object: &Object, progress: &ProgressModel, inventory: &Inventory, item_defs: &ItemDefs) -> SmallVec<[AvailableVerb; 4]> {
+	let name = &object.name;
+	let object_state = progress.state.object_state(object_id);
+
+	let mut verbs = SmallVec::<[AvailableVerb; 4]>::new();
+	let mut add = |verb, prompt| verbs.push(AvailableVerb { verb, prompt });
+
+	// Verbs implied by the kind of object
+	match &object.info {
+		ObjectInfo::Debug => add(Verb::Use, format!("Frob '{name}'")),
+		ObjectInfo::Ladder{target_world, ..} => add(Verb::Use, format!("To {target_world}")),
+		ObjectInfo::Chest{..} => add(Verb::Use, format!("Open '{name}'")),
+		ObjectInfo::Door{..} => match object_state.opened {
+			false => add(Verb::Use, format!("Open '{name}'")),
+			true => add(Verb::Use, format!("Close '{name}'")),
+		},
+		ObjectInfo::Altar{waypoint, ..} => {
+			add(Verb::Pray, format!("Kneel at '{name}'"));
+
+			if let Some(waypoint) = waypoint {
+				match object_state.attuned {
+					true => add(Verb::Use, format!("Travel from {waypoint}")),
+					false => add(Verb::Use, format!("Attune to {waypoint}")),
+				}
+			}
+		}
+		ObjectInfo::Npc{..} => add(Verb::Use, format!("Talk to '{name}'")),
+
+		ObjectInfo::Light(light) => match is_light_on(light, object_state) {
+			false => add(Verb::Light, format!("Light '{name}'")),
+			true => add(Verb::Light, format!("Extinguish '{name}'")),
+		},
+	}
+
+	if let Some(item) = inventory.selected_item() {
+		add(Verb::UseItem, format!("Use {} on '{name}'", item_defs.name(item)));
+	}
+
+	// Authored verbs either add to the above or override their prompt
+	for object_verb in object.verbs.iter() {
+		if !object_verb.condition.as_ref().is_none_or(|condition| progress.state.check(condition)) {
+			continue
+		}
+
+		let prompt = object_verb.prompt.clone()
+			.unwrap_or_else(|| format!("{} '{name}'", object_verb.verb.label()));
+
+		match verbs.iter_mut().find(|available| available.verb == object_verb.verb) {
+			Some(available) => if object_verb.prompt.is_some() {
+				available.prompt = prompt;
+			}
+
+			None => verbs.push(AvailableVerb { verb: object_verb.verb, prompt }),
+		}
+	}
+
+	// Keep display order stable regardless of how verbs were authored
+	verbs.sort_by_key(|available| Verb::ALL.iter().position(|&verb| verb == available.verb));
+	verbs
+}
+
+
+
+fn interact(object_id: ObjectId, object: &Object, player: &Player, progress: &ProgressModel, item_defs: &ItemDefs, message_bus: &MessageBus) {
+	let object_state = progress.state.object_state(object_id);
+
+	match &object.info {
+		ObjectInfo::Debug => {
+			message_bus.emit(Effect::ShowDialog { dialogue: "debug".into() });
+		}
+
+		ObjectInfo::Npc{dialogue} => {
+			message_bus.emit(Effect::ShowDialog { dialogue: dialogue.clone() });
+		}
+
+		ObjectInfo::Ladder {target_world, target_object} => {
+			message_bus.emit(Effect::TransitionWorld {
+				world_name: target_world.clone(),
+				object_name: target_object.clone(),
+			});
+		}
+
+		ObjectInfo::Chest{content, salt} => {
+			if object_state.opened {
+				message_bus.emit(HudCmd::ShowText("It's empty".into()));
+				return
+			}
+
+			message_bus.emit(ProgressCmd::SetObjectFlag(object_id, ObjectFlag::Opened, true));
+
+			for item in content {
+				message_bus.emit(InventoryCmd::Give(item.clone(), 1));
+			}
+
+			if *salt > 0 {
+				message_bus.emit(ResourceCmd::Add(Resource::Salt, *salt as i32));
+			}
+
+			let mut found: Vec<_> = content.iter().map(|item| item_defs.name(item).to_string()).collect();
+			if *salt > 0 {
+				found.push(format!("{salt} salt"));
+			}
+
+			let message = match found.is_empty() {
+				true => "It's empty".to_string(),
+				false => format!("Found {}", found.join(", ")),
+			};
+
+			message_bus.emit(HudCmd::ShowText(message));
+		}
+
+		ObjectInfo::Door{key_item, blood_cost} => {
+			match key_item {
+				Some(key_item) if !object_state.opened => {
+					message_bus.emit(HudCmd::ShowText(format!("It's locked. Perhaps {} would help", item_defs.name(key_item))));
+				}
+
+				_ if *blood_cost > 0 && !object_state.opened => {
+					if player.blood > *blood_cost {
+						message_bus.emit(ResourceCmd::Spend(Resource::Blood, *blood_cost));
+						message_bus.emit(ProgressCmd::SetObjectFlag(object_id, ObjectFlag::Opened, true));
+					} else {
+						message_bus.emit(HudCmd::ShowText(format!("The seal demands {blood_cost} blood")));
+					}
+				}
+
+				_ => message_bus.emit(ProgressCmd::SetObjectFlag(object_id, ObjectFlag::Opened, !object_state.opened)),
+			}
+		}
+
+		ObjectInfo::Altar{waypoint: Some(waypoint), ..} => {
+			if object_state.attuned {
+				message_bus.emit(TravelCmd::Open(object_id));
+			} else {
+				message_bus.emit(ProgressCmd::SetObjectFlag(object_id, ObjectFlag::Attuned, true));
+				message_bus.emit(HudCmd::ShowText(format!("You are attuned to {waypoint}")));
+			}
+		}
+
+		_ => {}
+	}
+}
+
+fn pray(object_id: ObjectId, object: &Object, player: &Player, message_bus: &MessageBus) {
+	match &object.info {
+		ObjectInfo::Altar{ritual, ..} if !ritual.is_empty() => {
+			message_bus.emit(RitualCmd::Begin(object_id));
+		}
+
+		ObjectInfo::Altar{..} => {
+			if player.blood < MAX_BLOOD {
+				message_bus.emit(ResourceCmd::Add(Resource::Blood, MAX_BLOOD as i32));
+				message_bus.emit(HudCmd::ShowText("Your blood is restored".into()));
+			}
+		}
+
+		_ => {}
+	}
+}
+
+fn use_item(object_id: ObjectId, object: &Object, item: &ItemId, progress: &ProgressModel, item_defs: &ItemDefs, message_bus: &MessageBus) {
+	let object_state = progress.state.object_state(object_id);
+
+	match &object.info {
+		ObjectInfo::Door{key_item: Some(key_item), ..} if key_item == item && !object_state.opened => {
+			message_bus.emit(ProgressCmd::SetObjectFlag(object_id, ObjectFlag::Opened, true));
+			message_bus.emit(InventoryCmd::Take(item.clone(), 1));
+			message_bus.emit(HudCmd::ShowText(format!("Used {}", item_defs.name(item))));
+		}
+
+		_ => {
+			message_bus.emit(HudCmd::ShowText("Nothing happens".into()));
+		}
+	}
+}
+
+
+
+#[test]
+fn authored_verbs_depend_on_condition() {
+	let mut world = World::new();
+	let spawn = world.player_spawn;
+
+	let mut examine = ObjectVerb::new(Verb::Examine);
+	examine.prompt = Some("Read the inscription".into());
+	examine.condition = Some(Condition::Flag("can-read".into()));
+	examine.effects.push(Effect::SetFlag { name: "read".into(), value: true });
+
+	let altar_id = world.objects.insert(Object {
+		name: "altar".into(),
+		placement: Placement { position: spawn.position + spawn.forward() * 0.3, .. spawn },
+		info: ObjectInfo::Altar { ritual: Ritual::default(), waypoint: None },
+		verbs: vec![examine],
+	});
+
+	let (source_model, bus, mut model) = test_model(&world);
+
+	step_with_pressed(&mut model, &source_model, &bus, None);
+	assert_eq!(model.interactions.hovered_object, Some(altar_id));
+	assert_eq!(model.interactions.verbs.iter().map(|available| available.verb).collect::<Vec<_>>(), [Verb::Pray]);
+
+	// Unavailable verbs do nothing
+	step_with_pressed(&mut model, &source_model, &bus, Some(Action::Examine));
+	step_with_pressed(&mut model, &source_model, &bus, None);
+	assert!(!model.progress.state.flag("read"));
+
+	bus.emit(ProgressCmd::SetFlag("can-read".into(), true));
+	step_with_pressed(&mut model, &source_model, &bus, None);
+	assert_eq!(model.interactions.verbs[1], AvailableVerb { verb: Verb::Examine, prompt: "Read the inscription".into() });
+
+	step_with_pressed(&mut model, &source_model, &bus, Some(Action::Examine));
+	step_with_pressed(&mut model, &source_model, &bus, None);
+	assert!(model.progress.state.flag("read"));
+}
+
+#[test]
+fn targets_nearest_object_through_aperture() {
+	let mut world = World::new();
+	world.geometry = WorldGeometry::new_square(1.0);
+
+	let near_room = world.geometry.first_room();
+	let far_room = world.geometry.insert_room_from_positions(&[
+		Vec2::new(4.5, -0.5),
+		Vec2::new(4.5,  0.5),
+		Vec2::new(5.5,  0.5),
+		Vec2::new(5.5, -0.5),
+	]);
+
+	world.player_spawn = Placement { room_id: near_room, position: Vec2::zero(), yaw: 0.0 };
+	let forward = world.player_spawn.forward();
+	world.player_spawn.position = forward * 0.2;
+
+	// Connect the wall the player is facing to any wall of the other room
+	let near_wall = world.geometry.room_walls(near_room)
+		.max_by(|a, b| world.geometry.wall_center(*a).dot(forward).total_cmp(&world.geometry.wall_center(*b).dot(forward)))
+		.unwrap();
+
+	let far_wall = world.geometry.room_walls(far_room).next().unwrap();
+	world.geometry.connect_wall(near_wall, far_wall).unwrap();
+
+	// Just the other side of the aperture
+	let far_wall_center = world.geometry.wall_center(far_wall);
+	let far_room_center = Vec2::new(5.0, 0.0);
+	let far_position = far_wall_center + (far_room_center - far_wall_center).normalize() * 0.2;
+
+	let far_id = world.objects.insert(Object {
+		name: "far".into(),
+		placement: Placement { room_id: far_room, position: far_position, yaw: 0.0 },
+		info: ObjectInfo::Debug,
+		verbs: Vec::new(),
+	});
+
+	let (source_model, bus, mut model) = test_model(&world);
+
+	model.step(&source_model, &TickInput::default(), &bus);
+	assert_eq!(model.interactions.hovered_object, Some(far_id));
+
+	// Looking up over it
+	model.player.pitch = -0.5;
+	model.step(&source_model, &TickInput::default(), &bus);
+	assert_eq!(model.interactions.hovered_object, None);
+	model.player.pitch = 0.0;
+
+	// A nearer object in the same line takes priority
+	let mut world = world;
+	let near_id = world.objects.insert(Object {
+		name: "near".into(),
+		placement: Placement { room_id: near_room, position: forward * 0.4, yaw: 0.0 },
+		info: ObjectInfo::Debug,
+		verbs: Vec::new(),
+	});
+
+	let source_model = SourceModel::from_world(world.clone());
+	bus.emit(WorldChangedEvent);
+	model.step(&source_model, &TickInput::default(), &bus);
+	model.step(&source_model, &TickInput::default(), &bus);
+	assert_eq!(model.interactions.hovered_object, Some(near_id));
+}
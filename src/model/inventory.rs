@@ -0,0 +1,227 @@
+use crate::prelude::*;
+use model::*;
+
+
+#[derive(Debug, Clone)]
+pub enum InventoryCmd {
+	Give(ItemId, u32),
+	Take(ItemId, u32),
+
+	SelectNext,
+	SelectPrevious,
+}
+
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ItemStack {
+	pub item: ItemId,
+	pub count: u32,
+}
+
+
+/// Serializable part of the inventory. This is what ends up in save games.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Inventory {
+	pub stacks: Vec<ItemStack>,
+	pub selected: usize,
+}
+
+impl Inventory {
+	pub fn count(&self, item: &ItemId) -> u32 {
+		self.stacks.iter()
+			.filter(|stack| stack.item == *item)
+			.map(|stack| stack.count)
+			.sum()
+	}
+
+	pub fn contains(&self, item: &ItemId) -> bool {
+		self.count(item) > 0
+	}
+
+	pub fn selected_item(&self) -> Option<&ItemId> {
+		self.stacks.get(self.selected).map(|stack| &stack.item)
+	}
+
+	pub fn give(&mut self, item: ItemId, count: u32, item_defs: &ItemDefs) {
+		if count == 0 {
+			return
+		}
+
+		if item_defs.is_stackable(&item) {
+			if let Some(stack) = self.stacks.iter_mut().find(|stack| stack.item == item) {
+				stack.count += count;
+			} else {
+				self.stacks.push(ItemStack { item, count });
+			}
+
+		} else {
+			for _ in 0..count {
+				self.stacks.push(ItemStack { item: item.clone(), count: 1 });
+			}
+		}
+	}
+
+	/// Removes up to `count` of an item, returning how many were actually removed.
+	pub fn take(&mut self, item: &ItemId, count: u32) -> u32 {
+		let mut remaining = count;
+
+		for stack in self.stacks.iter_mut().rev() {
+			if remaining == 0 {
+				break
+			}
+
+			if stack.item == *item {
+				let taken = stack.count.min(remaining);
+				stack.count -= taken;
+				remaining -= taken;
+			}
+		}
+
+		self.stacks.retain(|stack| stack.count > 0);
+		self.selected = self.selected.min(self.stacks.len().saturating_sub(1));
+
+		count - remaining
+	}
+
+	fn select_offset(&mut self, offset: isize) {
+		let num_stacks = self.stacks.len() as isize;
+		if num_stacks > 0 {
+			self.selected = (self.selected as isize + offset).rem_euclid(num_stacks) as usize;
+		}
+	}
+}
+
+
+#[derive(Debug)]
+pub struct InventoryModel {
+	pub inventory: Inventory,
+
+	inventory_cmd_sub: Subscription<InventoryCmd>,
+}
+
+impl InventoryModel {
+	pub fn new(bus: &MessageBus) -> Self {
+		InventoryModel {
+			inventory: Inventory::default(),
+			inventory_cmd_sub: bus.subscribe(),
+		}
+	}
+
+	pub fn update(&mut self, item_defs: &ItemDefs, bus: &MessageBus) {
+		for cmd in bus.poll_consume(&self.inventory_cmd_sub) {
+			match cmd {
+				InventoryCmd::Give(item, count) => {
+					log::info!("Received {count}x '{item}'");
+					self.inventory.give(item, count, item_defs);
+				}
+
+				InventoryCmd::Take(item, count) => {
+					let taken = self.inventory.take(&item, count);
+					if taken < count {
+						log::warn!("Tried to take {count}x '{item}' but only had {taken}");
+					}
+				}
+
+				InventoryCmd::SelectNext => self.inventory.select_offset(1),
+				InventoryCmd::SelectPrevious => self.inventory.select_offset(-1),
+			}
+		}
+	}
+}
+
+
+
+pub fn handle_inventory_commands(ctx: &mut Context, _model: &Model) -> anyhow::Result<()> {
+	if let Some(args) = ctx.console.command("give") {
+		let (item, count) = args.split_once(' ').unwrap_or((&args, "1"));
+		if item.is_empty() {
+			anyhow::bail!("'give' requires item id argument");
+		}
+
+		let count = count.trim().parse().with_context(|| format!("Invalid item count '{count}'"))?;
+		ctx.bus.emit(InventoryCmd::Give(item.into(), count));
+	}
+
+	Ok(())
+}
+
+
+
+#[cfg(test)]
+fn world_with_object_in_front(info: ObjectInfo) -> (World, ObjectId) {
+	let mut world = World::new();
+	let spawn = world.player_spawn;
+
+	let object_id = world.objects.insert(Object {
+		name: "test object".into(),
+		placement: Placement {
+			position: spawn.position + spawn.forward() * 0.3,
+			.. spawn
+		},
+		info,
+		verbs: Vec::new(),
+	});
+
+	(world, object_id)
+}
+
+#[test]
+fn chest_gives_items_once() {
+	let (world, chest_id) = world_with_object_in_front(ObjectInfo::Chest {
+		content: vec!["rusty-key".into(), "candle".into()],
+		salt: 0,
+	});
+
+	let (source_model, bus, mut model) = test_model(&world);
+
+	step_with_pressed(&mut model, &source_model, &bus, None);
+	assert_eq!(model.interactions.hovered_object, Some(chest_id));
+
+	step_with_pressed(&mut model, &source_model, &bus, Some(Action::Interact));
+	step_with_pressed(&mut model, &source_model, &bus, Some(Action::Interact));
+	step_with_pressed(&mut model, &source_model, &bus, None);
+
+	let inventory = &model.inventory.inventory;
+	assert_eq!(inventory.count(&"rusty-key".into()), 1);
+	assert_eq!(inventory.count(&"candle".into()), 1);
+	assert!(model.progress.state.object_state(chest_id).opened);
+}
+
+#[test]
+fn key_unlocks_door() {
+	let (world, door_id) = world_with_object_in_front(ObjectInfo::Door {
+		key_item: Some("rusty-key".into()),
+		blood_cost: 0,
+	});
+
+	let (source_model, bus, mut model) = test_model(&world);
+
+	bus.emit(InventoryCmd::Give("candle".into(), 1));
+	bus.emit(InventoryCmd::Give("rusty-key".into(), 1));
+	step_with_pressed(&mut model, &source_model, &bus, None);
+
+	// Interacting with a locked door or using the wrong item does nothing
+	step_with_pressed(&mut model, &source_model, &bus, Some(Action::Interact));
+	step_with_pressed(&mut model, &source_model, &bus, Some(Action::UseItem));
+	step_with_pressed(&mut model, &source_model, &bus, None);
+	assert!(!model.progress.state.object_state(door_id).opened);
+
+	step_with_pressed(&mut model, &source_model, &bus, Some(Action::NextItem));
+	step_with_pressed(&mut model, &source_model, &bus, None);
+	assert_eq!(model.inventory.inventory.selected_item(), Some(&"rusty-key".into()));
+
+	step_with_pressed(&mut model, &source_model, &bus, Some(Action::UseItem));
+	step_with_pressed(&mut model, &source_model, &bus, None);
+	step_with_pressed(&mut model, &source_model, &bus, None);
+
+	assert!(model.progress.state.object_state(door_id).opened);
+	assert!(!model.inventory.inventory.contains(&"rusty-key".into()));
+
+	// Opened doors stay around, and can be closed again without the key
+	assert!(model.processed_world.objects.contains_key(door_id));
+	assert_eq!(model.interactions.hovered_object, Some(door_id));
+
+	step_with_pressed(&mut model, &source_model, &bus, Some(Action::Interact));
+	step_with_pressed(&mut model, &source_model, &bus, None);
+	assert!(!model.progress.state.object_state(door_id).opened);
+}
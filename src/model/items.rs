@@ -0,0 +1,106 @@
+use crate::prelude::*;
+
+use std::collections::BTreeMap;
+
+
+pub const ITEM_DEFS_PATH: &str = "items.json";
+
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct ItemId(pub String);
+
+impl ItemId {
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+}
+
+impl From<&str> for ItemId {
+	fn from(value: &str) -> Self {
+		ItemId(value.to_string())
+	}
+}
+
+impl std::fmt::Display for ItemId {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ItemDef {
+	pub name: String,
+
+	#[serde(default)]
+	pub description: String,
+
+	#[serde(default)]
+	pub icon: ItemIcon,
+
+	/// Whether multiple of this item share an inventory slot.
+	#[serde(default)]
+	pub stackable: bool,
+
+	#[serde(default)]
+	pub tags: Vec<String>,
+}
+
+impl ItemDef {
+	pub fn has_tag(&self, tag: &str) -> bool {
+		self.tags.iter().any(|t| t == tag)
+	}
+}
+
+
+// TODO(pat.m): image icons once UiPainter can draw textures
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ItemIcon {
+	pub glyph: String,
+	pub color: Color,
+}
+
+impl Default for ItemIcon {
+	fn default() -> Self {
+		ItemIcon {
+			glyph: "?".into(),
+			color: Color::grey(0.5),
+		}
+	}
+}
+
+
+/// All known items, keyed by id. Loaded from `items.json`.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct ItemDefs {
+	pub items: BTreeMap<ItemId, ItemDef>,
+}
+
+impl ItemDefs {
+	pub fn load(vfs: &vfs::Vfs) -> ItemDefs {
+		match vfs.load_json_resource(ITEM_DEFS_PATH) {
+			Ok(item_defs) => item_defs,
+			Err(err) => {
+				log::error!("Failed to load item definitions from '{ITEM_DEFS_PATH}': {err}");
+				ItemDefs::default()
+			}
+		}
+	}
+
+	pub fn get(&self, item_id: &ItemId) -> Option<&ItemDef> {
+		self.items.get(item_id)
+	}
+
+	/// Display name for an item, falling back to the id for unknown items.
+	pub fn name<'s>(&'s self, item_id: &'s ItemId) -> &'s str {
+		self.get(item_id)
+			.map(|def| def.name.as_str())
+			.unwrap_or(item_id.as_str())
+	}
+
+	pub fn is_stackable(&self, item_id: &ItemId) -> bool {
+		self.get(item_id).is_some_and(|def| def.stackable)
+	}
+}
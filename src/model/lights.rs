@@ -0,0 +1,224 @@
+use crate::prelude::*;
+use model::*;
+
+use slotmap::{Key, SecondaryMap};
+use std::f32::consts::TAU;
+
+
+/// How long lights take to come on fully, or to go out, once lit or snuffed.
+pub const LIGHT_FADE_TIME: f32 = 0.3;
+
+
+/// Lights that start lit are put out with `ObjectFlag::Extinguished`, and lights that start unlit are lit with `ObjectFlag::Lit`,
+/// so that neither need any progress state until the player touches them.
+pub fn is_light_on(light: &LightObject, state: ObjectState) -> bool {
+	match light.starts_lit {
+		true => !state.extinguished,
+		false => state.lit,
+	}
+}
+
+/// Lights a light that's out, or snuffs one that's lit.
+pub fn toggle_light_cmd(object_id: ObjectId, light: &LightObject, state: ObjectState) -> ProgressCmd {
+	match light.starts_lit {
+		true => ProgressCmd::SetObjectFlag(object_id, ObjectFlag::Extinguished, !state.extinguished),
+		false => ProgressCmd::SetObjectFlag(object_id, ObjectFlag::Lit, !state.lit),
+	}
+}
+
+
+/// How a light looks right now, with animation and fading applied.
+#[derive(Debug, Copy, Clone)]
+pub struct LightState {
+	pub color: Color,
+
+	/// Zero once the light has gone out.
+	pub power: f32,
+
+	/// 1.0 when lit, 0.0 when out, and in between while fading.
+	pub brightness: f32,
+}
+
+
+/// Animates lights every tick, and fades them in and out as they're lit and snuffed.
+/// Keyed by the same `ObjectId`s as the processed world, so the view can look up lights it baked into rooms without rebuilding them.
+#[derive(Debug, Default)]
+pub struct LightModel {
+	pub lights: SecondaryMap<ObjectId, LightState>,
+
+	/// Seconds since the model was created. Drives animation.
+	pub time: f32,
+}
+
+impl LightModel {
+	pub fn new() -> Self {
+		LightModel::default()
+	}
+
+	pub fn update(&mut self, world: &ProcessedWorld, dt: f32) {
+		self.time += dt;
+
+		let runtime_objects = world.runtime_objects();
+		self.lights.retain(|object_id, _| runtime_objects.get(object_id).is_some_and(|(object, _)| object.as_light().is_some()));
+
+		for (object_id, object, state) in runtime_objects.iter() {
+			let Some(light) = object.as_light() else { continue };
+
+			let target_brightness = if state.light_on { 1.0 } else { 0.0 };
+
+			// New lights start out as they are, so everything doesn't fade in when a world is loaded.
+			let light_state = self.lights.entry(object_id).unwrap()
+				.or_insert(LightState { color: light.color, power: 0.0, brightness: target_brightness });
+
+			let fade_step = dt / LIGHT_FADE_TIME;
+			light_state.brightness = match light_state.brightness < target_brightness {
+				true => (light_state.brightness + fade_step).min(target_brightness),
+				false => (light_state.brightness - fade_step).max(target_brightness),
+			};
+
+			let (color, power_scale) = evaluate_light_animation(&light.animation, light.color, self.time, light_phase(object_id));
+			light_state.color = color;
+			light_state.power = light.power * power_scale * light_state.brightness;
+		}
+	}
+
+	pub fn light(&self, object_id: ObjectId) -> Option<&LightState> {
+		self.lights.get(object_id)
+	}
+}
+
+
+/// Returns the color of a light at `time`, and how much of its power it has.
+/// `phase` offsets irregular animations so that neighbouring lights don't flicker in lockstep.
+pub fn evaluate_light_animation(animation: &LightAnimation, color: Color, time: f32, phase: f32) -> (Color, f32) {
+	match animation {
+		LightAnimation::Steady => (color, 1.0),
+
+		&LightAnimation::Flicker { amount, speed } => {
+			// Sines at unrelated frequencies look random enough, and don't need any state
+			let t = time * speed + phase;
+			let noise = t.sin() * 0.5 + (t * 2.3 + 1.7).sin() * 0.3 + (t * 5.1 + 0.3).sin() * 0.2;
+			let noise = noise * 0.5 + 0.5;
+
+			(color, 1.0 - amount.clamp(0.0, 1.0) * noise)
+		}
+
+		&LightAnimation::Pulse { period, min_power } => {
+			if period <= 0.0 {
+				return (color, 1.0)
+			}
+
+			let wave = (TAU * time / period).cos() * 0.5 + 0.5;
+			let min_power = min_power.clamp(0.0, 1.0);
+
+			(color, min_power + (1.0 - min_power) * wave)
+		}
+
+		LightAnimation::ColorCycle { colors, period } => {
+			if colors.is_empty() || *period <= 0.0 {
+				return (color, 1.0)
+			}
+
+			let t = time / period;
+			let index = t.floor() as usize % colors.len();
+			let next_index = (index + 1) % colors.len();
+
+			(lerp_color(colors[index], colors[next_index], t.fract()), 1.0)
+		}
+	}
+}
+
+fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+	let [r0, g0, b0, _] = from.to_array();
+	let [r1, g1, b1, _] = to.to_array();
+
+	Color::rgb(
+		r0 + (r1 - r0) * t,
+		g0 + (g1 - g0) * t,
+		b0 + (b1 - b0) * t,
+	)
+}
+
+fn light_phase(object_id: ObjectId) -> f32 {
+	// Low bits are the slot index
+	let index = object_id.data().as_ffi() as u32;
+	(index as f32 * 0.618034).fract() * TAU
+}
+
+
+
+#[test]
+fn light_animations_stay_in_range() {
+	let white = Color::white();
+
+	for tick in 0..600 {
+		let time = tick as f32 * SIM_DT;
+
+		let (_, power) = evaluate_light_animation(&LightAnimation::Flicker { amount: 0.4, speed: 8.0 }, white, time, 1.0);
+		assert!((0.6..=1.0).contains(&power), "flicker power {power} out of range");
+
+		let (_, power) = evaluate_light_animation(&LightAnimation::Pulse { period: 2.0, min_power: 0.25 }, white, time, 0.0);
+		assert!((0.25..=1.0).contains(&power), "pulse power {power} out of range");
+	}
+
+	let red = Color::rgb(1.0, 0.0, 0.0);
+	let blue = Color::rgb(0.0, 0.0, 1.0);
+	let cycle = LightAnimation::ColorCycle { colors: vec![red, blue], period: 2.0 };
+
+	let color_at = |time| evaluate_light_animation(&cycle, white, time, 0.0).0.to_array();
+
+	assert_eq!(color_at(0.0), red.to_array());
+	assert_eq!(color_at(1.0), [0.5, 0.0, 0.5, 1.0]);
+	assert_eq!(color_at(2.0), blue.to_array());
+	assert_eq!(color_at(4.0), red.to_array());
+}
+
+#[test]
+fn lighting_candles_fades_without_rebuilding_world() {
+	let mut world = World::new();
+	let candle_id = world.objects.insert(Object {
+		name: "candle".into(),
+		placement: world.player_spawn,
+		info: ObjectInfo::Light(LightObject {
+			color: Color::white(),
+			height: 0.5,
+			power: 2.0,
+			radius: 2.0,
+			starts_lit: false,
+			animation: LightAnimation::Steady,
+		}),
+		verbs: Vec::new(),
+	});
+
+	let (source_model, bus, mut model) = test_model(&world);
+	let change_sub = bus.subscribe::<ProcessedWorldChangedEvent>();
+
+	model.step(&source_model, &TickInput::default(), &bus);
+	bus.poll_consume(&change_sub).for_each(drop);
+	assert_eq!(model.lights.light(candle_id).unwrap().power, 0.0);
+
+	let light = world.objects[candle_id].as_light().unwrap();
+	bus.emit(toggle_light_cmd(candle_id, light, model.progress.state.object_state(candle_id)));
+	model.step(&source_model, &TickInput::default(), &bus);
+
+	let brightness = model.lights.light(candle_id).unwrap().brightness;
+	assert!(brightness > 0.0 && brightness < 1.0);
+	assert!(!bus.any(&change_sub));
+
+	for _ in 0..(LIGHT_FADE_TIME / SIM_DT) as u32 + 1 {
+		model.step(&source_model, &TickInput::default(), &bus);
+	}
+
+	let candle = model.lights.light(candle_id).unwrap();
+	assert_eq!((candle.power, candle.brightness), (2.0, 1.0));
+	assert!(!bus.any(&change_sub));
+
+	// And out again
+	bus.emit(toggle_light_cmd(candle_id, light, model.progress.state.object_state(candle_id)));
+	for _ in 0..(LIGHT_FADE_TIME / SIM_DT) as u32 + 2 {
+		model.step(&source_model, &TickInput::default(), &bus);
+	}
+
+	assert_eq!(model.lights.light(candle_id).unwrap().power, 0.0);
+	assert!(model.processed_world.objects.contains_key(candle_id));
+}
@@ -0,0 +1,202 @@
+use crate::prelude::*;
+use model::*;
+
+
+/// How far away the player can mark.
+pub const MARKER_REACH: f32 = 1.5;
+
+/// Chalk the player starts with. Each marker uses one, and rubbing one out gives it back.
+pub const STARTING_CHALK: u32 = 12;
+
+/// Marking within this distance of an existing marker rubs it out instead.
+const MARKER_RUB_OUT_DISTANCE: f32 = 0.2;
+
+/// Wall markers are kept this far out from the wall, so they stay inside their room.
+const MARKER_WALL_OFFSET: f32 = 0.01;
+
+
+#[derive(Debug, Clone)]
+pub enum MarkerCmd {
+	/// Mark whatever the player is looking at, or rub out the marker that's already there.
+	Mark,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarkerEvent {
+	Placed(Marker),
+	RubbedOut(Marker),
+}
+
+
+#[derive(Debug, Copy, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum MarkerSurface {
+	Floor,
+
+	Wall {
+		/// Points out of the wall, into the marker's room.
+		normal: Vec2,
+
+		/// Height above the floor.
+		height: f32,
+	},
+}
+
+
+/// Chalk mark left by the player to find their way.
+#[derive(Debug, Copy, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Marker {
+	/// Source room space, so that markers survive changes to how rooms are processed.
+	pub location: Location,
+	pub surface: MarkerSurface,
+}
+
+
+#[derive(Debug)]
+pub struct MarkerModel {
+	marker_cmd_sub: Subscription<MarkerCmd>,
+}
+
+impl MarkerModel {
+	pub fn new(bus: &MessageBus) -> Self {
+		MarkerModel {
+			marker_cmd_sub: bus.subscribe(),
+		}
+	}
+
+	pub fn update(&mut self, player: &mut Player, world: &ProcessedWorld, progress: &mut ProgressModel, bus: &MessageBus) {
+		for cmd in bus.poll_consume(&self.marker_cmd_sub) {
+			match cmd {
+				MarkerCmd::Mark => self.mark(player, world, progress, bus),
+			}
+		}
+	}
+
+	fn mark(&mut self, player: &mut Player, world: &ProcessedWorld, progress: &mut ProgressModel, bus: &MessageBus) {
+		if player.free_cam {
+			return
+		}
+
+		let Some(marker) = find_marker_target(player, world) else {
+			bus.emit(HudCmd::ShowText("There's nothing to mark".into()));
+			return
+		};
+
+		let markers = &mut progress.state.markers;
+
+		let existing = markers.iter().position(|existing| {
+			existing.location.room_id == marker.location.room_id
+				&& std::mem::discriminant(&existing.surface) == std::mem::discriminant(&marker.surface)
+				&& (existing.location.position - marker.location.position).length() < MARKER_RUB_OUT_DISTANCE
+		});
+
+		if let Some(index) = existing {
+			let marker = markers.remove(index);
+			player.chalk += 1;
+			bus.emit(MarkerEvent::RubbedOut(marker));
+			return
+		}
+
+		if player.chalk == 0 {
+			bus.emit(HudCmd::ShowText("You're out of chalk".into()));
+			return
+		}
+
+		player.chalk -= 1;
+		markers.push(marker);
+		bus.emit(MarkerEvent::Placed(marker));
+	}
+}
+
+
+/// Finds the floor or wall the player is looking at, and returns a marker for it in source room space.
+/// Ceilings are out of reach.
+fn find_marker_target(player: &Player, world: &ProcessedWorld) -> Option<Marker> {
+	let view_ray = WorldRay::from_view(player.placement, PLAYER_HEIGHT, player.pitch);
+	let segments = world.trace_ray(view_ray, MARKER_REACH);
+
+	let last_segment = segments.last()?;
+	if last_segment.start_distance + last_segment.length >= MARKER_REACH {
+		return None
+	}
+
+	let geometry = world.geometry();
+	let room_id = last_segment.ray.room_id;
+	let (position, height) = last_segment.ray.point_at(last_segment.length);
+
+	let (position, surface) = if height < 0.01 {
+		(position, MarkerSurface::Floor)
+
+	} else {
+		let wall_id = geometry.room_walls(room_id)
+			.find(|&wall_id| {
+				let (wall_start, wall_end) = geometry.wall_vertices(wall_id);
+				let wall_direction = (wall_end - wall_start).normalize();
+				wall_direction.wedge(position - wall_start).abs() < 0.01
+			})?;
+
+		let (wall_start, wall_end) = geometry.wall_vertices(wall_id);
+		let normal = -(wall_end - wall_start).normalize().perp();
+
+		(position + normal * MARKER_WALL_OFFSET, MarkerSurface::Wall { normal, height })
+	};
+
+	Some(Marker {
+		location: world.to_source_location(Location { room_id, position }),
+		surface,
+	})
+}
+
+
+
+#[cfg(test)]
+fn press_mark(model: &mut Model, source_model: &SourceModel, bus: &MessageBus) {
+	let mut input = TickInput::default();
+	input.held.insert(Action::Mark);
+	input.pressed.insert(Action::Mark);
+	model.step(source_model, &input, bus);
+	model.step(source_model, &TickInput::default(), bus);
+}
+
+#[test]
+fn marking_uses_chalk_and_rubbing_out_returns_it() {
+	let world = World::new();
+	let (source_model, bus, mut model) = test_model(&world);
+
+	// Look at the floor just in front
+	model.player.pitch = 1.2;
+	press_mark(&mut model, &source_model, &bus);
+
+	assert_eq!(model.progress.state.markers.len(), 1);
+	assert_eq!(model.progress.state.markers[0].surface, MarkerSurface::Floor);
+	assert_eq!(model.player.chalk, STARTING_CHALK - 1);
+
+	// Same spot again
+	press_mark(&mut model, &source_model, &bus);
+	assert!(model.progress.state.markers.is_empty());
+	assert_eq!(model.player.chalk, STARTING_CHALK);
+
+	// Out of chalk
+	model.player.chalk = 0;
+	press_mark(&mut model, &source_model, &bus);
+	assert!(model.progress.state.markers.is_empty());
+}
+
+#[test]
+fn wall_markers_are_saved() {
+	let world = World::new();
+	let (source_model, bus, mut model) = test_model(&world);
+
+	// Walk up to a wall
+	model.player.placement.position = model.player.placement.forward() * 1.5;
+	press_mark(&mut model, &source_model, &bus);
+
+	let [marker] = model.progress.state.markers[..] else { panic!("expected one marker") };
+	let MarkerSurface::Wall { normal, height } = marker.surface else { panic!("expected wall marker, got {marker:?}") };
+	assert!(normal.dot(model.player.placement.forward()) < -0.99);
+	assert!((height - PLAYER_HEIGHT).abs() < 0.01);
+
+	let save_game = SaveGame::from_model(&source_model, &model);
+	let save_game: SaveGame = serde_json::from_str(&serde_json::to_string(&save_game).unwrap()).unwrap();
+	assert_eq!(save_game.progress.markers, [marker]);
+	assert_eq!(save_game.player.chalk, STARTING_CHALK - 1);
+}
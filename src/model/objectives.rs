@@ -0,0 +1,206 @@
+use crate::prelude::*;
+use model::*;
+
+
+/// How close the player has to get to complete a `Reach` objective.
+pub const OBJECTIVE_REACH_DISTANCE: f32 = 0.6;
+
+
+/// Authored goal for a world. A world is complete once all of its objectives are.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Objective {
+	/// Identifies the objective in saves, so that objectives can be reordered or reworded without losing progress.
+	/// Should be unique within a world. Objectives without one are identified by their description instead.
+	#[serde(default)]
+	pub name: String,
+
+	/// Shown to the player.
+	pub description: String,
+	pub goal: ObjectiveGoal,
+}
+
+impl Objective {
+	pub fn id(&self) -> &str {
+		match self.name.is_empty() {
+			true => &self.description,
+			false => &self.name,
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum ObjectiveGoal {
+	/// Get close to the named object.
+	Reach { object_name: String },
+
+	/// Carry at least `count` of an item.
+	Collect { item: ItemId, count: u32 },
+
+	/// Have every light whose name starts with `name_prefix` lit at once.
+	LightAll { name_prefix: String },
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObjectiveEvent {
+	/// Id of one of the current world's objectives - see `Objective::id`.
+	Completed(String),
+
+	/// Every objective in the current world has been completed.
+	WorldCompleted,
+}
+
+
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct ObjectiveStatus {
+	pub current: u32,
+	pub required: u32,
+
+	/// Objectives stay complete once completed, even if e.g., the items are later dropped.
+	pub completed: bool,
+}
+
+
+/// Tracks progress towards the current world's objectives, and notices when the world is complete.
+#[derive(Debug, Default)]
+pub struct ObjectiveModel {
+	/// One per objective in the current world.
+	pub statuses: Vec<ObjectiveStatus>,
+}
+
+impl ObjectiveModel {
+	pub fn new() -> Self {
+		ObjectiveModel::default()
+	}
+
+	pub fn update(&mut self, source_world: &World, player: &Player, world: &ProcessedWorld, progress: &ProgressModel,
+		inventory: &Inventory, bus: &MessageBus)
+	{
+		self.statuses.clear();
+
+		let mut any_completed = false;
+
+		for objective in source_world.objectives.iter() {
+			let (current, required) = objective_progress(&objective.goal, player, world, inventory);
+			let already_completed = progress.state.completed_objectives.contains(objective.id());
+
+			// Progress picks this up at the start of the next step, so it's only sent once.
+			if !already_completed && current >= required {
+				log::info!("Completed objective '{}'", objective.description);
+				bus.emit(ProgressCmd::CompleteObjective(objective.id().to_string()));
+
+				bus.emit(ObjectiveEvent::Completed(objective.id().to_string()));
+				bus.emit(HudCmd::ShowText(format!("{} - done", objective.description)));
+				any_completed = true;
+			}
+
+			self.statuses.push(ObjectiveStatus {
+				current: current.min(required),
+				required,
+				completed: already_completed || current >= required,
+			});
+		}
+
+		if any_completed && self.is_world_completed() {
+			log::info!("Completed world '{}'", source_world.name);
+			bus.emit(ObjectiveEvent::WorldCompleted);
+			bus.emit(HudCmd::ShowText("The way onward opens".into()));
+		}
+	}
+
+	/// Worlds without objectives can never be completed.
+	pub fn is_world_completed(&self) -> bool {
+		!self.statuses.is_empty() && self.statuses.iter().all(|status| status.completed)
+	}
+}
+
+
+/// Returns how far along an objective is, and what it needs to reach.
+fn objective_progress(goal: &ObjectiveGoal, player: &Player, world: &ProcessedWorld, inventory: &Inventory) -> (u32, u32) {
+	match goal {
+		ObjectiveGoal::Reach { object_name } => {
+			let runtime_objects = world.runtime_objects();
+
+			let reached = runtime_objects.find_by_name(object_name)
+				.and_then(|object_id| runtime_objects.placement(object_id))
+				.map(|placement| world.to_processed_placement(placement))
+				.is_some_and(|placement| placement.room_id == player.placement.room_id
+					&& (placement.position - player.placement.position).length() < OBJECTIVE_REACH_DISTANCE);
+
+			(reached as u32, 1)
+		}
+
+		ObjectiveGoal::Collect { item, count } => (inventory.count(item), *count),
+
+		ObjectiveGoal::LightAll { name_prefix } => {
+			let lights = world.runtime_objects().iter()
+				.filter(|(_, object, _)| object.as_light().is_some() && object.name.starts_with(name_prefix.as_str()));
+
+			// Can't light all of nothing - that's more likely a typo than something to complete straight away
+			let (lit, total) = lights.fold((0, 0), |(lit, total), (_, _, state)| (lit + state.light_on as u32, total + 1));
+			(lit, total.max(1))
+		}
+	}
+}
+
+
+
+#[test]
+fn completing_all_objectives_completes_world() {
+	let mut world = World::new();
+	let spawn = world.player_spawn;
+
+	let light = |name: &str| Object {
+		name: name.into(),
+		placement: spawn,
+		info: ObjectInfo::Light(LightObject {
+			color: Color::white(),
+			height: 0.5,
+			power: 1.0,
+			radius: 2.0,
+			starts_lit: true,
+			animation: LightAnimation::Steady,
+		}),
+		verbs: Vec::new(),
+	};
+
+	let candle_id = world.objects.insert(light("candle-1"));
+	world.objects.insert(light("candle-2"));
+	world.objects.insert(light("lamp"));
+
+	world.objectives = vec![
+		Objective { name: "key".into(), description: "Find the key".into(), goal: ObjectiveGoal::Collect { item: "key".into(), count: 1 } },
+		Objective { name: String::new(), description: "Light the candles".into(), goal: ObjectiveGoal::LightAll { name_prefix: "candle".into() } },
+	];
+
+	let (source_model, bus, mut model) = test_model(&world);
+	let event_sub = bus.subscribe::<ObjectiveEvent>();
+
+	// Lights start lit, so snuff one out first
+	bus.emit(ProgressCmd::SetObjectFlag(candle_id, ObjectFlag::Extinguished, true));
+	model.step(&source_model, &TickInput::default(), &bus);
+	model.step(&source_model, &TickInput::default(), &bus);
+	bus.poll_consume(&event_sub).for_each(drop);
+
+	assert_eq!(model.objectives.statuses[1], ObjectiveStatus { current: 1, required: 2, completed: false });
+
+	bus.emit(InventoryCmd::Give("key".into(), 1));
+	model.step(&source_model, &TickInput::default(), &bus);
+	assert_eq!(bus.poll_consume(&event_sub).collect::<Vec<_>>(), [ObjectiveEvent::Completed("key".into())]);
+
+	// Dropping the key doesn't undo the objective
+	bus.emit(InventoryCmd::Take("key".into(), 1));
+	bus.emit(ProgressCmd::SetObjectFlag(candle_id, ObjectFlag::Extinguished, false));
+	model.step(&source_model, &TickInput::default(), &bus);
+	model.step(&source_model, &TickInput::default(), &bus);
+
+	let events: Vec<_> = bus.poll_consume(&event_sub).collect();
+	assert_eq!(events, [ObjectiveEvent::Completed("Light the candles".into()), ObjectiveEvent::WorldCompleted]);
+	assert!(model.objectives.is_world_completed());
+
+	// Completion sticks once progress has caught up, and doesn't repeat
+	model.step(&source_model, &TickInput::default(), &bus);
+	assert!(!bus.any(&event_sub));
+	assert!(model.progress.state.completed_objectives.contains("key"));
+	assert!(model.progress.state.completed_objectives.contains("Light the candles"));
+}
@@ -0,0 +1,264 @@
+use crate::prelude::*;
+use model::*;
+
+
+/// Perception won't follow paths through more rooms than this.
+pub const PERCEPTION_MAX_ROOMS: usize = 8;
+
+/// Fraction of a noise that makes it through each aperture.
+const APERTURE_NOISE_ATTENUATION: f32 = 0.6;
+
+/// How quickly awareness builds while the player is in plain sight, per second.
+const SIGHT_AWARENESS_RATE: f32 = 1.5;
+
+/// Awareness gained from hearing a noise at full volume.
+const HEARING_AWARENESS: f32 = 0.25;
+
+/// How quickly awareness fades once the player can't be seen or heard, per second.
+const AWARENESS_DECAY_RATE: f32 = 0.1;
+
+
+/// Something that entities with `Perception` might hear.
+#[derive(Debug, Copy, Clone)]
+pub struct NoiseEvent {
+	/// Processed room space.
+	pub location: Location,
+	pub loudness: f32,
+}
+
+
+/// Lets an entity notice the player, by sight through apertures and by hearing noises carried through the room graph.
+#[derive(Debug, Clone)]
+pub struct Perception {
+	pub sight_range: f32,
+
+	/// Half angle of the vision cone, in radians.
+	pub sight_half_angle: f32,
+
+	/// Distance at which a noise of loudness 1 becomes inaudible.
+	pub hearing_range: f32,
+
+	/// 0..1 how aware the entity is of the player. Builds while the player is seen or heard, and fades otherwise.
+	pub awareness: f32,
+
+	pub can_see_player: bool,
+
+	/// Where the player was last seen or heard. Processed room space.
+	pub last_known_location: Option<Location>,
+}
+
+impl Perception {
+	pub fn new(sight_range: f32, sight_half_angle: f32, hearing_range: f32) -> Perception {
+		Perception {
+			sight_range,
+			sight_half_angle,
+			hearing_range,
+
+			awareness: 0.0,
+			can_see_player: false,
+			last_known_location: None,
+		}
+	}
+
+	/// `eye` and `facing` are in processed room space. `eye_height` is above the floor of the eye's room.
+	pub fn update(&mut self, eye: Location, facing: Vec2, eye_height: f32, player: &Player, world: &ProcessedWorld,
+		fog: &FogParameters, noises: &[NoiseEvent], dt: f32)
+	{
+		let player_location = player.placement.location();
+		let mut perceived = false;
+
+		self.can_see_player = false;
+
+		if !player.free_cam
+			&& let Some(distance) = self.can_see(eye, facing, eye_height, player_location, world)
+		{
+			// Things are harder to make out the further away and foggier they are
+			let clarity = fog_visibility(fog, distance) * (1.0 - distance / self.sight_range);
+
+			self.awareness += clarity * SIGHT_AWARENESS_RATE * dt;
+			self.can_see_player = true;
+			self.last_known_location = Some(player_location);
+			perceived = true;
+		}
+
+		for noise in noises {
+			if let Some(volume) = self.hear(eye, noise, world) {
+				self.awareness += volume * HEARING_AWARENESS;
+				self.last_known_location = Some(noise.location);
+				perceived = true;
+			}
+		}
+
+		if !perceived {
+			self.awareness -= AWARENESS_DECAY_RATE * dt;
+		}
+
+		self.awareness = self.awareness.clamp(0.0, 1.0);
+	}
+
+	/// Returns the distance to `target` if it's within the vision cone and not blocked by walls.
+	pub fn can_see(&self, eye: Location, facing: Vec2, eye_height: f32, target: Location, world: &ProcessedWorld) -> Option<f32> {
+		let path = world.find_path(eye, target, PERCEPTION_MAX_ROOMS)?;
+
+		let to_target = path.end_to_start * target.position - eye.position;
+		let distance = to_target.length();
+		if distance > self.sight_range {
+			return None
+		}
+
+		if distance < 0.001 {
+			return Some(distance)
+		}
+
+		let direction = to_target / distance;
+		if facing.normalize().dot(direction) < self.sight_half_angle.cos() {
+			return None
+		}
+
+		let ray = WorldRay {
+			room_id: eye.room_id,
+			origin: eye.position,
+			height: eye_height,
+			direction,
+			rise: 0.0,
+		};
+
+		// Blocked if the ray stops short or ends up somewhere else - paths through rooms aren't necessarily straight lines.
+		let segments = world.trace_ray(ray, distance);
+		let last_segment = segments.last()?;
+		let reached = last_segment.ray.room_id == target.room_id
+			&& last_segment.start_distance + last_segment.length >= distance - 0.001;
+
+		reached.then_some(distance)
+	}
+
+	/// Returns how loud `noise` is by the time it reaches `ear`, if it's audible at all.
+	pub fn hear(&self, ear: Location, noise: &NoiseEvent, world: &ProcessedWorld) -> Option<f32> {
+		let path = world.find_path(ear, noise.location, PERCEPTION_MAX_ROOMS)?;
+
+		let aperture_attenuation = APERTURE_NOISE_ATTENUATION.powi(path.walls.len() as i32);
+		let distance_attenuation = 1.0 - path.distance / (self.hearing_range * noise.loudness);
+		let volume = noise.loudness * aperture_attenuation * distance_attenuation;
+
+		(volume > 0.0).then_some(volume)
+	}
+}
+
+
+/// How much of something `distance` away can be seen through fog. Matches the fog shader.
+pub fn fog_visibility(fog: &FogParameters, distance: f32) -> f32 {
+	let fog_distance = (distance - fog.start).max(0.0) / fog.distance;
+	(-fog_distance * 4.0).exp()
+}
+
+
+
+#[cfg(test)]
+fn narrow_aperture_world() -> ProcessedWorld {
+	let mut world = World::new();
+	world.geometry = WorldGeometry::new_square(1.0);
+
+	let near_room = world.geometry.first_room();
+	let far_room = world.geometry.insert_room_from_positions(&[
+		Vec2::new(4.5, -0.2),
+		Vec2::new(4.5,  0.2),
+		Vec2::new(5.5,  0.2),
+		Vec2::new(5.5, -0.2),
+	]);
+
+	// Isolated room that can't be seen or heard from anywhere
+	world.geometry.insert_room_from_positions(&[
+		Vec2::new(-5.5, -0.5),
+		Vec2::new(-5.5,  0.5),
+		Vec2::new(-4.5,  0.5),
+		Vec2::new(-4.5, -0.5),
+	]);
+
+	// Connect the +x wall of the near room with the -x wall of the far room, so the far room is 4 units closer in near room space
+	let near_wall = world.geometry.room_walls(near_room)
+		.max_by(|a, b| world.geometry.wall_center(*a).x.total_cmp(&world.geometry.wall_center(*b).x))
+		.unwrap();
+
+	let far_wall = world.geometry.room_walls(far_room)
+		.min_by(|a, b| world.geometry.wall_center(*a).x.total_cmp(&world.geometry.wall_center(*b).x))
+		.unwrap();
+
+	world.geometry.connect_wall(near_wall, far_wall).unwrap();
+
+	ProcessedWorld::new(&world, &ProgressModel::new(&MessageBus::new()), &MessageBus::new())
+}
+
+#[cfg(test)]
+fn location_in(world: &ProcessedWorld, position: Vec2) -> Location {
+	let room_id = world.geometry().rooms.keys()
+		.find(|&room_id| world.geometry().room_contains_point(room_id, position))
+		.expect("Position not in any room");
+
+	Location { room_id, position }
+}
+
+#[test]
+fn sight_is_blocked_by_walls_around_apertures() {
+	let world = narrow_aperture_world();
+	let perception = Perception::new(5.0, 60.0f32.to_radians(), 5.0);
+
+	let forward = Vec2::new(1.0, 0.0);
+	let player = location_in(&world, Vec2::new(5.4, 0.15));
+
+	// Straight through the aperture
+	let eye = location_in(&world, Vec2::new(0.0, 0.0));
+	let distance = perception.can_see(eye, forward, 0.3, player, &world);
+	assert!(distance.is_some_and(|distance| (distance - 1.4).abs() < 0.01), "{distance:?}");
+
+	// Looking away
+	assert!(perception.can_see(eye, -forward, 0.3, player, &world).is_none());
+
+	// Line of sight hits the wall beside the aperture
+	let eye = location_in(&world, Vec2::new(0.0, 0.45));
+	assert!(perception.can_see(eye, forward, 0.3, player, &world).is_none());
+
+	// No path at all
+	let isolated = location_in(&world, Vec2::new(-5.0, 0.0));
+	assert!(perception.can_see(isolated, forward, 0.3, player, &world).is_none());
+}
+
+#[test]
+fn noises_are_quieter_through_apertures() {
+	let world = narrow_aperture_world();
+	let perception = Perception::new(5.0, 60.0f32.to_radians(), 5.0);
+
+	let ear = location_in(&world, Vec2::new(0.0, 0.0));
+	let same_room = NoiseEvent { location: location_in(&world, Vec2::new(0.0, 0.4)), loudness: 1.0 };
+	let next_room = NoiseEvent { location: location_in(&world, Vec2::new(4.6, 0.0)), loudness: 1.0 };
+	let isolated = NoiseEvent { location: location_in(&world, Vec2::new(-5.0, 0.0)), loudness: 1.0 };
+
+	let same_room_volume = perception.hear(ear, &same_room, &world).unwrap();
+	let next_room_volume = perception.hear(ear, &next_room, &world).unwrap();
+
+	assert!(next_room_volume < same_room_volume);
+	assert!(perception.hear(ear, &isolated, &world).is_none());
+}
+
+#[test]
+fn fog_slows_awareness() {
+	let world = narrow_aperture_world();
+
+	let mut player = Model::new(&World::new(), 0, &MessageBus::new()).player;
+	player.placement = Placement { room_id: location_in(&world, Vec2::new(5.4, 0.0)).room_id, position: Vec2::new(5.4, 0.0), yaw: 0.0 };
+
+	let eye = location_in(&world, Vec2::new(0.0, 0.0));
+	let forward = Vec2::new(1.0, 0.0);
+
+	let clear = FogParameters { distance: 100.0, .. FogParameters::default() };
+	let foggy = FogParameters { distance: 2.0, .. FogParameters::default() };
+
+	let mut clear_perception = Perception::new(5.0, 60.0f32.to_radians(), 5.0);
+	let mut foggy_perception = clear_perception.clone();
+
+	clear_perception.update(eye, forward, 0.3, &player, &world, &clear, &[], SIM_DT);
+	foggy_perception.update(eye, forward, 0.3, &player, &world, &foggy, &[], SIM_DT);
+
+	assert!(clear_perception.can_see_player && foggy_perception.can_see_player);
+	assert!(foggy_perception.awareness < clear_perception.awareness);
+	assert!(foggy_perception.awareness > 0.0);
+}
@@ -1,5 +1,5 @@
 use crate::prelude::*;
-use model::{Placement, ProcessedWorld, HudModel};
+use model::{Placement, ProcessedWorld, HudModel, TickInput, Verb, NoiseEvent};
 
 /// Ratio of player height to max step distance.
 pub const PLAYER_MAX_STEP_HEIGHT: f32 = 0.2;
@@ -9,7 +9,13 @@ pub const PLAYER_RADIUS: f32 = 0.1;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum PlayerCmd {
-	Interact,
+	/// Use a verb on whatever object is being looked at.
+	Interact(Verb),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlayerEvent {
+	Footstep,
 }
 
 
@@ -24,6 +30,12 @@ pub struct Player {
 	pub blood: u32,
 	pub salt: u32,
 
+	/// Time left on the current salt ward, in seconds.
+	pub ward_time_remaining: f32,
+
+	/// Markers left to place.
+	pub chalk: u32,
+
 	// TODO(pat.m): these should be separate to player
 	pub free_pos: Vec3,
 	pub free_cam: bool,
@@ -33,20 +45,30 @@ pub struct Player {
 }
 
 impl Player {
-	pub fn handle_input(&mut self, ctx: &mut Context<'_>, processed_world: &ProcessedWorld, hud: &HudModel) {
+	pub fn update(&mut self, input: &TickInput, dt: f32, processed_world: &ProcessedWorld, hud: &HudModel, bus: &MessageBus) {
 		self.hack_height_change = None;
 
-		let interact_pressed = ctx.input.button_just_down(input::MouseButton::Left) || ctx.input.button_just_down(input::keys::KeyF);
+		// Dialogue and choices take over input until they're closed
+		if hud.in_dialog() || hud.choice.is_some() {
+			if input.just_down(Action::MenuUp) || input.just_down(Action::MoveForward) {
+				bus.emit(model::HudCmd::MoveChoiceSelection(-1));
+			}
+
+			if input.just_down(Action::MenuDown) || input.just_down(Action::MoveBack) {
+				bus.emit(model::HudCmd::MoveChoiceSelection(1));
+			}
 
-		if hud.in_dialog {
-			if interact_pressed {
-				ctx.bus.emit(model::HudCmd::DismissDialog);
+			if input.just_down(Action::Interact) || input.just_down(Action::MenuConfirm) {
+				match hud.in_dialog() {
+					true => bus.emit(model::HudCmd::AdvanceDialog),
+					false => bus.emit(model::HudCmd::ConfirmChoice),
+				}
 			}
 
 			return;
 		}
 
-		if ctx.input.button_just_down(input::keys::KeyV) {
+		if input.just_down(Action::ToggleFreeCam) {
 			self.free_cam = !self.free_cam;
 
 			if !self.free_cam {
@@ -54,20 +76,56 @@ impl Player {
 			}
 		}
 
-		if interact_pressed {
-			ctx.bus.emit(PlayerCmd::Interact);
+		for &verb in Verb::ALL {
+			if input.just_down(verb.action()) {
+				bus.emit(PlayerCmd::Interact(verb));
+			}
+		}
+
+		if input.just_down(Action::Ward) {
+			bus.emit(model::ResourceCmd::Ward);
 		}
 
-		let forward_pressed = ctx.input.button_down(input::keys::KeyW);
-		let back_pressed = ctx.input.button_down(input::keys::KeyS);
-		let right_pressed = ctx.input.button_down(input::keys::KeyD);
-		let left_pressed = ctx.input.button_down(input::keys::KeyA);
+		if input.just_down(Action::Mark) {
+			bus.emit(model::MarkerCmd::Mark);
+		}
+
+		if input.just_down(Action::Map) {
+			bus.emit(model::HudCmd::ToggleMap);
+		}
+
+		if input.just_down(Action::NextItem) {
+			bus.emit(model::InventoryCmd::SelectNext);
+		}
+
+		if input.just_down(Action::PreviousItem) {
+			bus.emit(model::InventoryCmd::SelectPrevious);
+		}
+
+		let forward_pressed = input.is_down(Action::MoveForward);
+		let back_pressed = input.is_down(Action::MoveBack);
+		let right_pressed = input.is_down(Action::StrafeRight);
+		let left_pressed = input.is_down(Action::StrafeLeft);
+
+		let yaw_left_pressed = input.is_down(Action::TurnLeft);
+		let yaw_right_pressed = input.is_down(Action::TurnRight);
 
-		let yaw_left_pressed = ctx.input.button_down(input::keys::ArrowLeft) || ctx.input.button_down(input::keys::Numpad4);
-		let yaw_right_pressed = ctx.input.button_down(input::keys::ArrowRight) || ctx.input.button_down(input::keys::Numpad6);
+		let pitch_up_pressed = input.is_down(Action::LookUp);
+		let pitch_down_pressed = input.is_down(Action::LookDown);
 
-		let pitch_up_pressed = ctx.input.button_down(input::keys::ArrowUp) || ctx.input.button_down(input::keys::Numpad8);
-		let pitch_down_pressed = ctx.input.button_down(input::keys::ArrowDown) || ctx.input.button_down(input::keys::Numpad2);
+		// x: strafe, y: forward. Digital and analog input are combined so keyboard and gamepad can be used together.
+		let mut move_input = input.move_axis;
+
+		if forward_pressed { move_input.y += 1.0; }
+		if back_pressed { move_input.y -= 1.0; }
+		if right_pressed { move_input.x += 1.0; }
+		if left_pressed { move_input.x -= 1.0; }
+
+		// Combining them mustn't go faster than either alone
+		let move_length = move_input.length();
+		if move_length > 1.0 {
+			move_input = move_input / move_length;
+		}
 
 		let yaw_key_delta = match (yaw_right_pressed, yaw_left_pressed) {
 			(true, false) => 1.0,
@@ -82,15 +140,15 @@ impl Player {
 		};
 
 		{
-			let (dyaw, dpitch) = ctx.input.mouse_delta_radians().map_or((0.0, 0.0), Vec2::to_tuple);
+			let (dyaw, dpitch) = input.look_delta.to_tuple();
+			let look_axis = input.look_axis;
 
 			// https://github.com/id-Software/Quake-III-Arena/blob/dbe4ddb10315479fc00086f08e25d968b4b43c49/code/client/cl_input.c#L293
 			// https://github.com/id-Software/Quake-III-Arena/blob/dbe4ddb10315479fc00086f08e25d968b4b43c49/code/client/cl_main.c#L2300
 			let pitch_yaw_speed = 140.0f32.to_radians();
-			let dt = ctx.delta_time;
 
-			let key_yaw = yaw_key_delta * pitch_yaw_speed * dt;
-			let key_pitch = pitch_key_delta * pitch_yaw_speed * dt;
+			let key_yaw = (yaw_key_delta + look_axis.x) * pitch_yaw_speed * dt;
+			let key_pitch = (pitch_key_delta + look_axis.y) * pitch_yaw_speed * dt;
 
 			self.placement.yaw += dyaw + key_yaw;
 			self.placement.yaw %= TAU;
@@ -101,11 +159,11 @@ impl Player {
 
 
 
-		let base_speed = ctx.delta_time;
-		let speed = match (ctx.input.button_down(input::keys::Shift), ctx.input.button_down(input::keys::Alt)) {
-			(true, false) => 2.0 * base_speed,
-			(false, true) => 0.25 * base_speed,
-			_ => base_speed,
+		let base_speed = dt;
+		let (speed, footstep_loudness) = match (input.is_down(Action::Sprint), input.is_down(Action::Sneak)) {
+			(true, false) => (2.0 * base_speed, 2.0),
+			(false, true) => (0.25 * base_speed, 0.3),
+			_ => (base_speed, 1.0),
 		};
 
 		if self.free_cam {
@@ -117,21 +175,7 @@ impl Player {
 			let right = yaw_orientation.right();
 			let forward = orientation.forward();
 
-			if forward_pressed {
-				self.free_pos += forward * speed;
-			}
-
-			if back_pressed {
-				self.free_pos -= forward * speed;
-			}
-
-			if right_pressed {
-				self.free_pos += right * speed;
-			}
-
-			if left_pressed {
-				self.free_pos -= right * speed;
-			}
+			self.free_pos += (forward * move_input.y + right * move_input.x) * speed;
 
 			self.step_accumulator = 0.0;
 
@@ -139,23 +183,7 @@ impl Player {
 			let forward = self.placement.forward();
 			let right = self.placement.right();
 
-			let mut delta = Vec2::zero();
-
-			if forward_pressed {
-				delta += forward * speed;
-			}
-
-			if back_pressed {
-				delta -= forward * speed;
-			}
-
-			if right_pressed {
-				delta += right * speed;
-			}
-
-			if left_pressed {
-				delta -= right * speed;
-			}
+			let delta = (forward * move_input.y + right * move_input.x) * speed;
 
 			let distance_traveled = self.try_move_by(processed_world, delta);
 			self.step_accumulator += distance_traveled;
@@ -163,7 +191,8 @@ impl Player {
 			// Dumb step sounds
 			let step_size = 0.35;
 			if self.step_accumulator > step_size {
-				ctx.audio.trigger();
+				bus.emit(PlayerEvent::Footstep);
+				bus.emit(NoiseEvent { location: self.placement.location(), loudness: footstep_loudness });
 				self.step_accumulator -= step_size;
 			}
 		}
@@ -171,6 +200,13 @@ impl Player {
 }
 
 
+impl Player {
+	pub fn is_warded(&self) -> bool {
+		self.ward_time_remaining > 0.0
+	}
+}
+
+
 // TODO(pat.m): some kind of transform/connectivity cache
 
 impl Player {
@@ -197,6 +233,18 @@ impl Player {
 			}
 		}
 
+		fn collide_segment(desired_position: &mut Vec2, start: Vec2, end: Vec2, radius: f32) {
+			let segment = end - start;
+			let t = ((*desired_position - start).dot(segment) / segment.dot(segment)).clamp(0.0, 1.0);
+			collide_vertex(desired_position, start + segment * t, radius);
+		}
+
+		// Collide with closed doors
+		// TODO(pat.m): doors in neighbouring rooms aren't considered, so a door right on an aperture only blocks from one side
+		for (start, end) in processed_world.blocking_segments_in_room(self.placement.room_id) {
+			collide_segment(&mut desired_position, start, end, PLAYER_RADIUS);
+		}
+
 		// Collide with room verts
 		for wall_id in geometry.room_walls(self.placement.room_id) {
 			let vertex_id = geometry.walls[wall_id].source_vertex;
@@ -5,10 +5,12 @@ use slotmap::SecondaryMap;
 
 #[derive(Debug)]
 pub struct ProcessedWorld {
-	// Copied from source World for now
+	// Objects that currently exist, with runtime state applied and placed in processed rooms.
 	pub objects: SlotMap<ObjectId, Object>,
 	pub fog: FogParameters,
 
+	runtime_objects: RuntimeObjects,
+
 	wall_infos: SecondaryMap<WallId, WallInfo>,
 	room_infos: SecondaryMap<RoomId, RoomInfo>,
 
@@ -17,14 +19,22 @@ pub struct ProcessedWorld {
 	source_to_processed_rooms: SecondaryMap<RoomId, SmallVec<[RoomId; 4]>>,
 
 	world_change_sub: Subscription<WorldChangedEvent>,
+	progress_event_sub: Subscription<ProgressEvent>,
+	object_cmd_sub: Subscription<ObjectCmd>,
 }
 
+/// Emitted after ProcessedWorld has been rebuilt, either due to the source world or progression changing.
+#[derive(Debug, Clone)]
+pub struct ProcessedWorldChangedEvent;
+
 impl ProcessedWorld {
-	pub fn new(world: &World, message_bus: &MessageBus) -> Self {
+	pub fn new(world: &World, progress: &ProgressModel, message_bus: &MessageBus) -> Self {
 		let mut this = Self {
 			objects: SlotMap::with_key(),
 			fog: FogParameters::default(),
 
+			runtime_objects: RuntimeObjects::new(world),
+
 			wall_infos: SecondaryMap::new(),
 			room_infos: SecondaryMap::new(),
 
@@ -33,15 +43,57 @@ impl ProcessedWorld {
 			source_to_processed_rooms: SecondaryMap::new(),
 
 			world_change_sub: message_bus.subscribe(),
+			progress_event_sub: message_bus.subscribe(),
+			object_cmd_sub: message_bus.subscribe(),
 		};
 
-		this.rebuild_world(world);
+		this.rebuild_world(world, progress);
 		this
 	}
 
-	pub fn update(&mut self, world: &World, _progress: &ProgressModel, message_bus: &MessageBus) {
-		if message_bus.any(&self.world_change_sub) {
-			self.rebuild_world(world);
+	pub fn update(&mut self, world: &World, progress: &mut ProgressModel, message_bus: &MessageBus) {
+		let world_changed = message_bus.any(&self.world_change_sub);
+
+		let mut progress_changed = false;
+		let mut lights_changed = false;
+		let mut fog_changed = false;
+		for event in message_bus.poll(&self.progress_event_sub) {
+			match event {
+				// Lights are animated every frame anyway, so lighting or snuffing one doesn't need a rebuild.
+				ProgressEvent::ObjectStateChanged { flag: ObjectFlag::Lit | ObjectFlag::Extinguished, .. } => lights_changed = true,
+				ProgressEvent::ObjectStateChanged { .. } => progress_changed = true,
+				ProgressEvent::RoomFogChanged(_) => fog_changed = true,
+
+				// Nothing in the processed world depends on these.
+				ProgressEvent::FlagChanged { .. }
+				| ProgressEvent::CounterChanged { .. }
+				| ProgressEvent::RoomVisited(_)
+				| ProgressEvent::ObjectiveCompleted(_) => {}
+			}
+		}
+
+		if world_changed {
+			let changed_ids = self.runtime_objects.sync(world);
+			progress.state.remap_object_states(changed_ids);
+		}
+
+		let mut objects_changed = false;
+		for cmd in message_bus.poll_consume(&self.object_cmd_sub) {
+			objects_changed |= self.runtime_objects.apply(cmd, message_bus);
+		}
+
+		if world_changed || progress_changed || objects_changed {
+			self.rebuild_world(world, progress);
+			message_bus.emit(ProcessedWorldChangedEvent);
+
+		} else {
+			if lights_changed {
+				self.runtime_objects.apply_progress(&progress.state);
+			}
+
+			if fog_changed {
+				self.apply_room_fog(progress);
+			}
 		}
 	}
 
@@ -49,6 +101,12 @@ impl ProcessedWorld {
 		self.wall_infos.get(wall_id)
 	}
 
+	/// Fog within a processed room, taking overrides from progress into account.
+	pub fn room_fog(&self, room_id: RoomId) -> FogParameters {
+		self.room_info(room_id)
+			.map_or(self.fog, |info| info.fog)
+	}
+
 	pub fn room_info(&self, room_id: RoomId) -> Option<&RoomInfo> {
 		self.room_infos.get(room_id)
 	}
@@ -57,6 +115,23 @@ impl ProcessedWorld {
 		&self.geometry
 	}
 
+	/// Every object in the world regardless of whether it's currently visible, in source room space.
+	pub fn runtime_objects(&self) -> &RuntimeObjects {
+		&self.runtime_objects
+	}
+
+	/// Changes made through this aren't visible in the processed world until it's next rebuilt.
+	pub fn runtime_objects_mut(&mut self) -> &mut RuntimeObjects {
+		&mut self.runtime_objects
+	}
+
+	/// Restores runtime objects as they were saved, e.g., when returning to a world, and rebuilds.
+	pub fn restore_runtime_objects(&mut self, world: &World, save: &RuntimeObjectsSave, progress: &mut ProgressModel) {
+		let spawned_ids = self.runtime_objects.restore(save);
+		progress.state.remap_object_states(spawned_ids);
+		self.rebuild_world(world, progress);
+	}
+
 	pub fn connection_info(&self, wall_id: WallId) -> Option<&ConnectionInfo> {
 		self.wall_infos.get(wall_id)
 			.and_then(|wall| wall.connection_info.as_ref())
@@ -86,6 +161,13 @@ impl ProcessedWorld {
 			.map(move |idx| &self.objects[idx])
 	}
 
+	/// Segments that currently block movement in a room - i.e., closed doors.
+	pub fn blocking_segments_in_room(&self, room_id: RoomId) -> impl Iterator<Item=(Vec2, Vec2)> + use<'_> {
+		self.object_ids_for_room(room_id)
+			.filter(move |&object_id| self.runtime_objects.get(object_id).is_some_and(|(_, state)| !state.opened))
+			.filter_map(move |object_id| self.objects[object_id].blocking_segment())
+	}
+
 	pub fn to_source_placement(&self, processed_placement: Placement) -> Placement {
 		Placement {
 			room_id: self.to_source_room(processed_placement.room_id),
@@ -107,6 +189,18 @@ impl ProcessedWorld {
 		source_placement
 	}
 
+	pub fn to_source_location(&self, processed_location: Location) -> Location {
+		Location {
+			room_id: self.to_source_room(processed_location.room_id),
+			.. processed_location
+		}
+	}
+
+	pub fn to_processed_location(&self, source_location: Location) -> Location {
+		let placement = Placement { room_id: source_location.room_id, position: source_location.position, yaw: 0.0 };
+		self.to_processed_placement(placement).location()
+	}
+
 	pub fn to_source_room(&self, processed_room_id: RoomId) -> RoomId {
 		assert!(processed_room_id.is_valid(&self.geometry), "RoomId given to to_source_room that doesn't exist in processed geometry");
 
@@ -122,7 +216,156 @@ impl ProcessedWorld {
 		rooms
 	}
 
-	fn rebuild_world(&mut self, world: &World) {
+	/// Follows a ray through rooms and apertures until it hits a wall, floor or ceiling, or has travelled `max_distance`.
+	/// Returns the part of the ray in each room it passes through, in order.
+	pub fn trace_ray(&self, mut ray: WorldRay, max_distance: f32) -> SmallVec<[RaySegment; 4]> {
+		let geometry = &self.geometry;
+
+		let mut segments = SmallVec::new();
+		let mut distance_travelled = 0.0;
+		let mut from_wall = None;
+
+		for _ in 0..MAX_RAY_ROOM_TRANSITIONS {
+			let remaining_distance = max_distance - distance_travelled;
+			if remaining_distance <= 0.0 {
+				break
+			}
+
+			let room_height = geometry.rooms[ray.room_id].height;
+
+			// Distance to floor or ceiling
+			let mut segment_length = match ray.rise {
+				rise if rise < 0.0 => -ray.height / rise,
+				rise if rise > 0.0 => (room_height - ray.height) / rise,
+				_ => f32::INFINITY,
+			};
+
+			// ASSUME: rooms are convex, so the nearest wall the ray is moving out through is the one it leaves through.
+			let mut exit_wall = None;
+
+			for wall_id in geometry.room_walls(ray.room_id) {
+				if Some(wall_id) == from_wall {
+					continue
+				}
+
+				let (wall_start, wall_end) = geometry.wall_vertices(wall_id);
+				let wall_direction = wall_end - wall_start;
+
+				// Clockwise wedge product means the ray is moving inwards
+				let outward_rate = wall_direction.wedge(ray.direction);
+				if outward_rate <= 0.0 {
+					continue
+				}
+
+				let wall_distance = -wall_direction.wedge(ray.origin - wall_start) / outward_rate;
+				if wall_distance < segment_length {
+					segment_length = wall_distance.max(0.0);
+					exit_wall = Some(wall_id);
+				}
+			}
+
+			segments.push(RaySegment {
+				ray,
+				start_distance: distance_travelled,
+				length: segment_length.min(remaining_distance),
+			});
+
+			if segment_length >= remaining_distance {
+				break
+			}
+
+			// Hit the floor, ceiling or a solid wall
+			let Some(connection) = exit_wall.and_then(|wall_id| self.connection_info(wall_id)) else {
+				break
+			};
+
+			let (exit_position, exit_height) = ray.point_at(segment_length);
+
+			// Make sure the ray passes through the aperture and not the wall around it
+			let aperture_diff = connection.aperture_end - connection.aperture_start;
+			let aperture_fraction = (exit_position - connection.aperture_start).dot(aperture_diff) / aperture_diff.dot(aperture_diff);
+			let aperture_bottom = connection.height_difference.max(0.0);
+			let aperture_top = aperture_bottom + connection.aperture_height;
+
+			if !(0.0..=1.0).contains(&aperture_fraction) || exit_height < aperture_bottom || exit_height > aperture_top {
+				break
+			}
+
+			let origin = connection.source_to_target * exit_position;
+
+			ray = WorldRay {
+				room_id: connection.target_room,
+				origin,
+				height: exit_height - connection.height_difference,
+				direction: connection.source_to_target * (exit_position + ray.direction) - origin,
+				rise: ray.rise,
+			};
+
+			from_wall = Some(connection.target_wall);
+			distance_travelled += segment_length;
+		}
+
+		segments
+	}
+
+	/// Finds the apertures to pass through to get from `from` to `to`, visiting at most `max_rooms` rooms.
+	/// Doesn't take step heights into account, so only makes sense for things that can float.
+	pub fn find_path(&self, from: Location, to: Location, max_rooms: usize) -> Option<WorldPath> {
+		let mut previous: SecondaryMap<RoomId, (RoomId, WallId)> = SecondaryMap::new();
+		let mut queue = std::collections::VecDeque::from([from.room_id]);
+		let mut rooms_visited = 0;
+
+		// Breadth first, so the path with the fewest rooms wins rather than the shortest.
+		while let Some(room_id) = queue.pop_front() {
+			if room_id == to.room_id {
+				break
+			}
+
+			rooms_visited += 1;
+			if rooms_visited > max_rooms {
+				return None
+			}
+
+			for connection in self.connections_for_room(room_id) {
+				let target_room = connection.target_room;
+				if target_room == from.room_id || previous.contains_key(target_room) {
+					continue
+				}
+
+				previous.insert(target_room, (room_id, connection.source_wall));
+				queue.push_back(target_room);
+			}
+		}
+
+		let mut walls = SmallVec::new();
+		let mut room_id = to.room_id;
+		while room_id != from.room_id {
+			let &(previous_room, wall_id) = previous.get(room_id)?;
+			walls.push(wall_id);
+			room_id = previous_room;
+		}
+
+		walls.reverse();
+
+		// Distance between aperture centers along the path
+		let mut distance = 0.0;
+		let mut position = from.position;
+		let mut end_to_start = Mat2x3::identity();
+
+		for &wall_id in walls.iter() {
+			let connection = self.connection_info(wall_id)?;
+			let aperture_center = (connection.aperture_start + connection.aperture_end) / 2.0;
+			distance += (aperture_center - position).length();
+			position = connection.source_to_target * aperture_center;
+			end_to_start = end_to_start * connection.target_to_source;
+		}
+
+		distance += (to.position - position).length();
+
+		Some(WorldPath { walls, distance, end_to_start })
+	}
+
+	fn rebuild_world(&mut self, world: &World, progress: &ProgressModel) {
 		self.room_infos.clear();
 		self.wall_infos.clear();
 		self.processed_to_source_rooms.clear();
@@ -137,7 +380,9 @@ impl ProcessedWorld {
 		invert_processed_to_source_room_map(&mut self.source_to_processed_rooms, &self.processed_to_source_rooms);
 
 		self.fog = world.fog.clone();
-		self.objects = world.objects.clone();
+
+		self.runtime_objects.apply_progress(&progress.state);
+		self.objects = self.runtime_objects.resolve();
 
 		// Map objects into the right rooms
 		for object in self.objects.values_mut() {
@@ -186,8 +431,18 @@ impl ProcessedWorld {
 			self.room_infos.insert(room_id, RoomInfo {
 				object_ids,
 				connecting_walls,
+				fog: self.fog,
 			});
 		}
+
+		self.apply_room_fog(progress);
+	}
+
+	fn apply_room_fog(&mut self, progress: &ProgressModel) {
+		for (room_id, info) in self.room_infos.iter_mut() {
+			let source_room_id = self.processed_to_source_rooms.get(room_id).copied().unwrap_or(room_id);
+			info.fog = progress.state.room_fog.get(source_room_id).copied().unwrap_or(self.fog);
+		}
 	}
 }
 
@@ -287,10 +542,70 @@ impl ConnectionInfo {
 
 
 
+/// Upper bound on the number of apertures `ProcessedWorld::trace_ray` will follow.
+pub const MAX_RAY_ROOM_TRANSITIONS: usize = 8;
+
+/// A ray in the space of the room it's currently in.
+#[derive(Debug, Copy, Clone)]
+pub struct WorldRay {
+	pub room_id: RoomId,
+	pub origin: Vec2,
+
+	/// Height above the floor of `room_id`.
+	pub height: f32,
+
+	/// Horizontal and vertical parts of the direction. Together they form a unit vector.
+	pub direction: Vec2,
+	pub rise: f32,
+}
+
+impl WorldRay {
+	/// Ray looking out from a placement. Negative pitch looks up.
+	pub fn from_view(placement: Placement, eye_height: f32, pitch: f32) -> WorldRay {
+		WorldRay {
+			room_id: placement.room_id,
+			origin: placement.position,
+			height: eye_height,
+			direction: placement.forward() * pitch.cos(),
+			rise: -pitch.sin(),
+		}
+	}
+
+	pub fn point_at(&self, distance: f32) -> (Vec2, f32) {
+		(self.origin + self.direction * distance, self.height + self.rise * distance)
+	}
+}
+
+/// The part of a traced ray within a single room.
+#[derive(Debug, Copy, Clone)]
+pub struct RaySegment {
+	pub ray: WorldRay,
+
+	/// Distance along the full ray at which this segment starts.
+	pub start_distance: f32,
+	pub length: f32,
+}
+
+
+/// Route between two locations found by `ProcessedWorld::find_path`.
+#[derive(Debug, Clone)]
+pub struct WorldPath {
+	/// Connecting walls to pass through in order, each in the room reached by the one before.
+	pub walls: SmallVec<[WallId; 8]>,
+
+	/// Approximate length of the path.
+	pub distance: f32,
+
+	/// Transforms from the room space of the last room to that of the first.
+	pub end_to_start: Mat2x3,
+}
+
+
 #[derive(Default, Debug)]
 pub struct RoomInfo {
 	pub object_ids: Vec<ObjectId>,
 	pub connecting_walls: Vec<WallId>,
+	pub fog: FogParameters,
 }
 
 
@@ -820,4 +1135,30 @@ fn split_concave_rooms_with_failure_case_3() {
 	split_concave_rooms(&mut geometry, &mut room_map).expect("split_concave_rooms failed");
 
 	model::world::validation::validate_geometry(&geometry).expect("validation failed");
-}
\ No newline at end of file
+}
+
+#[test]
+fn flags_counters_and_visits_dont_rebuild_world() {
+	let mut world = World::new();
+	let chest_id = world.objects.insert(Object {
+		name: "chest".into(),
+		placement: world.player_spawn,
+		info: ObjectInfo::Debug,
+		verbs: Vec::new(),
+	});
+
+	let (source_model, bus, mut model) = test_model(&world);
+	let change_sub = bus.subscribe::<ProcessedWorldChangedEvent>();
+
+	model.step(&source_model, &TickInput::default(), &bus);
+	bus.poll_consume(&change_sub).for_each(drop);
+
+	bus.emit(ProgressCmd::AddCounter("offerings".into(), 1));
+	bus.emit(ProgressCmd::SetFlag("door_open".into(), true));
+	model.step(&source_model, &TickInput::default(), &bus);
+	assert!(!bus.any(&change_sub));
+
+	bus.emit(ProgressCmd::SetObjectFlag(chest_id, ObjectFlag::Opened, true));
+	model.step(&source_model, &TickInput::default(), &bus);
+	assert!(bus.any(&change_sub));
+}
@@ -1,6 +1,308 @@
+use crate::prelude::*;
+use model::*;
 
+use slotmap::SecondaryMap;
+use std::collections::{BTreeMap, BTreeSet};
 
-#[derive(Debug, Default)]
+
+/// Requests to change progression state. Applied at the start of the next simulation step.
+#[derive(Debug, Clone)]
+pub enum ProgressCmd {
+	SetFlag(String, bool),
+	SetCounter(String, i32),
+	AddCounter(String, i32),
+	SetObjectFlag(ObjectId, ObjectFlag, bool),
+
+	/// Marks an objective of the current world as completed, by `Objective::id`. Objectives can't be uncompleted.
+	CompleteObjective(String),
+
+	/// Overrides the world fog within a source room, or with `None` goes back to the world fog.
+	SetRoomFog(RoomId, Option<FogParameters>),
+}
+
+/// Emitted whenever progression state actually changes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressEvent {
+	FlagChanged { name: String, value: bool },
+	CounterChanged { name: String, value: i32 },
+	ObjectStateChanged { object_id: ObjectId, flag: ObjectFlag, value: bool },
+
+	/// The player entered a room in the current world for the first time. In source room space.
+	RoomVisited(RoomId),
+
+	ObjectiveCompleted(String),
+
+	/// In source room space.
+	RoomFogChanged(RoomId),
+}
+
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ObjectFlag {
+	Opened,
+	Collected,
+	Lit,
+
+	/// Light has been put out, and no longer lights the world.
+	Extinguished,
+
+	/// Altar can be travelled to.
+	Attuned,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ObjectState {
+	pub opened: bool,
+	pub collected: bool,
+	pub lit: bool,
+
+	#[serde(default)]
+	pub extinguished: bool,
+
+	#[serde(default)]
+	pub attuned: bool,
+}
+
+impl ObjectState {
+	pub fn get(&self, flag: ObjectFlag) -> bool {
+		match flag {
+			ObjectFlag::Opened => self.opened,
+			ObjectFlag::Collected => self.collected,
+			ObjectFlag::Lit => self.lit,
+			ObjectFlag::Extinguished => self.extinguished,
+			ObjectFlag::Attuned => self.attuned,
+		}
+	}
+
+	pub fn set(&mut self, flag: ObjectFlag, value: bool) {
+		match flag {
+			ObjectFlag::Opened => self.opened = value,
+			ObjectFlag::Collected => self.collected = value,
+			ObjectFlag::Lit => self.lit = value,
+			ObjectFlag::Extinguished => self.extinguished = value,
+			ObjectFlag::Attuned => self.attuned = value,
+		}
+	}
+}
+
+
+/// Something that can be asked of progression state - e.g., whether a door should be unlocked or a dialogue option shown.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Condition {
+	Flag(String),
+	NotFlag(String),
+	CounterAtLeast(String, i32),
+	Object(ObjectId, ObjectFlag),
+
+	All(Vec<Condition>),
+	Any(Vec<Condition>),
+}
+
+
+/// Serializable part of progression. This is what ends up in save games.
+/// Flags and counters are shared by all worlds, everything else is for the current world only - see `Campaign`.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct ProgressState {
+	pub flags: BTreeSet<String>,
+	pub counters: BTreeMap<String, i32>,
+
+	// Runtime state of objects that differs from how they're defined in the world.
+	pub object_states: SecondaryMap<ObjectId, ObjectState>,
+
+	// Source rooms the player has set foot in.
+	#[serde(default)]
+	pub visited_rooms: BTreeSet<RoomId>,
+
+	#[serde(default)]
+	pub markers: Vec<Marker>,
+
+	// Ids of the current world's objectives that have been completed - see `Objective::id`.
+	#[serde(default)]
+	pub completed_objectives: BTreeSet<String>,
+
+	// Fog set by effects, keyed by source room. Rooms without an override use the world fog.
+	#[serde(default)]
+	pub room_fog: SecondaryMap<RoomId, FogParameters>,
+}
+
+impl ProgressState {
+	pub fn flag(&self, name: &str) -> bool {
+		self.flags.contains(name)
+	}
+
+	pub fn counter(&self, name: &str) -> i32 {
+		self.counters.get(name).copied().unwrap_or(0)
+	}
+
+	pub fn object_state(&self, object_id: ObjectId) -> ObjectState {
+		self.object_states.get(object_id).copied().unwrap_or_default()
+	}
+
+	/// Moves object states to the new ids of objects that have been given new ids, e.g., spawned objects restored from a save.
+	/// Takes `(old id, new id)` pairs.
+	pub fn remap_object_states(&mut self, id_pairs: impl IntoIterator<Item=(ObjectId, ObjectId)>) {
+		// Take everything out first, in case one object has been given another's old id
+		let moved_states: Vec<_> = id_pairs.into_iter()
+			.filter_map(|(old_object_id, new_object_id)| Some((new_object_id, self.object_states.remove(old_object_id)?)))
+			.collect();
+
+		for (object_id, state) in moved_states {
+			self.object_states.insert(object_id, state);
+		}
+	}
+
+	pub fn check(&self, condition: &Condition) -> bool {
+		match condition {
+			Condition::Flag(name) => self.flag(name),
+			Condition::NotFlag(name) => !self.flag(name),
+			Condition::CounterAtLeast(name, value) => self.counter(name) >= *value,
+			Condition::Object(object_id, flag) => self.object_state(*object_id).get(*flag),
+
+			Condition::All(conditions) => conditions.iter().all(|condition| self.check(condition)),
+			Condition::Any(conditions) => conditions.iter().any(|condition| self.check(condition)),
+		}
+	}
+
+	fn apply(&mut self, cmd: ProgressCmd) -> Option<ProgressEvent> {
+		match cmd {
+			ProgressCmd::SetFlag(name, value) => {
+				let changed = match value {
+					true => self.flags.insert(name.clone()),
+					false => self.flags.remove(&name),
+				};
+
+				changed.then_some(ProgressEvent::FlagChanged { name, value })
+			}
+
+			ProgressCmd::SetCounter(name, value) => {
+				let previous = self.counters.insert(name.clone(), value).unwrap_or(0);
+				(previous != value).then_some(ProgressEvent::CounterChanged { name, value })
+			}
+
+			ProgressCmd::AddCounter(name, delta) => {
+				let value = self.counter(&name) + delta;
+				self.apply(ProgressCmd::SetCounter(name, value))
+			}
+
+			ProgressCmd::SetObjectFlag(object_id, flag, value) => {
+				let state = self.object_states.entry(object_id)?.or_default();
+				if state.get(flag) == value {
+					return None
+				}
+
+				state.set(flag, value);
+				Some(ProgressEvent::ObjectStateChanged { object_id, flag, value })
+			}
+
+			ProgressCmd::CompleteObjective(objective_id) => {
+				self.completed_objectives.insert(objective_id.clone())
+					.then_some(ProgressEvent::ObjectiveCompleted(objective_id))
+			}
+
+			ProgressCmd::SetRoomFog(room_id, Some(fog)) => {
+				let previous = self.room_fog.insert(room_id, fog);
+				(previous != Some(fog)).then_some(ProgressEvent::RoomFogChanged(room_id))
+			}
+
+			ProgressCmd::SetRoomFog(room_id, None) => {
+				self.room_fog.remove(room_id)
+					.map(|_| ProgressEvent::RoomFogChanged(room_id))
+			}
+		}
+	}
+}
+
+
+/// Keeps track of progression. what doors unlocked, items gathered, etc
+#[derive(Debug)]
 pub struct ProgressModel {
-	pub has_item: bool,
+	pub state: ProgressState,
+
+	progress_cmd_sub: Subscription<ProgressCmd>,
+}
+
+impl ProgressModel {
+	pub fn new(bus: &MessageBus) -> Self {
+		ProgressModel {
+			state: ProgressState::default(),
+			progress_cmd_sub: bus.subscribe(),
+		}
+	}
+
+	pub fn update(&mut self, bus: &MessageBus) {
+		for cmd in bus.poll_consume(&self.progress_cmd_sub) {
+			if let Some(event) = self.state.apply(cmd) {
+				log::info!("{event:?}");
+				bus.emit(event);
+			}
+		}
+	}
+
+	/// Records that the player is in `room_id`, a source room.
+	pub fn visit_room(&mut self, room_id: RoomId, bus: &MessageBus) {
+		if self.state.visited_rooms.insert(room_id) {
+			bus.emit(ProgressEvent::RoomVisited(room_id));
+		}
+	}
+}
+
+
+
+pub fn handle_progress_commands(ctx: &mut Context, model: &Model) -> anyhow::Result<()> {
+	if let Some(args) = ctx.console.command("flag") {
+		let (name, value) = args.split_once(' ').unwrap_or((&args, "1"));
+		if name.is_empty() {
+			anyhow::bail!("'flag' requires flag name argument");
+		}
+
+		let value = !matches!(value.trim(), "0" | "false");
+		ctx.bus.emit(ProgressCmd::SetFlag(name.to_string(), value));
+	}
+
+	if let Some(args) = ctx.console.command("counter") {
+		let Some((name, value)) = args.split_once(' ') else {
+			anyhow::bail!("'counter' requires counter name and value arguments");
+		};
+
+		let value = value.trim().parse().with_context(|| format!("Invalid counter value '{value}'"))?;
+		ctx.bus.emit(ProgressCmd::SetCounter(name.to_string(), value));
+	}
+
+	if ctx.console.command("progress").is_some() {
+		log::info!("{:#?}", model.progress.state);
+	}
+
+	Ok(())
+}
+
+
+
+#[test]
+fn progress_commands_emit_events_only_on_change() {
+	let bus = MessageBus::new();
+	let event_sub = bus.subscribe::<ProgressEvent>();
+	let mut progress = ProgressModel::new(&bus);
+
+	bus.emit(ProgressCmd::SetFlag("seal-broken".into(), true));
+	bus.emit(ProgressCmd::SetFlag("seal-broken".into(), true));
+	bus.emit(ProgressCmd::AddCounter("candles".into(), 2));
+	bus.emit(ProgressCmd::AddCounter("candles".into(), 1));
+	progress.update(&bus);
+
+	let events: Vec<_> = bus.poll_consume(&event_sub).collect();
+	assert_eq!(events, [
+		ProgressEvent::FlagChanged { name: "seal-broken".into(), value: true },
+		ProgressEvent::CounterChanged { name: "candles".into(), value: 2 },
+		ProgressEvent::CounterChanged { name: "candles".into(), value: 3 },
+	]);
+
+	assert!(progress.state.check(&Condition::All(vec![
+		Condition::Flag("seal-broken".into()),
+		Condition::CounterAtLeast("candles".into(), 3),
+	])));
+
+	assert!(!progress.state.check(&Condition::Any(vec![
+		Condition::NotFlag("seal-broken".into()),
+		Condition::CounterAtLeast("candles".into(), 4),
+	])));
 }
@@ -0,0 +1,191 @@
+use crate::prelude::*;
+use model::*;
+
+
+pub const REPLAY_DIRECTORY: &str = "replays";
+
+pub fn replay_path(name: &str) -> String {
+	format!("{REPLAY_DIRECTORY}/{name}.replay")
+}
+
+
+/// A recorded play session. Contains everything needed to reproduce it through `Model::step`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Replay {
+	// Stored in full rather than by name, so replays still work after the world has been edited.
+	pub world: World,
+	#[serde(default)]
+	pub items: ItemDefs,
+	#[serde(default)]
+	pub dialogues: DialogueDefs,
+	pub seed: u64,
+
+	pub ticks: Vec<TickInput>,
+
+	// Where the player ended up when recording stopped. Used to detect desyncs.
+	pub final_placement: Option<Placement>,
+}
+
+impl Replay {
+	pub fn load(vfs: &vfs::Vfs, name: &str) -> anyhow::Result<Replay> {
+		vfs.load_json_resource(replay_path(name))
+			.with_context(|| format!("Failed to load replay '{name}'"))
+	}
+
+	pub fn save(&self, vfs: &vfs::Vfs, name: &str) -> anyhow::Result<()> {
+		vfs.save_json_resource(replay_path(name), self)
+			.with_context(|| format!("Failed to save replay '{name}'"))
+	}
+
+	pub fn source_model(&self) -> SourceModel {
+		SourceModel {
+			world: self.world.clone(),
+			items: self.items.clone(),
+			dialogues: self.dialogues.clone(),
+		}
+	}
+
+	/// Runs the whole replay without any gfx/audio, returning the final model state.
+	pub fn run_headless(&self) -> Model {
+		let bus = MessageBus::new();
+		let source_model = self.source_model();
+		let mut model = Model::new(&self.world, self.seed, &bus);
+
+		for input in self.ticks.iter() {
+			model.step(&source_model, input, &bus);
+		}
+
+		model
+	}
+}
+
+
+/// Collects tick inputs while a session is being recorded.
+#[derive(Debug)]
+pub struct ReplayRecorder {
+	replay: Replay,
+}
+
+impl ReplayRecorder {
+	pub fn new(source_model: &SourceModel, seed: u64) -> ReplayRecorder {
+		ReplayRecorder {
+			replay: Replay {
+				world: source_model.world.clone(),
+				items: source_model.items.clone(),
+				dialogues: source_model.dialogues.clone(),
+				seed,
+				ticks: Vec::new(),
+				final_placement: None,
+			},
+		}
+	}
+
+	pub fn record(&mut self, input: &TickInput) {
+		self.replay.ticks.push(*input);
+	}
+
+	pub fn finish(mut self, final_placement: Placement) -> Replay {
+		self.replay.final_placement = Some(final_placement);
+		self.replay
+	}
+}
+
+
+/// Feeds recorded tick inputs back into the simulation.
+#[derive(Debug)]
+pub struct ReplayPlayback {
+	replay: Replay,
+	next_tick: usize,
+}
+
+impl ReplayPlayback {
+	pub fn new(replay: Replay) -> ReplayPlayback {
+		ReplayPlayback {
+			replay,
+			next_tick: 0,
+		}
+	}
+
+	pub fn next_input(&mut self) -> Option<TickInput> {
+		let input = self.replay.ticks.get(self.next_tick).copied();
+		self.next_tick += 1;
+		input
+	}
+
+	pub fn is_finished(&self) -> bool {
+		self.next_tick >= self.replay.ticks.len()
+	}
+
+	/// Compares the final recorded placement against the current one, logging any desync.
+	pub fn verify(&self, placement: Placement) -> bool {
+		match self.replay.final_placement {
+			Some(expected) if expected != placement => {
+				log::error!("Replay desync - expected final placement {expected:?}, got {placement:?}");
+				false
+			}
+
+			_ => {
+				log::info!("Replay finished after {} ticks", self.replay.ticks.len());
+				true
+			}
+		}
+	}
+}
+
+
+
+#[test]
+fn replay_survives_serialization() {
+	let world = World::new();
+
+	let source_model = SourceModel::from_world(world.clone());
+	let mut recorder = ReplayRecorder::new(&source_model, 1234);
+	let bus = MessageBus::new();
+	let mut model = Model::new(&world, 1234, &bus);
+
+	for tick in 0..600 {
+		let mut input = TickInput::default();
+		input.held.insert(Action::MoveForward);
+		input.look_delta = Vec2::new(0.003, 0.0);
+
+		if tick % 100 < 30 {
+			input.held.insert(Action::StrafeLeft);
+		}
+
+		model.step(&source_model, &input, &bus);
+		recorder.record(&input);
+	}
+
+	let replay = recorder.finish(model.player.placement);
+	let replay: Replay = serde_json::from_str(&serde_json::to_string(&replay).unwrap()).unwrap();
+
+	assert_eq!(Some(replay.run_headless().player.placement), replay.final_placement);
+}
+
+/// Any replay dropped into resource/replays is treated as a regression test.
+#[test]
+fn recorded_replays_match_final_placement() {
+	let replay_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("resource").join(REPLAY_DIRECTORY);
+	let entries = std::fs::read_dir(&replay_dir)
+		.unwrap_or_else(|err| panic!("Failed to read {}: {err}", replay_dir.display()));
+
+	let mut num_checked = 0;
+
+	for entry in entries {
+		let path = entry.unwrap().path();
+		if path.extension().is_none_or(|ext| ext != "replay") {
+			continue
+		}
+
+		let data = std::fs::read_to_string(&path).unwrap();
+		let replay: Replay = serde_json::from_str(&data)
+			.unwrap_or_else(|err| panic!("Failed to parse replay {}: {err}", path.display()));
+
+		let Some(expected) = replay.final_placement else { continue };
+
+		assert_eq!(replay.run_headless().player.placement, expected, "Replay {} desynced", path.display());
+		num_checked += 1;
+	}
+
+	assert!(num_checked > 0, "No replays with a final placement in {}", replay_dir.display());
+}
@@ -0,0 +1,347 @@
+use crate::prelude::*;
+use model::*;
+
+
+/// Authored description of what an altar asks for and what it does once the ritual is complete.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct Ritual {
+	#[serde(default)]
+	pub offering: RitualOffering,
+
+	/// Presented to the player in order once the offering has been accepted.
+	#[serde(default)]
+	pub choices: Vec<RitualChoice>,
+
+	/// Applied on completion, after the effects of any chosen options.
+	#[serde(default)]
+	pub effects: Vec<Effect>,
+
+	/// Whether the ritual can be performed again after completion.
+	#[serde(default)]
+	pub repeatable: bool,
+}
+
+impl Ritual {
+	/// An altar with no ritual defined just restores blood.
+	pub fn is_empty(&self) -> bool {
+		self.offering.is_empty() && self.choices.is_empty() && self.effects.is_empty()
+	}
+}
+
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct RitualOffering {
+	#[serde(default)]
+	pub items: Vec<ItemId>,
+
+	#[serde(default)]
+	pub blood: u32,
+
+	#[serde(default)]
+	pub salt: u32,
+}
+
+impl RitualOffering {
+	pub fn is_empty(&self) -> bool {
+		self.items.is_empty() && self.blood == 0 && self.salt == 0
+	}
+
+	/// Describes what is still missing from the offering, if anything.
+	fn missing(&self, player: &Player, inventory: &Inventory, item_defs: &ItemDefs) -> Option<String> {
+		let mut missing = Vec::new();
+
+		for item in self.items.iter() {
+			let required = self.items.iter().filter(|other| *other == item).count() as u32;
+			let name = item_defs.name(item).to_string();
+			if inventory.count(item) < required && !missing.contains(&name) {
+				missing.push(name);
+			}
+		}
+
+		// Offering blood can't kill you
+		if self.blood > 0 && player.blood <= self.blood {
+			missing.push(format!("{} blood", self.blood));
+		}
+
+		if player.salt < self.salt {
+			missing.push(format!("{} salt", self.salt));
+		}
+
+		match missing.is_empty() {
+			true => None,
+			false => Some(missing.join(", ")),
+		}
+	}
+}
+
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct RitualChoice {
+	pub prompt: String,
+	pub options: Vec<RitualOption>,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct RitualOption {
+	pub label: String,
+
+	#[serde(default)]
+	pub effects: Vec<Effect>,
+}
+
+
+
+#[derive(Debug, Clone)]
+pub enum RitualCmd {
+	Begin(ObjectId),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RitualEvent {
+	Completed(ObjectId),
+	Abandoned(ObjectId),
+}
+
+
+/// Label of the extra option added to every choice that lets the player back out.
+pub const RITUAL_ABANDON_LABEL: &str = "Step away";
+
+
+#[derive(Debug, Clone)]
+pub struct ActiveRitual {
+	pub altar_id: ObjectId,
+
+	/// Index of the chosen option for each choice made so far.
+	pub chosen_options: Vec<usize>,
+}
+
+
+/// Steps through altar rituals - checks offerings, presents choices via the hud and applies effects.
+#[derive(Debug)]
+pub struct RitualModel {
+	pub active: Option<ActiveRitual>,
+
+	ritual_cmd_sub: Subscription<RitualCmd>,
+	hud_event_sub: Subscription<HudEvent>,
+}
+
+impl RitualModel {
+	pub fn new(bus: &MessageBus) -> Self {
+		RitualModel {
+			active: None,
+
+			ritual_cmd_sub: bus.subscribe(),
+			hud_event_sub: bus.subscribe(),
+		}
+	}
+
+	pub fn update(&mut self, player: &Player, world: &ProcessedWorld, progress: &ProgressModel,
+		inventory: &Inventory, item_defs: &ItemDefs, bus: &MessageBus)
+	{
+		for cmd in bus.poll_consume(&self.ritual_cmd_sub) {
+			match cmd {
+				RitualCmd::Begin(altar_id) => self.begin(altar_id, player, world, progress, inventory, item_defs, bus),
+			}
+		}
+
+		for event in bus.poll(&self.hud_event_sub) {
+			let Some(active) = &mut self.active else { continue };
+
+			match event {
+				HudEvent::ChoiceMade(option_index) => {
+					let Some(ritual) = altar_ritual(world, active.altar_id) else {
+						self.active = None;
+						continue
+					};
+
+					let num_options = ritual.choices.get(active.chosen_options.len())
+						.map_or(0, |choice| choice.options.len());
+
+					if option_index >= num_options {
+						bus.emit(RitualEvent::Abandoned(active.altar_id));
+						bus.emit(HudCmd::ShowText("You step away from the altar".into()));
+						self.active = None;
+						continue
+					}
+
+					active.chosen_options.push(option_index);
+					self.advance(player, world, inventory, item_defs, bus);
+				}
+
+				HudEvent::ChoiceCancelled => {
+					bus.emit(RitualEvent::Abandoned(active.altar_id));
+					self.active = None;
+				}
+
+				HudEvent::DialogueFinished(_) => {}
+			}
+		}
+	}
+
+	pub fn is_active(&self) -> bool {
+		self.active.is_some()
+	}
+
+	fn begin(&mut self, altar_id: ObjectId, player: &Player, world: &ProcessedWorld, progress: &ProgressModel,
+		inventory: &Inventory, item_defs: &ItemDefs, bus: &MessageBus)
+	{
+		if self.active.is_some() {
+			return
+		}
+
+		let Some(ritual) = altar_ritual(world, altar_id) else { return };
+
+		if !ritual.repeatable && progress.state.object_state(altar_id).opened {
+			bus.emit(HudCmd::ShowText("The altar lies dormant".into()));
+			return
+		}
+
+		if let Some(missing) = ritual.offering.missing(player, inventory, item_defs) {
+			bus.emit(HudCmd::ShowText(format!("The altar demands {missing}")));
+			return
+		}
+
+		self.active = Some(ActiveRitual {
+			altar_id,
+			chosen_options: Vec::new(),
+		});
+
+		self.advance(player, world, inventory, item_defs, bus);
+	}
+
+	/// Presents the next choice, or completes the ritual if there are none left.
+	fn advance(&mut self, player: &Player, world: &ProcessedWorld, inventory: &Inventory, item_defs: &ItemDefs, bus: &MessageBus) {
+		let Some(active) = &self.active else { return };
+		let Some(ritual) = altar_ritual(world, active.altar_id) else {
+			self.active = None;
+			return
+		};
+
+		if let Some(choice) = ritual.choices.get(active.chosen_options.len()) {
+			let mut options: Vec<String> = choice.options.iter().map(|option| option.label.clone()).collect();
+			options.push(RITUAL_ABANDON_LABEL.into());
+
+			bus.emit(HudCmd::ShowChoice(HudChoice {
+				prompt: choice.prompt.clone(),
+				options,
+				selected: 0,
+			}));
+
+			return
+		}
+
+		let Some(active) = self.active.take() else { return };
+
+		// Things may have changed while choices were being made
+		if let Some(missing) = ritual.offering.missing(player, inventory, item_defs) {
+			bus.emit(HudCmd::ShowText(format!("The altar demands {missing}")));
+			bus.emit(RitualEvent::Abandoned(active.altar_id));
+			return
+		}
+
+		log::info!("Completed ritual at '{}'", world.objects[active.altar_id].name);
+
+		let offering = &ritual.offering;
+		for item in offering.items.iter() {
+			bus.emit(InventoryCmd::Take(item.clone(), 1));
+		}
+
+		if offering.blood > 0 {
+			bus.emit(ResourceCmd::Spend(Resource::Blood, offering.blood));
+		}
+
+		if offering.salt > 0 {
+			bus.emit(ResourceCmd::Spend(Resource::Salt, offering.salt));
+		}
+
+		let chosen_effects = ritual.choices.iter()
+			.zip(active.chosen_options.iter())
+			.filter_map(|(choice, &option_index)| choice.options.get(option_index))
+			.flat_map(|option| option.effects.iter());
+
+		for effect in chosen_effects.chain(ritual.effects.iter()) {
+			bus.emit(effect.clone());
+		}
+
+		if !ritual.repeatable {
+			bus.emit(ProgressCmd::SetObjectFlag(active.altar_id, ObjectFlag::Opened, true));
+		}
+
+		bus.emit(RitualEvent::Completed(active.altar_id));
+	}
+}
+
+
+fn altar_ritual(world: &ProcessedWorld, altar_id: ObjectId) -> Option<&Ritual> {
+	match &world.objects.get(altar_id)?.info {
+		ObjectInfo::Altar{ritual, ..} => Some(ritual),
+		_ => None,
+	}
+}
+
+
+
+#[test]
+fn ritual_choice_applies_chosen_effects() {
+	let mut world = World::new();
+	let spawn = world.player_spawn;
+
+	let seal_id = world.objects.insert(Object {
+		name: "seal".into(),
+		placement: Placement { position: spawn.position - spawn.forward() * 1.0, .. spawn },
+		info: ObjectInfo::Door { key_item: None, blood_cost: 0 },
+		verbs: Vec::new(),
+	});
+
+	let altar_id = world.objects.insert(Object {
+		name: "altar".into(),
+		placement: Placement { position: spawn.position + spawn.forward() * 0.3, .. spawn },
+		info: ObjectInfo::Altar {
+			ritual: Ritual {
+				offering: RitualOffering { items: vec!["candle".into()], blood: 0, salt: 5 },
+				choices: vec![RitualChoice {
+					prompt: "What do you ask for?".into(),
+					options: vec![
+						RitualOption { label: "Passage".into(), effects: vec![Effect::OpenObject { object_name: "seal".into() }] },
+						RitualOption { label: "Knowledge".into(), effects: vec![Effect::SetFlag { name: "knowledge".into(), value: true }] },
+					],
+				}],
+				effects: vec![Effect::SetFlag { name: "ritual-done".into(), value: true }],
+				repeatable: false,
+			},
+			waypoint: None,
+		},
+		verbs: Vec::new(),
+	});
+
+	let (source_model, bus, mut model) = test_model(&world);
+	model.player.salt = 10;
+
+	// Without the candle the altar refuses
+	step_with_pressed(&mut model, &source_model, &bus, None);
+	step_with_pressed(&mut model, &source_model, &bus, Some(Action::Pray));
+	step_with_pressed(&mut model, &source_model, &bus, None);
+	assert!(!model.ritual.is_active());
+	assert!(model.hud.choice.is_none());
+
+	bus.emit(InventoryCmd::Give("candle".into(), 1));
+	step_with_pressed(&mut model, &source_model, &bus, Some(Action::Pray));
+	step_with_pressed(&mut model, &source_model, &bus, None);
+	step_with_pressed(&mut model, &source_model, &bus, None);
+	assert!(model.ritual.is_active());
+	assert!(model.hud.choice.is_some());
+
+	// Pick 'Passage'
+	step_with_pressed(&mut model, &source_model, &bus, Some(Action::Interact));
+	for _ in 0..4 {
+		step_with_pressed(&mut model, &source_model, &bus, None);
+	}
+
+	assert!(!model.ritual.is_active());
+	assert!(model.progress.state.object_state(seal_id).opened);
+	assert!(model.progress.state.object_state(altar_id).opened);
+	assert!(model.progress.state.flag("ritual-done"));
+	assert!(!model.progress.state.flag("knowledge"));
+	assert!(!model.inventory.inventory.contains(&"candle".into()));
+	assert_eq!(model.player.salt, 5);
+}
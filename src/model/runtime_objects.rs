@@ -0,0 +1,339 @@
+use crate::prelude::*;
+use model::*;
+
+use slotmap::SecondaryMap;
+
+
+/// Requests to change objects while the game is running. These never touch the source world.
+#[derive(Debug, Clone)]
+pub enum ObjectCmd {
+	/// Placement is in source room space.
+	Spawn(Object),
+	Despawn(ObjectId),
+	SetEnabled(ObjectId, bool),
+
+	/// Placement is in source room space.
+	Move(ObjectId, Placement),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObjectEvent {
+	Spawned(ObjectId),
+	Despawned(ObjectId),
+}
+
+
+#[derive(Debug, Copy, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RuntimeObjectState {
+	/// Created at runtime rather than authored in the source world.
+	pub spawned: bool,
+
+	/// Authored objects can't be removed, only despawned - otherwise the next sync would bring them back.
+	pub despawned: bool,
+
+	/// Disabled objects still exist, but don't appear in the processed world.
+	pub enabled: bool,
+
+	/// Overrides the authored placement once the object has been moved at runtime. In source room space.
+	pub placement: Option<Placement>,
+
+	// Mirrored from progress, so not saved.
+	#[serde(skip)]
+	pub collected: bool,
+	#[serde(skip)]
+	pub opened: bool,
+
+	/// Always false for objects that aren't lights.
+	#[serde(skip)]
+	pub light_on: bool,
+}
+
+impl RuntimeObjectState {
+	fn new(spawned: bool) -> RuntimeObjectState {
+		RuntimeObjectState {
+			spawned,
+			despawned: false,
+			enabled: true,
+			placement: None,
+
+			collected: false,
+			opened: false,
+			light_on: false,
+		}
+	}
+
+	pub fn exists(&self) -> bool {
+		!self.despawned
+	}
+}
+
+
+/// Runtime object state as it's saved.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct RuntimeObjectsSave {
+	/// State of authored objects that has changed since the world was loaded. Keyed by source world ids.
+	pub authored: SecondaryMap<ObjectId, RuntimeObjectState>,
+
+	/// Spawned objects don't exist in the source world, so are saved whole along with the ids they had.
+	pub spawned: Vec<(ObjectId, Object, RuntimeObjectState)>,
+}
+
+
+/// Runtime layer over the objects in the source world.
+/// Keyed by the same `ObjectId`s as the source world, plus any objects spawned since.
+#[derive(Debug, Default)]
+pub struct RuntimeObjects {
+	// Authored definitions copied from the source world, and definitions of spawned objects.
+	definitions: SlotMap<ObjectId, Object>,
+	states: SecondaryMap<ObjectId, RuntimeObjectState>,
+}
+
+impl RuntimeObjects {
+	pub fn new(world: &World) -> RuntimeObjects {
+		let mut runtime_objects = RuntimeObjects::default();
+		runtime_objects.sync(world);
+		runtime_objects
+	}
+
+	pub fn get(&self, object_id: ObjectId) -> Option<(&Object, &RuntimeObjectState)> {
+		let state = self.states.get(object_id).filter(|state| state.exists())?;
+		Some((&self.definitions[object_id], state))
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item=(ObjectId, &Object, &RuntimeObjectState)> + use<'_> {
+		self.definitions.iter()
+			.map(|(object_id, object)| (object_id, object, &self.states[object_id]))
+			.filter(|(_, _, state)| state.exists())
+	}
+
+	pub fn find_by_name(&self, name: &str) -> Option<ObjectId> {
+		self.iter()
+			.find(|(_, object, _)| object.name == name)
+			.map(|(object_id, _, _)| object_id)
+	}
+
+	/// Current placement of an object, taking runtime moves into account. In source room space.
+	pub fn placement(&self, object_id: ObjectId) -> Option<Placement> {
+		let (object, state) = self.get(object_id)?;
+		Some(state.placement.unwrap_or(object.placement))
+	}
+
+	/// Picks up changes to the source world, e.g., from the editor.
+	/// Runtime state is kept for authored objects whose ids still match. Spawned objects are kept too,
+	/// but may be given new ids if the source world has started using theirs - returns `(old id, new id)` pairs for those,
+	/// so that anything keyed by them can be remapped.
+	pub fn sync(&mut self, world: &World) -> Vec<(ObjectId, ObjectId)> {
+		let mut definitions = world.objects.clone();
+		let mut states = SecondaryMap::new();
+		let mut changed_ids = Vec::new();
+
+		for object_id in definitions.keys() {
+			let state = match self.states.get(object_id) {
+				Some(state) if !state.spawned => *state,
+				_ => RuntimeObjectState::new(false),
+			};
+
+			states.insert(object_id, state);
+		}
+
+		for (object_id, state) in self.states.iter() {
+			if !state.spawned {
+				continue
+			}
+
+			let object = self.definitions[object_id].clone();
+			let new_object_id = definitions.insert(object);
+			if new_object_id != object_id {
+				log::info!("Spawned object '{}' changed id after source world change", definitions[new_object_id].name);
+				changed_ids.push((object_id, new_object_id));
+			}
+
+			states.insert(new_object_id, *state);
+		}
+
+		self.definitions = definitions;
+		self.states = states;
+
+		changed_ids
+	}
+
+	/// Returns whether anything changed.
+	pub fn apply(&mut self, cmd: ObjectCmd, bus: &MessageBus) -> bool {
+		match cmd {
+			ObjectCmd::Spawn(object) => {
+				let object_id = self.definitions.insert(object);
+				self.states.insert(object_id, RuntimeObjectState::new(true));
+				bus.emit(ObjectEvent::Spawned(object_id));
+				true
+			}
+
+			ObjectCmd::Despawn(object_id) => {
+				let Some(state) = self.states.get_mut(object_id).filter(|state| state.exists()) else {
+					return false
+				};
+
+				if state.spawned {
+					self.states.remove(object_id);
+					self.definitions.remove(object_id);
+				} else {
+					state.despawned = true;
+				}
+
+				bus.emit(ObjectEvent::Despawned(object_id));
+				true
+			}
+
+			ObjectCmd::SetEnabled(object_id, enabled) => {
+				let Some(state) = self.states.get_mut(object_id) else { return false };
+				let changed = state.enabled != enabled;
+				state.enabled = enabled;
+				changed
+			}
+
+			ObjectCmd::Move(object_id, placement) => {
+				let Some(state) = self.states.get_mut(object_id) else { return false };
+				state.placement = Some(placement);
+				true
+			}
+		}
+	}
+
+	pub fn to_save(&self) -> RuntimeObjectsSave {
+		let mut save = RuntimeObjectsSave::default();
+
+		for (object_id, state) in self.states.iter() {
+			if state.spawned {
+				save.spawned.push((object_id, self.definitions[object_id].clone(), *state));
+			} else if *state != RuntimeObjectState::new(false) {
+				save.authored.insert(object_id, *state);
+			}
+		}
+
+		save
+	}
+
+	/// Restores saved state over objects freshly synced from the source world.
+	/// Spawned objects are given new ids - returns `(saved id, new id)` pairs, so that anything keyed by them can be remapped.
+	pub fn restore(&mut self, save: &RuntimeObjectsSave) -> Vec<(ObjectId, ObjectId)> {
+		for (object_id, saved_state) in save.authored.iter() {
+			match self.states.get_mut(object_id) {
+				Some(state) if !state.spawned => *state = RuntimeObjectState { spawned: false, .. *saved_state },
+				_ => log::warn!("Saved state for object {object_id:?} doesn't match any object in the world - skipping"),
+			}
+		}
+
+		save.spawned.iter()
+			.map(|(saved_object_id, object, state)| {
+				let object_id = self.definitions.insert(object.clone());
+				self.states.insert(object_id, RuntimeObjectState { spawned: true, .. *state });
+				(*saved_object_id, object_id)
+			})
+			.collect()
+	}
+
+	pub fn apply_progress(&mut self, progress: &ProgressState) {
+		for (object_id, state) in self.states.iter_mut() {
+			let object_state = progress.object_state(object_id);
+			state.collected = object_state.collected;
+			state.opened = object_state.opened;
+			state.light_on = self.definitions[object_id].as_light()
+				.is_some_and(|light| is_light_on(light, object_state));
+		}
+	}
+
+	/// Objects that currently make up the world, with runtime state applied. Placements are in source room space.
+	pub fn resolve(&self) -> SlotMap<ObjectId, Object> {
+		let mut objects = self.definitions.clone();
+
+		objects.retain(|object_id, object| {
+			let state = &self.states[object_id];
+			if let Some(placement) = state.placement {
+				object.placement = placement;
+			}
+
+			// Unlit lights are kept, so they can still be interacted with and lit again without a rebuild - see `LightModel`.
+			// Opened doors are kept too, so they can be closed again - they just stop blocking movement.
+
+			state.exists() && state.enabled && !state.collected
+		});
+
+		objects
+	}
+}
+
+
+
+#[cfg(test)]
+fn test_object(name: &str, placement: Placement) -> Object {
+	Object {
+		name: name.into(),
+		placement,
+		info: ObjectInfo::Debug,
+		verbs: Vec::new(),
+	}
+}
+
+#[test]
+fn spawned_objects_appear_without_touching_source_world() {
+	let world = World::new();
+	let spawn = world.player_spawn;
+
+	let (source_model, bus, mut model) = test_model(&world);
+	let event_sub = bus.subscribe::<ObjectEvent>();
+
+	bus.emit(ObjectCmd::Spawn(test_object("spawned", spawn)));
+	model.step(&source_model, &TickInput::default(), &bus);
+
+	let events: Vec<_> = bus.poll_consume(&event_sub).collect();
+	let [ObjectEvent::Spawned(object_id)] = events[..] else { panic!("expected spawn event, got {events:?}") };
+
+	assert!(model.processed_world.objects.contains_key(object_id));
+	assert!(source_model.world.objects.is_empty());
+
+	bus.emit(ObjectCmd::SetEnabled(object_id, false));
+	model.step(&source_model, &TickInput::default(), &bus);
+	assert!(!model.processed_world.objects.contains_key(object_id));
+	assert!(model.processed_world.runtime_objects().get(object_id).is_some());
+
+	bus.emit(ObjectCmd::Despawn(object_id));
+	model.step(&source_model, &TickInput::default(), &bus);
+	assert!(model.processed_world.runtime_objects().get(object_id).is_none());
+}
+
+#[test]
+fn source_changes_keep_runtime_state() {
+	let mut world = World::new();
+	let spawn = world.player_spawn;
+
+	let moved_id = world.objects.insert(test_object("moved", spawn));
+	let disabled_id = world.objects.insert(test_object("disabled", spawn));
+
+	let bus = MessageBus::new();
+	let mut runtime_objects = RuntimeObjects::new(&world);
+
+	let moved_placement = Placement { position: spawn.position + Vec2::new(1.0, 0.0), .. spawn };
+	runtime_objects.apply(ObjectCmd::Move(moved_id, moved_placement), &bus);
+	runtime_objects.apply(ObjectCmd::SetEnabled(disabled_id, false), &bus);
+	runtime_objects.apply(ObjectCmd::Spawn(test_object("spawned", spawn)), &bus);
+	let spawned_id = runtime_objects.find_by_name("spawned").unwrap();
+
+	// Edit the source world, as the editor would
+	world.objects[disabled_id].name = "renamed".into();
+	let added_id = world.objects.insert(test_object("added", spawn));
+	let changed_ids = runtime_objects.sync(&world);
+
+	// The added object took the spawned object's id
+	assert_eq!(added_id, spawned_id);
+	assert_eq!(changed_ids, [(spawned_id, runtime_objects.find_by_name("spawned").unwrap())]);
+
+	assert_eq!(runtime_objects.placement(moved_id), Some(moved_placement));
+	assert!(!runtime_objects.get(disabled_id).unwrap().1.enabled);
+	assert_eq!(runtime_objects.get(disabled_id).unwrap().0.name, "renamed");
+	assert!(runtime_objects.get(added_id).unwrap().1.enabled);
+	assert!(runtime_objects.find_by_name("spawned").is_some());
+
+	let resolved = runtime_objects.resolve();
+	assert_eq!(resolved[moved_id].placement, moved_placement);
+	assert!(!resolved.contains_key(disabled_id));
+	assert_eq!(resolved.len(), 3);
+}
@@ -0,0 +1,229 @@
+use crate::prelude::*;
+use model::*;
+
+
+pub const SAVE_DIRECTORY: &str = "saves";
+
+/// Slot written when quitting to the main menu, and loaded by 'Continue'.
+pub const AUTOSAVE_SLOT: &str = "autosave";
+
+pub fn save_path(slot: &str) -> String {
+	format!("{SAVE_DIRECTORY}/{slot}.save")
+}
+
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SaveGame {
+	pub world_name: String,
+
+	pub player: PlayerSave,
+	pub progress: ProgressState,
+
+	#[serde(default)]
+	pub inventory: Inventory,
+
+	#[serde(default)]
+	pub campaign: Campaign,
+
+	#[serde(default)]
+	pub runtime_objects: RuntimeObjectsSave,
+
+	/// Worms are saved where they are, in source room space. Everything else about them starts afresh.
+	#[serde(default)]
+	pub worms: Vec<Placement>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PlayerSave {
+	// In source world space, so that saves survive changes to how rooms are processed.
+	pub placement: Placement,
+	pub pitch: f32,
+
+	pub blood: u32,
+	pub salt: u32,
+
+	#[serde(default = "default_chalk")]
+	pub chalk: u32,
+}
+
+fn default_chalk() -> u32 {
+	STARTING_CHALK
+}
+
+impl SaveGame {
+	pub fn from_model(source_model: &SourceModel, model: &Model) -> SaveGame {
+		let player = &model.player;
+
+		SaveGame {
+			world_name: source_model.world.name.clone(),
+
+			player: PlayerSave {
+				placement: model.processed_world.to_source_placement(player.placement),
+				pitch: player.pitch,
+
+				blood: player.blood,
+				salt: player.salt,
+				chalk: player.chalk,
+			},
+
+			progress: model.progress.state.clone(),
+			inventory: model.inventory.inventory.clone(),
+			campaign: model.campaign.clone(),
+
+			runtime_objects: model.processed_world.runtime_objects().to_save(),
+			worms: model.worms.worms.iter()
+				.map(|worm| model.processed_world.to_source_placement(worm.placement()))
+				.collect(),
+		}
+	}
+
+	/// Restores saved state over a freshly created model for `world_name`.
+	/// ProcessedWorld must be rebuilt afterwards to take progress into account.
+	pub fn apply_to_model(&self, model: &mut Model) {
+		let player = &mut model.player;
+
+		player.placement = model.processed_world.to_processed_placement(self.player.placement);
+		player.pitch = self.player.pitch;
+		player.blood = self.player.blood;
+		player.salt = self.player.salt;
+		player.chalk = self.player.chalk;
+
+		model.progress.state = self.progress.clone();
+		model.inventory.inventory = self.inventory.clone();
+
+		// Spawned objects get new ids, so their progress has to follow them.
+		let spawned_ids = model.processed_world.runtime_objects_mut().restore(&self.runtime_objects);
+		model.progress.state.remap_object_states(spawned_ids);
+
+		model.worms.worms = self.worms.iter()
+			.map(|&placement| Worm::new(&model.processed_world, placement))
+			.collect();
+
+		// Saves from before campaigns only know about the world they were made in.
+		model.campaign = self.campaign.clone();
+		model.campaign.current_world = self.world_name.clone();
+	}
+
+	pub fn load(vfs: &vfs::Vfs, slot: &str) -> anyhow::Result<SaveGame> {
+		vfs.load_json_resource(save_path(slot))
+			.with_context(|| format!("Failed to load save slot '{slot}'"))
+	}
+
+	pub fn save(&self, vfs: &vfs::Vfs, slot: &str) -> anyhow::Result<()> {
+		vfs.save_json_resource(save_path(slot), self)
+			.with_context(|| format!("Failed to write save slot '{slot}'"))
+	}
+
+	pub fn exists(vfs: &vfs::Vfs, slot: &str) -> bool {
+		Self::load(vfs, slot).is_ok()
+	}
+}
+
+
+
+#[test]
+fn save_game_restores_player_and_progress() {
+	let world = World::new();
+	let (source_model, bus, mut model) = test_model(&world);
+
+	let mut input = TickInput::default();
+	input.held.insert(Action::MoveForward);
+	input.held.insert(Action::TurnLeft);
+
+	for _ in 0..90 {
+		model.step(&source_model, &input, &bus);
+	}
+
+	model.player.blood = 42;
+	model.progress.state.flags.insert("has-item".into());
+
+	let save = SaveGame::from_model(&source_model, &model);
+	let save: SaveGame = serde_json::from_str(&serde_json::to_string(&save).unwrap()).unwrap();
+
+	let mut loaded_model = Model::new(&world, 1, &bus);
+	save.apply_to_model(&mut loaded_model);
+
+	assert_eq!(loaded_model.player.placement, model.player.placement);
+	assert_eq!(loaded_model.player.pitch, model.player.pitch);
+	assert_eq!(loaded_model.player.blood, 42);
+	assert!(loaded_model.progress.state.flag("has-item"));
+}
+
+#[test]
+fn save_game_restores_runtime_objects() {
+	let mut world = World::new();
+	let spawn = world.player_spawn;
+	let authored_id = world.objects.insert(Object {
+		name: "authored".into(),
+		placement: spawn,
+		info: ObjectInfo::Debug,
+		verbs: Vec::new(),
+	});
+
+	let (source_model, bus, mut model) = test_model(&world);
+
+	let spawned = Object { name: "spawned".into(), .. world.objects[authored_id].clone() };
+	bus.emit(ObjectCmd::Spawn(spawned));
+	bus.emit(ObjectCmd::SetEnabled(authored_id, false));
+	model.step(&source_model, &TickInput::default(), &bus);
+
+	let spawned_id = model.processed_world.runtime_objects().find_by_name("spawned").unwrap();
+	bus.emit(ProgressCmd::SetObjectFlag(spawned_id, ObjectFlag::Opened, true));
+	model.step(&source_model, &TickInput::default(), &bus);
+
+	let save = SaveGame::from_model(&source_model, &model);
+	let save: SaveGame = serde_json::from_str(&serde_json::to_string(&save).unwrap()).unwrap();
+
+	let mut loaded_model = Model::new(&world, 1, &bus);
+	save.apply_to_model(&mut loaded_model);
+	bus.emit(WorldChangedEvent);
+	loaded_model.step(&source_model, &TickInput::default(), &bus);
+
+	let runtime_objects = loaded_model.processed_world.runtime_objects();
+	let loaded_spawned_id = runtime_objects.find_by_name("spawned").unwrap();
+	assert!(loaded_model.progress.state.object_state(loaded_spawned_id).opened);
+	assert!(!runtime_objects.get(authored_id).unwrap().1.enabled);
+}
+
+#[test]
+fn save_game_restores_worms() {
+	let world = World::new();
+	let spawn = world.player_spawn;
+
+	let (source_model, bus, mut model) = test_model(&world);
+
+	let worm_placement = Placement { position: spawn.position + Vec2::new(1.0, 0.0), .. spawn };
+	bus.emit(WormCmd::Spawn(worm_placement));
+	model.step(&source_model, &TickInput::default(), &bus);
+
+	let save = SaveGame::from_model(&source_model, &model);
+	let save: SaveGame = serde_json::from_str(&serde_json::to_string(&save).unwrap()).unwrap();
+
+	let mut loaded_model = Model::new(&world, 1, &bus);
+	save.apply_to_model(&mut loaded_model);
+
+	let [worm] = &loaded_model.worms.worms[..] else { panic!("expected one worm") };
+	let head = model.worms.worms[0].head();
+	assert!((worm.head().position - head.position).length() < 0.01);
+}
+
+#[test]
+fn save_game_restores_room_fog() {
+	let world = World::new();
+	let spawn = world.player_spawn;
+
+	let (source_model, bus, mut model) = test_model(&world);
+
+	bus.emit(ProgressCmd::SetRoomFog(spawn.room_id, Some(FogParameters { distance: 5.0, .. FogParameters::default() })));
+	model.step(&source_model, &TickInput::default(), &bus);
+
+	let save = SaveGame::from_model(&source_model, &model);
+	let save: SaveGame = serde_json::from_str(&serde_json::to_string(&save).unwrap()).unwrap();
+
+	let mut loaded_model = Model::new(&world, 1, &bus);
+	save.apply_to_model(&mut loaded_model);
+	bus.emit(WorldChangedEvent);
+	loaded_model.step(&source_model, &TickInput::default(), &bus);
+
+	assert_eq!(loaded_model.environment.fog.distance, 5.0);
+}
@@ -0,0 +1,392 @@
+use crate::prelude::*;
+use model::*;
+
+use actions::ActionSet;
+
+/// Length of a single simulation tick in seconds.
+pub const SIM_DT: f32 = 1.0 / 60.0;
+
+/// Upper bound on ticks run per frame, so a long hitch doesn't stall the game trying to catch up.
+pub const MAX_TICKS_PER_FRAME: u32 = 8;
+
+
+/// Everything the simulation needs to know about player input for a single tick.
+#[derive(Debug, Copy, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TickInput {
+	pub held: ActionSet,
+	pub pressed: ActionSet,
+
+	/// Analog movement, x: strafe, y: forward.
+	pub move_axis: Vec2,
+
+	/// Analog look, scaled by turn speed. x: yaw, y: pitch.
+	pub look_axis: Vec2,
+
+	/// Absolute look delta in radians. Not scaled by tick length.
+	pub look_delta: Vec2,
+}
+
+impl Default for TickInput {
+	fn default() -> TickInput {
+		TickInput {
+			held: ActionSet::empty(),
+			pressed: ActionSet::empty(),
+			move_axis: Vec2::zero(),
+			look_axis: Vec2::zero(),
+			look_delta: Vec2::zero(),
+		}
+	}
+}
+
+impl TickInput {
+	pub fn is_down(&self, action: Action) -> bool {
+		self.held.contains(action)
+	}
+
+	pub fn just_down(&self, action: Action) -> bool {
+		self.pressed.contains(action)
+	}
+
+	/// Folds in a new frames worth of input. Edge triggered and accumulated input is kept until
+	/// `consume_transient` is called, so it isn't lost on frames where no tick runs.
+	pub fn accumulate(&mut self, actions: &actions::ActionState) {
+		self.held = actions.held();
+		self.pressed = self.pressed.union(actions.pressed());
+		self.move_axis = actions.move_axis();
+		self.look_axis = actions.look_axis();
+		self.look_delta += actions.mouse_look_delta();
+	}
+
+	/// Clears input that should only be seen by a single tick.
+	pub fn consume_transient(&mut self) {
+		self.pressed = ActionSet::empty();
+		self.look_delta = Vec2::zero();
+	}
+}
+
+
+impl Model {
+	pub fn new(world: &World, seed: u64, bus: &MessageBus) -> Model {
+		use rand::SeedableRng;
+
+		let progress = ProgressModel::new(bus);
+		let processed_world = ProcessedWorld::new(world, &progress, bus);
+
+		Model {
+			player: Player {
+				placement: processed_world.to_processed_placement(world.player_spawn),
+				pitch: 0.0,
+
+				step_accumulator: 0.0,
+
+				blood: 100,
+				salt: 100,
+
+				free_pos: Vec3::zero(),
+				free_cam: false,
+
+				ward_time_remaining: 0.0,
+				chalk: STARTING_CHALK,
+
+				hack_height_change: None,
+			},
+
+			interactions: Interactions::new(bus),
+			environment: EnvironmentModel::new(&processed_world, bus),
+			hud: HudModel::new(bus),
+			processed_world,
+
+			progress,
+			campaign: Campaign::new(world.name.clone()),
+			inventory: InventoryModel::new(bus),
+			economy: EconomyModel::new(bus),
+			ritual: RitualModel::new(bus),
+			travel: TravelModel::new(bus),
+			triggers: TriggerModel::new(),
+			worms: WormModel::new(bus),
+			markers: MarkerModel::new(bus),
+			objectives: ObjectiveModel::new(),
+			lights: LightModel::new(),
+			effects: EffectExecutor::new(bus),
+
+			seed,
+			rng: rand::rngs::StdRng::seed_from_u64(seed),
+		}
+	}
+
+	/// Moves the player into another world, arriving at the object named `target_object` if there is one.
+	/// Inventory, resources and flags carry over, and the world is restored as it was left if it's been visited before.
+	pub fn change_world(&mut self, world: &World, target_object: Option<&str>, bus: &MessageBus) {
+		let leaving_objects = self.processed_world.runtime_objects().to_save();
+		let arriving_objects = self.campaign.switch_world(&world.name, &mut self.progress.state, leaving_objects);
+
+		self.processed_world = ProcessedWorld::new(world, &self.progress, bus);
+		self.processed_world.restore_runtime_objects(world, &arriving_objects, &mut self.progress);
+		self.interactions = Interactions::new(bus);
+		self.triggers = TriggerModel::new();
+		self.worms = WormModel::new(bus);
+		self.objectives = ObjectiveModel::new();
+		self.lights = LightModel::new();
+		self.ritual = RitualModel::new(bus);
+		self.travel = TravelModel::new(bus);
+		self.environment = EnvironmentModel::new(&self.processed_world, bus);
+
+		let target = target_object.and_then(|object_name| {
+			let object = world.objects.values().find(|object| object.name == object_name);
+			if object.is_none() {
+				log::warn!("World '{}' has no object '{object_name}' to arrive at", world.name);
+			}
+
+			object
+		});
+
+		let arrival = target.map_or(world.player_spawn, |object| object.placement);
+		self.player.placement = self.processed_world.to_processed_placement(arrival);
+		self.player.pitch = 0.0;
+	}
+
+	/// Advances the simulation by exactly `SIM_DT`. Doesn't touch gfx, audio or egui, so can be driven headless.
+	pub fn step(&mut self, source_model: &SourceModel, input: &TickInput, bus: &MessageBus) {
+		let Model { processed_world, player, progress, inventory, economy, ritual, travel, interactions, triggers, worms, markers, objectives, lights, effects, environment, hud, rng, .. } = self;
+		let source_world = &source_model.world;
+
+		progress.update(bus);
+		inventory.update(&source_model.items, bus);
+		processed_world.update(source_world, progress, bus);
+
+		// TODO(pat.m): needs to happen somewhere else, but has to happen after processed world update
+		{
+			// Make sure player doesn't suddenly end up in a room that no longer exists.
+			if !player.placement.room_id.is_valid(processed_world.geometry()) {
+				player.placement = processed_world.to_processed_placement(source_world.player_spawn);
+			}
+		}
+
+		economy.update(player, processed_world, source_world, SIM_DT, bus);
+
+		player.update(input, SIM_DT, processed_world, hud, bus);
+		progress.visit_room(processed_world.to_source_placement(player.placement).room_id, bus);
+		markers.update(player, processed_world, progress, bus);
+		interactions.update(player, processed_world, progress, &inventory.inventory, &source_model.items, bus);
+		triggers.update(player, processed_world, source_world, progress, SIM_DT, bus);
+		worms.update(player, processed_world, &environment.fog, rng, SIM_DT, bus);
+		ritual.update(player, processed_world, progress, &inventory.inventory, &source_model.items, bus);
+		travel.update(player, processed_world, progress, bus);
+		effects.update(player, processed_world, progress, bus);
+		lights.update(processed_world, SIM_DT);
+		objectives.update(source_world, player, processed_world, progress, &inventory.inventory, bus);
+
+		hud.update(player, &progress.state, &source_model.dialogues, SIM_DT, bus);
+
+		environment.update(player, processed_world, bus);
+	}
+}
+
+
+
+#[cfg(test)]
+fn load_test_world(source: &str) -> World {
+	serde_json::from_str(source).expect("Failed to parse test world")
+}
+
+/// A fresh model for `world`, with its own bus.
+#[cfg(test)]
+pub fn test_model(world: &World) -> (SourceModel, MessageBus, Model) {
+	let source_model = SourceModel::from_world(world.clone());
+	let bus = MessageBus::new();
+	let model = Model::new(world, 0, &bus);
+	(source_model, bus, model)
+}
+
+/// Steps the model once, with `action` pressed for just that tick.
+#[cfg(test)]
+pub fn step_with_pressed(model: &mut Model, source_model: &SourceModel, bus: &MessageBus, action: Option<Action>) {
+	let mut input = TickInput::default();
+	if let Some(action) = action {
+		input.held.insert(action);
+		input.pressed.insert(action);
+	}
+
+	model.step(source_model, &input, bus);
+}
+
+#[cfg(test)]
+fn run_scripted_path(world: &World, script: &[(u32, TickInput)]) -> Model {
+	let (source_model, bus, mut model) = test_model(world);
+
+	for (num_ticks, input) in script {
+		for tick in 0..*num_ticks {
+			let mut input = *input;
+			if tick > 0 {
+				input.consume_transient();
+			}
+
+			model.step(&source_model, &input, &bus);
+		}
+	}
+
+	model
+}
+
+#[cfg(test)]
+fn held(actions: &[Action]) -> TickInput {
+	let mut input = TickInput::default();
+	for &action in actions {
+		input.held.insert(action);
+	}
+	input
+}
+
+#[test]
+fn walking_into_wall_stays_in_room() {
+	let world = World::new();
+
+	// Room is 4x4 centered on the origin, so 10 seconds of walking is plenty to reach the wall.
+	let model = run_scripted_path(&world, &[
+		(600, held(&[Action::MoveForward])),
+	]);
+
+	let placement = model.player.placement;
+	assert_eq!(model.processed_world.to_source_placement(placement).room_id, world.player_spawn.room_id);
+	assert!(model.processed_world.geometry().room_contains_point(placement.room_id, placement.position));
+
+	let travelled = (placement.position - world.player_spawn.position).length();
+	assert!(travelled > 1.5, "player only travelled {travelled}");
+	assert!(travelled < 2.0, "player travelled {travelled}, passing through wall");
+}
+
+#[test]
+fn closed_doors_block_movement() {
+	let mut world = World::new();
+	let spawn = world.player_spawn;
+
+	let door_id = world.objects.insert(Object {
+		name: "door".into(),
+		placement: Placement {
+			position: spawn.position + spawn.forward(),
+			.. spawn
+		},
+		info: ObjectInfo::Door { key_item: None, blood_cost: 0 },
+		verbs: Vec::new(),
+	});
+
+	let (source_model, bus, mut model) = test_model(&world);
+	let travelled = |model: &Model| (model.player.placement.position - spawn.position).dot(spawn.forward());
+
+	let walk = held(&[Action::MoveForward]);
+	for _ in 0..120 {
+		model.step(&source_model, &walk, &bus);
+	}
+
+	let blocked_at = travelled(&model);
+	assert!(blocked_at > 0.8, "player only travelled {blocked_at}");
+	assert!(blocked_at < 1.0 - PLAYER_RADIUS + 0.001, "player travelled {blocked_at}, passing through door");
+
+	bus.emit(ProgressCmd::SetObjectFlag(door_id, ObjectFlag::Opened, true));
+	for _ in 0..60 {
+		model.step(&source_model, &walk, &bus);
+	}
+
+	let open_travelled = travelled(&model);
+	assert!(open_travelled > 1.5, "player only travelled {open_travelled} through open door");
+}
+
+#[test]
+fn keys_and_stick_together_move_at_full_speed() {
+	let world = World::new();
+
+	let mut stick_only = TickInput::default();
+	stick_only.move_axis = Vec2::new(0.0, 1.0);
+
+	let mut stick_and_key = held(&[Action::MoveForward, Action::StrafeRight]);
+	stick_and_key.move_axis = Vec2::new(0.0, 1.0);
+
+	let distance = |input: TickInput| {
+		let model = run_scripted_path(&world, &[(30, input)]);
+		(model.player.placement.position - world.player_spawn.position).length()
+	};
+
+	let full_speed = distance(stick_only);
+	assert!(full_speed > 0.1);
+	assert!((distance(stick_and_key) - full_speed).abs() < 0.001);
+	assert!((distance(held(&[Action::MoveForward, Action::StrafeLeft])) - full_speed).abs() < 0.001);
+}
+
+#[test]
+fn turning_for_one_second() {
+	let world = World::new();
+
+	let model = run_scripted_path(&world, &[
+		(60, held(&[Action::TurnRight])),
+	]);
+
+	// One second of turning at 140°/s
+	let expected_yaw = 140.0f32.to_radians();
+	assert!((model.player.placement.yaw - expected_yaw).abs() < 0.001);
+}
+
+#[test]
+fn scripted_path_through_default_world_is_deterministic() {
+	let world = load_test_world(include_str!("../../resource/worlds/default.world"));
+	let spawn = world.player_spawn;
+
+	// Stays well clear of the walls of the spawn room, so the expected placement can be worked out by hand.
+	let script = [
+		(30, held(&[Action::MoveForward])),
+		(15, held(&[Action::TurnRight])),
+		(10, held(&[Action::MoveForward, Action::Sprint])),
+	];
+
+	let first = run_scripted_path(&world, &script);
+	let second = run_scripted_path(&world, &script);
+
+	let first_placement = first.player.placement;
+	let second_placement = second.player.placement;
+
+	assert_eq!(first_placement.room_id, second_placement.room_id);
+	assert_eq!(first_placement.position, second_placement.position);
+	assert_eq!(first_placement.yaw, second_placement.yaw);
+
+	// Half a second of walking, a quarter second of turning at 140°/s, then a sixth of a second sprinting at double speed
+	let turned = Placement { yaw: spawn.yaw + 140.0f32.to_radians() * 0.25, .. spawn };
+	let expected_position = spawn.position + spawn.forward() * 0.5 + turned.forward() * 2.0 / 6.0;
+
+	let source_placement = first.processed_world.to_source_placement(first_placement);
+	assert_eq!(source_placement.room_id, spawn.room_id);
+	assert!((source_placement.yaw - turned.yaw).abs() < 0.001, "expected yaw {}, got {}", turned.yaw, source_placement.yaw);
+	assert!((source_placement.position - expected_position).length() < 0.01,
+		"expected player at {expected_position:?}, got {:?}", source_placement.position);
+}
+
+#[test]
+fn changing_world_arrives_at_target_object() {
+	let first_world = World::new();
+	let first_source_model = SourceModel::from_world(first_world.clone());
+
+	let mut second_world = World::new();
+	second_world.name = "second".into();
+
+	let arrival = Placement { position: Vec2::new(1.0, -0.5), yaw: 1.0, .. second_world.player_spawn };
+	second_world.objects.insert(Object {
+		name: "ladder".into(),
+		placement: arrival,
+		info: ObjectInfo::Ladder { target_world: "default".into(), target_object: "ladder".into() },
+		verbs: Vec::new(),
+	});
+
+	let second_source_model = SourceModel::from_world(second_world.clone());
+
+	let bus = MessageBus::new();
+	let mut model = Model::new(&first_world, 0, &bus);
+
+	bus.emit(InventoryCmd::Give("candle".into(), 1));
+	bus.emit(ProgressCmd::SetFlag("visited-first".into(), true));
+	model.step(&first_source_model, &TickInput::default(), &bus);
+
+	model.change_world(&second_world, Some("ladder"), &bus);
+	model.step(&second_source_model, &TickInput::default(), &bus);
+
+	assert_eq!(model.processed_world.to_source_placement(model.player.placement), arrival);
+	assert!(model.inventory.inventory.contains(&"candle".into()));
+	assert!(model.progress.state.flag("visited-first"));
+}
@@ -0,0 +1,235 @@
+use crate::prelude::*;
+use model::*;
+
+use std::f32::consts::PI;
+
+
+/// Blood paid to travel between altars.
+pub const TRAVEL_BLOOD_COST: u32 = 10;
+
+/// Label of the extra option added to the travel list that lets the player stay where they are.
+pub const TRAVEL_STAY_LABEL: &str = "Stay";
+
+/// How far in front of the destination altar the player arrives.
+const TRAVEL_ARRIVAL_DISTANCE: f32 = 0.5;
+
+
+#[derive(Debug, Clone)]
+pub enum TravelCmd {
+	/// Lists the other attuned altars, and travels to whichever is chosen.
+	Open(ObjectId),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TravelEvent {
+	Travelled { from: ObjectId, to: ObjectId },
+}
+
+
+#[derive(Debug, Clone)]
+pub struct ActiveTravel {
+	pub from_altar: ObjectId,
+
+	/// Altars in the order they're listed.
+	pub destinations: Vec<ObjectId>,
+}
+
+
+/// Fast travel between attuned altars in the current world. The list of destinations is presented via the hud.
+#[derive(Debug)]
+pub struct TravelModel {
+	pub active: Option<ActiveTravel>,
+
+	travel_cmd_sub: Subscription<TravelCmd>,
+	hud_event_sub: Subscription<HudEvent>,
+}
+
+impl TravelModel {
+	pub fn new(bus: &MessageBus) -> Self {
+		TravelModel {
+			active: None,
+
+			travel_cmd_sub: bus.subscribe(),
+			hud_event_sub: bus.subscribe(),
+		}
+	}
+
+	pub fn update(&mut self, player: &mut Player, world: &ProcessedWorld, progress: &ProgressModel, bus: &MessageBus) {
+		for cmd in bus.poll_consume(&self.travel_cmd_sub) {
+			match cmd {
+				TravelCmd::Open(from_altar) => self.open(from_altar, world, progress, bus),
+			}
+		}
+
+		for event in bus.poll(&self.hud_event_sub) {
+			let Some(active) = &self.active else { continue };
+
+			match event {
+				HudEvent::ChoiceMade(option_index) => {
+					if let Some(&to_altar) = active.destinations.get(option_index) {
+						travel(active.from_altar, to_altar, player, world, bus);
+					}
+
+					self.active = None;
+				}
+
+				HudEvent::ChoiceCancelled => {
+					self.active = None;
+				}
+
+				HudEvent::DialogueFinished(_) => {}
+			}
+		}
+	}
+
+	pub fn is_active(&self) -> bool {
+		self.active.is_some()
+	}
+
+	fn open(&mut self, from_altar: ObjectId, world: &ProcessedWorld, progress: &ProgressModel, bus: &MessageBus) {
+		if self.active.is_some() {
+			return
+		}
+
+		let destinations: Vec<_> = attuned_waypoints(world, &progress.state).into_iter()
+			.filter(|&(altar_id, _)| altar_id != from_altar)
+			.collect();
+
+		if destinations.is_empty() {
+			bus.emit(HudCmd::ShowText("There is nowhere else to go".into()));
+			return
+		}
+
+		let mut options: Vec<String> = destinations.iter().map(|(_, waypoint)| waypoint.to_string()).collect();
+		options.push(TRAVEL_STAY_LABEL.into());
+
+		bus.emit(HudCmd::ShowChoice(HudChoice {
+			prompt: format!("Travel where? It costs {TRAVEL_BLOOD_COST} blood"),
+			options,
+			selected: 0,
+		}));
+
+		self.active = Some(ActiveTravel {
+			from_altar,
+			destinations: destinations.into_iter().map(|(altar_id, _)| altar_id).collect(),
+		});
+	}
+}
+
+
+/// Altars in the current world that the player has attuned to, with their waypoint names. Sorted by name.
+pub fn attuned_waypoints<'w>(world: &'w ProcessedWorld, progress: &ProgressState) -> Vec<(ObjectId, &'w str)> {
+	let mut waypoints: Vec<_> = world.runtime_objects().iter()
+		.filter(|&(altar_id, _, _)| progress.object_state(altar_id).attuned)
+		.filter_map(|(altar_id, object, _)| match &object.info {
+			ObjectInfo::Altar{waypoint: Some(waypoint), ..} => Some((altar_id, waypoint.as_str())),
+			_ => None,
+		})
+		.collect();
+
+	waypoints.sort_by_key(|&(_, waypoint)| waypoint);
+	waypoints
+}
+
+
+fn travel(from_altar: ObjectId, to_altar: ObjectId, player: &mut Player, world: &ProcessedWorld, bus: &MessageBus) {
+	// Travelling can't kill you
+	if player.blood <= TRAVEL_BLOOD_COST {
+		bus.emit(HudCmd::ShowText(format!("Travel demands {TRAVEL_BLOOD_COST} blood")));
+		return
+	}
+
+	let Some(altar_placement) = world.runtime_objects().placement(to_altar) else {
+		log::warn!("Tried to travel to an altar that no longer exists");
+		return
+	};
+
+	bus.emit(ResourceCmd::Spend(Resource::Blood, TRAVEL_BLOOD_COST));
+
+	player.placement = arrival_placement(world, altar_placement);
+	player.pitch = 0.0;
+
+	bus.emit(TravelEvent::Travelled { from: from_altar, to: to_altar });
+}
+
+/// Stands the player in front of an altar, facing it. Falls back to the altar itself if there's no room in front of it.
+/// `altar_placement` is in source room space, the result is in processed room space.
+fn arrival_placement(world: &ProcessedWorld, altar_placement: Placement) -> Placement {
+	let in_front = Placement {
+		position: altar_placement.position + altar_placement.forward() * TRAVEL_ARRIVAL_DISTANCE,
+		yaw: altar_placement.yaw + PI,
+		.. altar_placement
+	};
+
+	// Processed rooms share source room coordinates, so this only needs to find which one the position ended up in
+	let find_processed_room = |placement: Placement| {
+		world.to_processed_rooms(placement.room_id).into_iter()
+			.find(|&room_id| world.geometry().room_contains_point(room_id, placement.position))
+			.map(|room_id| Placement { room_id, .. placement })
+	};
+
+	find_processed_room(in_front)
+		.unwrap_or_else(|| world.to_processed_placement(Placement { yaw: in_front.yaw, .. altar_placement }))
+}
+
+
+
+#[cfg(test)]
+fn waypoint_altar(name: &str, placement: Placement) -> Object {
+	Object {
+		name: name.into(),
+		placement,
+		info: ObjectInfo::Altar { ritual: Ritual::default(), waypoint: Some(name.into()) },
+		verbs: Vec::new(),
+	}
+}
+
+#[test]
+fn travelling_between_attuned_altars_costs_blood() {
+	let mut world = World::new();
+	let spawn = world.player_spawn;
+
+	let near_id = world.objects.insert(waypoint_altar("Near Shrine", Placement { position: spawn.position + spawn.forward() * 0.3, .. spawn }));
+	let far_placement = Placement { position: spawn.position - spawn.forward() * 1.5, .. spawn };
+	let far_id = world.objects.insert(waypoint_altar("Far Shrine", far_placement));
+
+	let (source_model, bus, mut model) = test_model(&world);
+	let event_sub = bus.subscribe::<TravelEvent>();
+
+	step_with_pressed(&mut model, &source_model, &bus, None);
+	assert_eq!(model.interactions.hovered_object, Some(near_id));
+
+	// Attune, then try to travel with nowhere to go
+	step_with_pressed(&mut model, &source_model, &bus, Some(Action::Interact));
+	step_with_pressed(&mut model, &source_model, &bus, None);
+	assert!(model.progress.state.object_state(near_id).attuned);
+
+	step_with_pressed(&mut model, &source_model, &bus, Some(Action::Interact));
+	step_with_pressed(&mut model, &source_model, &bus, None);
+	assert!(!model.travel.is_active());
+
+	bus.emit(ProgressCmd::SetObjectFlag(far_id, ObjectFlag::Attuned, true));
+	step_with_pressed(&mut model, &source_model, &bus, None);
+	assert_eq!(attuned_waypoints(&model.processed_world, &model.progress.state), [(far_id, "Far Shrine"), (near_id, "Near Shrine")]);
+
+	step_with_pressed(&mut model, &source_model, &bus, Some(Action::Interact));
+	step_with_pressed(&mut model, &source_model, &bus, None);
+	assert!(model.travel.is_active());
+	assert_eq!(model.hud.choice.as_ref().unwrap().options, ["Far Shrine", TRAVEL_STAY_LABEL]);
+
+	// Pick 'Far Shrine'
+	let blood = model.player.blood;
+	step_with_pressed(&mut model, &source_model, &bus, Some(Action::Interact));
+	for _ in 0..3 {
+		step_with_pressed(&mut model, &source_model, &bus, None);
+	}
+
+	assert_eq!(bus.poll_consume(&event_sub).collect::<Vec<_>>(), [TravelEvent::Travelled { from: near_id, to: far_id }]);
+	assert_eq!(model.player.blood, blood - TRAVEL_BLOOD_COST);
+
+	// Stood in front of the altar, facing it, in a room that exists
+	let player = model.player.placement;
+	assert!(model.processed_world.geometry().room_contains_point(player.room_id, player.position));
+	assert!((player.position - (far_placement.position + far_placement.forward() * TRAVEL_ARRIVAL_DISTANCE)).length() < 0.01);
+	assert!(player.forward().dot(far_placement.forward()) < -0.99);
+}
@@ -0,0 +1,118 @@
+use crate::prelude::*;
+use model::*;
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TriggerEvent {
+	Entered(TriggerId),
+	Exited(TriggerId),
+}
+
+
+/// Tracks which triggers the player is inside of, and emits their effects.
+#[derive(Debug)]
+pub struct TriggerModel {
+	// Triggers the player is currently inside, and the time until their next `on_stay`.
+	occupied: HashMap<TriggerId, f32>,
+}
+
+impl TriggerModel {
+	pub fn new() -> Self {
+		TriggerModel {
+			occupied: HashMap::default(),
+		}
+	}
+
+	pub fn update(&mut self, player: &Player, world: &ProcessedWorld, source_world: &World, progress: &ProgressModel, dt: f32, bus: &MessageBus) {
+		// Triggers are authored in source room space
+		let placement = world.to_source_placement(player.placement);
+
+		self.occupied.retain(|trigger_id, _| source_world.triggers.contains_key(*trigger_id));
+
+		for (trigger_id, trigger) in source_world.triggers.iter() {
+			let inside = trigger.contains(placement.room_id, placement.position)
+				&& trigger.condition.as_ref().is_none_or(|condition| progress.state.check(condition));
+
+			match (inside, self.occupied.get_mut(&trigger_id)) {
+				(true, None) => {
+					log::info!("Entered trigger '{}'", trigger.name);
+					self.occupied.insert(trigger_id, trigger.stay_interval);
+					emit_effects(&trigger.on_enter, bus);
+					bus.emit(TriggerEvent::Entered(trigger_id));
+				}
+
+				(true, Some(time_until_stay)) => {
+					*time_until_stay -= dt;
+					if *time_until_stay <= 0.0 {
+						*time_until_stay += trigger.stay_interval.max(SIM_DT);
+						emit_effects(&trigger.on_stay, bus);
+					}
+				}
+
+				(false, Some(_)) => {
+					log::info!("Exited trigger '{}'", trigger.name);
+					self.occupied.remove(&trigger_id);
+					emit_effects(&trigger.on_exit, bus);
+					bus.emit(TriggerEvent::Exited(trigger_id));
+				}
+
+				(false, None) => {}
+			}
+		}
+	}
+
+	pub fn is_inside(&self, trigger_id: TriggerId) -> bool {
+		self.occupied.contains_key(&trigger_id)
+	}
+}
+
+
+
+
+#[test]
+fn walking_through_trigger_fires_enter_and_exit() {
+	let mut world = World::new();
+	let spawn = world.player_spawn;
+
+	let mut trigger = Trigger::new("strip", spawn.room_id, TriggerShape::Circle {
+		center: spawn.position + spawn.forward() * 0.5,
+		radius: 0.2,
+	});
+
+	trigger.on_enter.push(Effect::SetFlag { name: "entered".into(), value: true });
+	trigger.on_exit.push(Effect::SetFlag { name: "exited".into(), value: true });
+
+	let trigger_id = world.triggers.insert(trigger);
+
+	let (source_model, bus, mut model) = test_model(&world);
+	let event_sub = bus.subscribe::<TriggerEvent>();
+
+	let mut input = TickInput::default();
+	input.held.insert(Action::MoveForward);
+
+	let mut events = Vec::new();
+
+	// Walk forward for one second - far enough to pass all the way through
+	for _ in 0..60 {
+		model.step(&source_model, &input, &bus);
+		events.extend(bus.poll_consume(&event_sub));
+	}
+
+	model.step(&source_model, &TickInput::default(), &bus);
+
+	assert_eq!(events, [TriggerEvent::Entered(trigger_id), TriggerEvent::Exited(trigger_id)]);
+	assert!(!model.triggers.is_inside(trigger_id));
+	assert!(model.progress.state.flag("entered"));
+	assert!(model.progress.state.flag("exited"));
+}
+
+#[test]
+fn polygon_trigger_contains() {
+	let shape = TriggerShape::Polygon {
+		points: vec![Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0), Vec2::new(1.0, 1.0), Vec2::new(0.0, 1.0)],
+	};
+
+	assert!(shape.contains(Vec2::new(0.5, 0.5)));
+	assert!(!shape.contains(Vec2::new(1.5, 0.5)));
+	assert!(!shape.contains(Vec2::new(0.5, -0.5)));
+}
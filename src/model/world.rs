@@ -1,10 +1,12 @@
 use crate::prelude::*;
-use model::{Placement, FogParameters};
+use model::{Placement, FogParameters, Objective};
 
 mod object;
 mod geometry;
+mod trigger;
 pub use object::*;
 pub use geometry::*;
+pub use trigger::*;
 
 // world is set of rooms, described by walls.
 // rooms are connected by wall pairs
@@ -27,11 +29,18 @@ pub struct World {
 	// TODO(pat.m): split out static vs scripted objects
 	pub objects: SlotMap<ObjectId, Object>,
 
+	#[serde(default)]
+	pub triggers: SlotMap<TriggerId, Trigger>,
+
 	pub player_spawn: Placement,
 
 	// TODO(pat.m): split out into 'environment settings'
 	// TODO(pat.m): can this be specified per room?
 	pub fog: FogParameters,
+
+	/// Completing all of these completes the world.
+	#[serde(default)]
+	pub objectives: Vec<Objective>,
 }
 
 impl World {
@@ -44,6 +53,7 @@ impl World {
 
 			geometry,
 			objects: SlotMap::with_key(),
+			triggers: SlotMap::with_key(),
 
 			player_spawn: Placement {
 				room_id: first_room,
@@ -52,6 +62,7 @@ impl World {
 			},
 
 			fog: FogParameters::default(),
+			objectives: Vec::new(),
 		}
 	}
 }
@@ -64,6 +75,7 @@ pub fn generate() -> World {
 	World {
 		name: "generated".into(),
 		objects: SlotMap::with_key(),
+		triggers: SlotMap::with_key(),
 		fog: FogParameters {
 			color: Color::grey(0.1),
 			start: 0.0,
@@ -78,6 +90,7 @@ pub fn generate() -> World {
 			yaw: 0.0,
 		},
 
+		objectives: Vec::new(),
 		geometry,
 	}
 }
@@ -46,6 +46,10 @@ pub struct RoomDef {
 	pub floor_color: Color,
 	pub ceiling_color: Color,
 	pub height: f32,
+
+	/// Blood lost per second while standing in this room.
+	#[serde(default)]
+	pub blood_drain: f32,
 }
 
 /// Describes world layout via half-edge structure
@@ -224,6 +228,8 @@ impl Default for RoomDef {
 			ceiling_color: Color::white(),
 
 			height: 1.0,
+
+			blood_drain: 0.0,
 		}
 	}
 }
\ No newline at end of file
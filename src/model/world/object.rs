@@ -1,6 +1,8 @@
 use crate::prelude::*;
 
-use model::Placement;
+use model::{Placement, ItemId, Ritual, DialogueId, Effect, Condition};
+
+pub const DOOR_HALF_WIDTH: f32 = 0.25;
 
 slotmap::new_key_type! {
 	pub struct ObjectId;
@@ -16,17 +18,85 @@ pub struct Object {
 
 	pub info: ObjectInfo,
 
+	/// Extra ways the object can be interacted with, on top of those implied by `info`.
+	#[serde(default)]
+	pub verbs: Vec<ObjectVerb>,
+
 	// appearance
 	// interactions
 	// - how can it be interacted with
 	// 		- basic button interaction? does it need to be looked at or just in area
-	// 		- use an item on it?
 	// - can it give an item?
 	// - can it display text?
 	// - does it have a name?
 	// - does it take you somewhere else
-	// - does it trigger effects
-	// - can it be interacted with multiple ways
+}
+
+
+/// A way of interacting with an object. Each verb has its own binding.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Verb {
+	Use,
+	Examine,
+	Take,
+	UseItem,
+	Light,
+	Pray,
+}
+
+impl Verb {
+	pub const ALL: &'static [Verb] = &[Verb::Use, Verb::Examine, Verb::Take, Verb::UseItem, Verb::Light, Verb::Pray];
+
+	pub fn action(self) -> Action {
+		match self {
+			Verb::Use => Action::Interact,
+			Verb::Examine => Action::Examine,
+			Verb::Take => Action::Take,
+			Verb::UseItem => Action::UseItem,
+			Verb::Light => Action::Light,
+			Verb::Pray => Action::Pray,
+		}
+	}
+
+	pub fn label(self) -> &'static str {
+		match self {
+			Verb::Use => "Use",
+			Verb::Examine => "Examine",
+			Verb::Take => "Take",
+			Verb::UseItem => "Use Item",
+			Verb::Light => "Light",
+			Verb::Pray => "Pray at",
+		}
+	}
+}
+
+
+/// Authored verb on an object. If the object already has this verb, its effects are applied in addition to the built in behaviour.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ObjectVerb {
+	pub verb: Verb,
+
+	/// Shown in the hud instead of the default prompt for the verb.
+	#[serde(default)]
+	pub prompt: Option<String>,
+
+	/// Verb is only available while this passes.
+	#[serde(default)]
+	pub condition: Option<Condition>,
+
+	#[serde(default)]
+	pub effects: Vec<Effect>,
+}
+
+impl ObjectVerb {
+	pub fn new(verb: Verb) -> ObjectVerb {
+		ObjectVerb {
+			verb,
+			prompt: None,
+			condition: None,
+			effects: Vec::new(),
+		}
+	}
 }
 
 
@@ -43,13 +113,64 @@ pub enum ObjectInfo {
 	Light(LightObject),
 
 	Chest {
-		// content: Item,
+		#[serde(default)]
+		content: Vec<ItemId>,
+
+		#[serde(default)]
+		salt: u32,
 	},
 
-	Npc,
+	Door {
+		/// Item needed to unlock the door. If None, the door can just be opened.
+		#[serde(default)]
+		key_item: Option<ItemId>,
+
+		/// Blood paid to open the door the first time.
+		#[serde(default)]
+		blood_cost: u32,
+	},
+
+	/// Performs a ritual, or just restores blood if none is defined.
+	Altar {
+		#[serde(default)]
+		ritual: Ritual,
+
+		/// Name shown in the travel list. Altars with one can be attuned to and travelled between.
+		#[serde(default)]
+		waypoint: Option<String>,
+	},
+
+	Npc {
+		#[serde(default)]
+		dialogue: DialogueId,
+	},
 }
 
 impl Object {
+	/// Volume the player's view ray has to hit to target this object, relative to its placement and the floor.
+	// Deliberately generous - most objects don't have any visible geometry yet.
+	pub fn interaction_bounds(&self) -> InteractionBounds {
+		match &self.info {
+			ObjectInfo::Light(light) => InteractionBounds { radius: 0.1, bottom: light.height - 0.1, top: light.height + 0.1 },
+			ObjectInfo::Ladder{..} => InteractionBounds { radius: 0.2, bottom: 0.0, top: 1.0 },
+			ObjectInfo::Door{..} => InteractionBounds { radius: 0.3, bottom: 0.0, top: 0.8 },
+			ObjectInfo::Chest{..} | ObjectInfo::Altar{..} => InteractionBounds { radius: 0.2, bottom: 0.0, top: 0.6 },
+			ObjectInfo::Debug | ObjectInfo::Npc{..} => InteractionBounds { radius: 0.15, bottom: 0.0, top: 0.6 },
+		}
+	}
+
+	/// Segment that blocks movement while the object is closed, in the space of its room. Only doors block anything so far.
+	pub fn blocking_segment(&self) -> Option<(Vec2, Vec2)> {
+		match &self.info {
+			ObjectInfo::Door{..} => {
+				let half_width = self.placement.right() * DOOR_HALF_WIDTH;
+				Some((self.placement.position - half_width, self.placement.position + half_width))
+			}
+
+			_ => None
+		}
+	}
+
 	pub fn as_light(&self) -> Option<&LightObject> {
 		match &self.info {
 			ObjectInfo::Light(light) => Some(light),
@@ -66,10 +187,81 @@ impl Object {
 }
 
 
+/// Vertical cylinder around an object.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct InteractionBounds {
+	pub radius: f32,
+	pub bottom: f32,
+	pub top: f32,
+}
+
+
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct LightObject {
 	pub color: Color,
 	pub height: f32,
 	pub power: f32,
 	pub radius: f32,
-}
\ No newline at end of file
+
+	/// Candles and the like start unlit, and have to be lit by the player.
+	#[serde(default = "default_true")]
+	pub starts_lit: bool,
+
+	#[serde(default)]
+	pub animation: LightAnimation,
+}
+
+
+/// Changes a light's power or color over time. Evaluated every frame, see `LightModel`.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind")]
+pub enum LightAnimation {
+	#[default]
+	Steady,
+
+	/// Irregular wavering, like a candle flame. `amount` is the fraction of power that can be lost.
+	Flicker {
+		amount: f32,
+		speed: f32,
+	},
+
+	/// Smoothly dims and brightens again every `period` seconds. `min_power` is a fraction of the light's power.
+	Pulse {
+		period: f32,
+		min_power: f32,
+	},
+
+	/// Blends from one color to the next, taking `period` seconds for each. Replaces the light's own color.
+	ColorCycle {
+		colors: Vec<Color>,
+		period: f32,
+	},
+}
+
+impl LightAnimation {
+	pub const KIND_NAMES: &'static [&'static str] = &["Steady", "Flicker", "Pulse", "ColorCycle"];
+
+	pub fn kind_name(&self) -> &'static str {
+		match self {
+			LightAnimation::Steady => "Steady",
+			LightAnimation::Flicker{..} => "Flicker",
+			LightAnimation::Pulse{..} => "Pulse",
+			LightAnimation::ColorCycle{..} => "ColorCycle",
+		}
+	}
+
+	/// Creates an animation of the given kind with reasonable parameters. Used by the editor.
+	pub fn from_kind_name(name: &str) -> Option<LightAnimation> {
+		let animation = match name {
+			"Steady" => LightAnimation::Steady,
+			"Flicker" => LightAnimation::Flicker { amount: 0.3, speed: 8.0 },
+			"Pulse" => LightAnimation::Pulse { period: 2.0, min_power: 0.3 },
+			"ColorCycle" => LightAnimation::ColorCycle { colors: vec![Color::rgb(1.0, 0.2, 0.2), Color::rgb(0.2, 0.2, 1.0)], period: 2.0 },
+			_ => return None,
+		};
+
+		Some(animation)
+	}
+}
+
+fn default_true() -> bool { true }
\ No newline at end of file
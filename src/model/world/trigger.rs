@@ -0,0 +1,115 @@
+use crate::prelude::*;
+
+use model::{RoomId, Condition, Effect};
+
+slotmap::new_key_type! {
+	pub struct TriggerId;
+}
+
+
+/// A region of a room that does something when the player enters, leaves or stays inside it.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Trigger {
+	pub name: String,
+
+	pub room_id: RoomId,
+	pub shape: TriggerShape,
+
+	/// Trigger is only active while this passes.
+	#[serde(default)]
+	pub condition: Option<Condition>,
+
+	#[serde(default)]
+	pub on_enter: Vec<Effect>,
+
+	#[serde(default)]
+	pub on_exit: Vec<Effect>,
+
+	/// Applied every `stay_interval` seconds while the player remains inside.
+	#[serde(default)]
+	pub on_stay: Vec<Effect>,
+
+	#[serde(default = "default_stay_interval")]
+	pub stay_interval: f32,
+}
+
+fn default_stay_interval() -> f32 { 1.0 }
+
+impl Trigger {
+	pub fn new(name: impl Into<String>, room_id: RoomId, shape: TriggerShape) -> Trigger {
+		Trigger {
+			name: name.into(),
+			room_id,
+			shape,
+			condition: None,
+			on_enter: Vec::new(),
+			on_exit: Vec::new(),
+			on_stay: Vec::new(),
+			stay_interval: default_stay_interval(),
+		}
+	}
+
+	pub fn contains(&self, room_id: RoomId, position: Vec2) -> bool {
+		room_id == self.room_id && self.shape.contains(position)
+	}
+}
+
+
+/// Shape of a trigger, in the space of the room it's in.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind")]
+pub enum TriggerShape {
+	/// The whole room.
+	Room,
+
+	Circle {
+		center: Vec2,
+		radius: f32,
+	},
+
+	/// Points must be in CCW order, but needn't be convex.
+	Polygon {
+		points: Vec<Vec2>,
+	},
+}
+
+impl TriggerShape {
+	pub fn contains(&self, position: Vec2) -> bool {
+		match self {
+			TriggerShape::Room => true,
+
+			TriggerShape::Circle{center, radius} => (position - *center).length() <= *radius,
+
+			// Even-odd rule
+			TriggerShape::Polygon{points} => {
+				let mut inside = false;
+
+				for (index, &start) in points.iter().enumerate() {
+					let end = points[(index + 1) % points.len()];
+
+					if (start.y > position.y) != (end.y > position.y) {
+						let crossing_x = start.x + (position.y - start.y) / (end.y - start.y) * (end.x - start.x);
+						if position.x < crossing_x {
+							inside = !inside;
+						}
+					}
+				}
+
+				inside
+			}
+		}
+	}
+
+	pub fn translate(&mut self, delta: Vec2) {
+		match self {
+			TriggerShape::Room => {}
+			TriggerShape::Circle{center, ..} => *center += delta,
+			TriggerShape::Polygon{points} => {
+				for point in points.iter_mut() {
+					*point += delta;
+				}
+			}
+		}
+	}
+}
+
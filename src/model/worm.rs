@@ -0,0 +1,559 @@
+use crate::prelude::*;
+use model::*;
+
+use rand::RngCore;
+use std::collections::VecDeque;
+
+
+pub const WORM_SEGMENT_COUNT: usize = 10;
+pub const WORM_SEGMENT_SPACING: f32 = 0.12;
+pub const WORM_HEAD_RADIUS: f32 = 0.1;
+
+/// Height above the floor the worm floats at.
+pub const WORM_FLOAT_HEIGHT: f32 = 0.35;
+
+/// The worm loses track of the player if they're more than this many rooms away.
+pub const WORM_MAX_PATH_ROOMS: usize = 12;
+
+const WANDER_SPEED: f32 = 0.3;
+const STALK_SPEED: f32 = 0.6;
+const CHASE_SPEED: f32 = 1.2;
+const FLEE_SPEED: f32 = 1.6;
+
+/// Path distance at which the worm starts stalking the player.
+const STALK_DISTANCE: f32 = 6.0;
+
+/// Stalking worms don't get any closer than this.
+const STALK_KEEP_DISTANCE: f32 = 2.5;
+
+/// How long the worm stalks before losing patience and chasing.
+const STALK_PATIENCE: f32 = 8.0;
+
+/// Path distance at which the worm starts chasing, and at which it gives up.
+const CHASE_DISTANCE: f32 = 1.5;
+const LOSE_DISTANCE: f32 = 9.0;
+
+/// How aware of the player the worm must be before it starts stalking them.
+const STALK_AWARENESS: f32 = 0.3;
+
+/// Warded players scare off worms within this distance.
+const FLEE_DISTANCE: f32 = 6.0;
+
+const BITE_DISTANCE: f32 = 0.25;
+const BITE_BLOOD: i32 = 10;
+
+/// How long the worm backs off for after biting.
+const BITE_RECOIL_TIME: f32 = 3.0;
+
+const SIGHT_RANGE: f32 = 6.0;
+const SIGHT_HALF_ANGLE_DEGREES: f32 = 70.0;
+const HEARING_RANGE: f32 = 5.0;
+
+
+#[derive(Debug, Clone)]
+pub enum WormCmd {
+	/// Placement is in source room space.
+	Spawn(Placement),
+	DespawnAll,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WormEvent {
+	BehaviourChanged(WormBehaviour),
+	Bit,
+}
+
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WormBehaviour {
+	/// Drifts aimlessly from room to room.
+	Wander,
+
+	/// Follows the player at a distance.
+	Stalk,
+
+	Chase,
+
+	/// Keeps away from warded players, and backs off after biting.
+	Flee,
+}
+
+
+/// Part of the worm's body. Each segment may be in a different room to its neighbours.
+#[derive(Debug, Copy, Clone)]
+pub struct WormSegment {
+	/// Processed room space.
+	pub location: Location,
+
+	/// Height above the floor of the segment's room.
+	pub height: f32,
+	pub radius: f32,
+}
+
+
+#[derive(Debug, Copy, Clone)]
+struct TrailPoint {
+	location: Location,
+
+	/// Distance to the next newest point. Stored rather than calculated since neighbouring points can be in different rooms.
+	step: f32,
+}
+
+
+#[derive(Debug)]
+pub struct Worm {
+	pub behaviour: WormBehaviour,
+
+	/// Head first.
+	pub segments: Vec<WormSegment>,
+
+	/// Direction the head is moving in. Processed room space.
+	pub facing: Vec2,
+
+	pub perception: Perception,
+
+	/// Source room space, used to reset the worm if the processed world changes under it.
+	spawn: Placement,
+
+	/// Where the head has been, newest first. Segments are placed along this.
+	trail: VecDeque<TrailPoint>,
+
+	time: f32,
+	stalk_time: f32,
+	recoil_time: f32,
+
+	/// Connection the worm is wandering towards, and the room it's in.
+	wander_exit: Option<(RoomId, WallId)>,
+}
+
+impl Worm {
+	pub fn new(world: &ProcessedWorld, spawn: Placement) -> Worm {
+		let placement = world.to_processed_placement(spawn);
+		let head = placement.location();
+
+		let mut worm = Worm {
+			behaviour: WormBehaviour::Wander,
+			segments: Vec::with_capacity(WORM_SEGMENT_COUNT),
+
+			facing: placement.forward(),
+			perception: Perception::new(SIGHT_RANGE, SIGHT_HALF_ANGLE_DEGREES.to_radians(), HEARING_RANGE),
+
+			spawn,
+			trail: VecDeque::from([TrailPoint { location: head, step: 0.0 }]),
+
+			time: 0.0,
+			stalk_time: 0.0,
+			recoil_time: 0.0,
+
+			wander_exit: None,
+		};
+
+		worm.update_segments();
+		worm
+	}
+
+	pub fn head(&self) -> Location {
+		self.trail[0].location
+	}
+
+	/// Head location, facing the way the worm is moving. Processed room space.
+	pub fn placement(&self) -> Placement {
+		let head = self.head();
+		let right = self.facing.perp();
+
+		Placement {
+			room_id: head.room_id,
+			position: head.position,
+			yaw: right.y.atan2(right.x),
+		}
+	}
+
+	fn update(&mut self, player: &Player, world: &ProcessedWorld, fog: &FogParameters, noises: &[NoiseEvent],
+		rng: &mut impl RngCore, dt: f32, bus: &MessageBus)
+	{
+		// Rooms may have been rebuilt since last update
+		if self.trail.iter().any(|point| !point.location.room_id.is_valid(world.geometry())) {
+			*self = Worm::new(world, self.spawn);
+		}
+
+		self.time += dt;
+		self.recoil_time = (self.recoil_time - dt).max(0.0);
+
+		let head = self.head();
+		self.perception.update(head, self.facing, WORM_FLOAT_HEIGHT, player, world, fog, noises, dt);
+
+		let path = world.find_path(head, player.placement.location(), WORM_MAX_PATH_ROOMS);
+
+		let behaviour = self.next_behaviour(path.as_ref().map(|path| path.distance), player.is_warded());
+		if behaviour != self.behaviour {
+			log::debug!("Worm {:?} -> {behaviour:?}", self.behaviour);
+			bus.emit(WormEvent::BehaviourChanged(behaviour));
+
+			self.behaviour = behaviour;
+			self.stalk_time = 0.0;
+			self.wander_exit = None;
+		}
+
+		// Where the head should move towards next in its own room - either the player, or the next aperture towards them.
+		let toward_player = path.as_ref().map(|path| match path.walls.first() {
+			Some(&wall_id) => exit_point(world, wall_id),
+			None => player.placement.position,
+		});
+
+		let (target, speed) = match (self.behaviour, toward_player) {
+			(WormBehaviour::Stalk, Some(target)) => {
+				self.stalk_time += dt;
+
+				let distance = path.as_ref().map_or(0.0, |path| path.distance);
+				(target, if distance > STALK_KEEP_DISTANCE { STALK_SPEED } else { 0.0 })
+			}
+
+			(WormBehaviour::Chase, Some(target)) => (target, CHASE_SPEED),
+
+			(WormBehaviour::Flee, Some(threat)) => {
+				// Leave through whichever aperture is furthest from the player, as long as it doesn't lead towards them
+				let toward_player_wall = path.as_ref().and_then(|path| path.walls.first().copied());
+				let exit = world.connections_for_room(head.room_id)
+					.filter(|connection| Some(connection.source_wall) != toward_player_wall)
+					.map(|connection| exit_point(world, connection.source_wall))
+					.max_by(|a, b| (*a - threat).length().total_cmp(&(*b - threat).length()));
+
+				// Otherwise just back away
+				let target = exit.unwrap_or(head.position + (head.position - threat).normalize());
+				(target, FLEE_SPEED)
+			}
+
+			_ => match self.wander_target(world, rng) {
+				Some(target) => (target, WANDER_SPEED),
+				None => (head.position, 0.0),
+			},
+		};
+
+		let moved = self.move_head(world, target, speed * dt);
+		if moved > 0.0 {
+			self.trail[0].step = moved;
+			self.trail.push_front(TrailPoint { location: self.head(), step: 0.0 });
+		}
+
+		// Forget the trail once it's longer than the body
+		let body_length = WORM_SEGMENT_SPACING * (WORM_SEGMENT_COUNT - 1) as f32;
+		let mut trail_length = 0.0;
+		let mut trail_points = 0;
+		for point in self.trail.iter() {
+			trail_points += 1;
+			trail_length += point.step;
+			if trail_length > body_length {
+				break
+			}
+		}
+
+		self.trail.truncate(trail_points + 1);
+
+		// Bite
+		let head = self.head();
+		if self.behaviour == WormBehaviour::Chase
+			&& !player.free_cam
+			&& head.room_id == player.placement.room_id
+			&& (head.position - player.placement.position).length() < BITE_DISTANCE
+		{
+			bus.emit(ResourceCmd::Add(Resource::Blood, -BITE_BLOOD));
+			bus.emit(HudCmd::ShowText("Something bites you".into()));
+			bus.emit(WormEvent::Bit);
+
+			self.recoil_time = BITE_RECOIL_TIME;
+		}
+
+		self.update_segments();
+	}
+
+	fn next_behaviour(&self, path_distance: Option<f32>, player_warded: bool) -> WormBehaviour {
+		let Some(distance) = path_distance else {
+			return WormBehaviour::Wander
+		};
+
+		if self.recoil_time > 0.0 || player_warded && distance < FLEE_DISTANCE {
+			return WormBehaviour::Flee
+		}
+
+		// Anything this close is noticed regardless, but otherwise the worm has to have seen or heard the player to go after them.
+		let awareness = self.perception.awareness;
+
+		match self.behaviour {
+			WormBehaviour::Chase if distance < LOSE_DISTANCE && awareness > 0.0 => WormBehaviour::Chase,
+			WormBehaviour::Stalk if self.stalk_time > STALK_PATIENCE => WormBehaviour::Chase,
+			_ if distance < CHASE_DISTANCE => WormBehaviour::Chase,
+			WormBehaviour::Stalk if distance < STALK_DISTANCE && awareness > 0.0 => WormBehaviour::Stalk,
+			_ if distance < STALK_DISTANCE && awareness >= STALK_AWARENESS => WormBehaviour::Stalk,
+			_ => WormBehaviour::Wander,
+		}
+	}
+
+	fn wander_target(&mut self, world: &ProcessedWorld, rng: &mut impl RngCore) -> Option<Vec2> {
+		let head = self.head();
+
+		// Pick a new exit whenever we end up in a new room
+		if self.wander_exit.is_none_or(|(room_id, _)| room_id != head.room_id) {
+			let connections: SmallVec<[WallId; 8]> = world.connections_for_room(head.room_id)
+				.map(|connection| connection.source_wall)
+				.collect();
+
+			self.wander_exit = match connections.is_empty() {
+				true => None,
+				false => Some((head.room_id, connections[rng.next_u32() as usize % connections.len()])),
+			};
+		}
+
+		let (_, wall_id) = self.wander_exit?;
+		Some(exit_point(world, wall_id))
+	}
+
+	/// Moves the head towards `target`, sliding along walls and passing through apertures.
+	/// Returns the distance moved.
+	fn move_head(&mut self, world: &ProcessedWorld, target: Vec2, max_distance: f32) -> f32 {
+		let head = &mut self.trail[0].location;
+		let geometry = world.geometry();
+
+		let to_target = target - head.position;
+		let target_distance = to_target.length();
+		if target_distance < 0.001 || max_distance <= 0.0 {
+			return 0.0
+		}
+
+		self.facing = to_target / target_distance;
+
+		let mut desired_position = head.position + to_target * (max_distance.min(target_distance) / target_distance);
+
+		for wall_id in geometry.room_walls(head.room_id) {
+			let (wall_start, wall_end) = geometry.wall_vertices(wall_id);
+
+			let wall_direction = (wall_end - wall_start).normalize();
+			let wall_length = (wall_end - wall_start).length();
+
+			let desired_delta_wall_space = desired_position - wall_start;
+			let wall_penetration = wall_direction.wedge(desired_delta_wall_space);
+
+			// ASSUME: rooms are convex, and walls are specified in CCW order.
+			if wall_penetration + WORM_HEAD_RADIUS < 0.0 {
+				continue
+			}
+
+			let distance_along_wall = wall_direction.dot(desired_delta_wall_space);
+			if distance_along_wall < 0.0 || distance_along_wall >= wall_length {
+				continue
+			}
+
+			// The worm floats, so only cares that the aperture is tall enough
+			if let Some(connection_info) = world.connection_info(wall_id)
+				&& connection_info.aperture_height > WORM_FLOAT_HEIGHT + WORM_HEAD_RADIUS
+			{
+				let aperture_center = wall_length/2.0 + connection_info.aperture_offset;
+				if (aperture_center - distance_along_wall).abs() < connection_info.aperture_extent - WORM_HEAD_RADIUS {
+					if wall_penetration < 0.0 {
+						continue
+					}
+
+					let travel_distance = (desired_position - head.position).length();
+
+					head.room_id = connection_info.target_room;
+					head.position = connection_info.source_to_target * desired_position;
+
+					let facing_point = connection_info.source_to_target * (desired_position + self.facing);
+					self.facing = (facing_point - head.position).normalize();
+
+					return travel_distance
+				}
+			}
+
+			// Slide along wall
+			desired_position -= wall_direction.perp() * (wall_penetration + WORM_HEAD_RADIUS);
+		}
+
+		let travel_distance = (desired_position - head.position).length();
+		head.position = desired_position;
+		travel_distance
+	}
+
+	/// Spaces segments out along the trail.
+	fn update_segments(&mut self) {
+		self.segments.clear();
+
+		let mut point_index = 0;
+		let mut distance_to_point = 0.0;
+
+		for segment_index in 0..WORM_SEGMENT_COUNT {
+			let segment_distance = segment_index as f32 * WORM_SEGMENT_SPACING;
+
+			while point_index + 1 < self.trail.len() && distance_to_point + self.trail[point_index + 1].step < segment_distance {
+				point_index += 1;
+				distance_to_point += self.trail[point_index].step;
+			}
+
+			let newer = self.trail[point_index];
+			let location = match self.trail.get(point_index + 1) {
+				// Can only interpolate within a room
+				Some(older) if older.location.room_id == newer.location.room_id && older.step > 0.0 => {
+					let fraction = ((segment_distance - distance_to_point) / older.step).clamp(0.0, 1.0);
+					Location {
+						room_id: newer.location.room_id,
+						position: newer.location.position + (older.location.position - newer.location.position) * fraction,
+					}
+				}
+
+				_ => newer.location,
+			};
+
+			let body_fraction = segment_index as f32 / WORM_SEGMENT_COUNT as f32;
+			let bob = (self.time * 2.0 - segment_index as f32 * 0.6).sin() * 0.04;
+
+			self.segments.push(WormSegment {
+				location,
+				height: WORM_FLOAT_HEIGHT + bob,
+				radius: WORM_HEAD_RADIUS * (1.0 - body_fraction * 0.6),
+			});
+		}
+	}
+}
+
+
+/// A point just past the aperture of `wall_id`, so that moving towards it takes you through to the other side.
+fn exit_point(world: &ProcessedWorld, wall_id: WallId) -> Vec2 {
+	let Some(connection) = world.connection_info(wall_id) else {
+		return world.geometry().wall_center(wall_id)
+	};
+
+	let aperture_center = (connection.aperture_start + connection.aperture_end) / 2.0;
+	let outward = (connection.aperture_end - connection.aperture_start).normalize().perp();
+	aperture_center + outward * WORM_HEAD_RADIUS * 2.0
+}
+
+
+
+/// Entities that follow the player through the labyrinth.
+#[derive(Debug)]
+pub struct WormModel {
+	pub worms: Vec<Worm>,
+
+	worm_cmd_sub: Subscription<WormCmd>,
+	noise_sub: Subscription<NoiseEvent>,
+}
+
+impl WormModel {
+	pub fn new(bus: &MessageBus) -> Self {
+		WormModel {
+			worms: Vec::new(),
+			worm_cmd_sub: bus.subscribe(),
+			noise_sub: bus.subscribe(),
+		}
+	}
+
+	pub fn update(&mut self, player: &Player, world: &ProcessedWorld, fog: &FogParameters, rng: &mut impl RngCore, dt: f32, bus: &MessageBus) {
+		for cmd in bus.poll_consume(&self.worm_cmd_sub) {
+			match cmd {
+				WormCmd::Spawn(placement) => self.worms.push(Worm::new(world, placement)),
+				WormCmd::DespawnAll => self.worms.clear(),
+			}
+		}
+
+		let noises: SmallVec<[NoiseEvent; 4]> = bus.poll(&self.noise_sub).collect();
+
+		for worm in self.worms.iter_mut() {
+			worm.update(player, world, fog, &noises, rng, dt, bus);
+		}
+	}
+}
+
+
+
+pub fn handle_worm_commands(ctx: &mut Context, model: &Model) -> anyhow::Result<()> {
+	if ctx.console.command("worm").is_some() {
+		// Spawn behind the player if there's room, so it has somewhere to come from
+		let player = model.player.placement;
+		let behind = Placement { position: player.position - player.forward(), .. player };
+
+		let placement = match model.processed_world.geometry().room_contains_point(player.room_id, behind.position) {
+			true => behind,
+			false => player,
+		};
+
+		ctx.bus.emit(WormCmd::Spawn(model.processed_world.to_source_placement(placement)));
+	}
+
+	if ctx.console.command("noworms").is_some() {
+		ctx.bus.emit(WormCmd::DespawnAll);
+	}
+
+	Ok(())
+}
+
+
+
+#[cfg(test)]
+fn two_connected_rooms() -> (World, RoomId, RoomId) {
+	let mut world = World::new();
+	world.geometry = WorldGeometry::new_square(1.0);
+
+	let near_room = world.geometry.first_room();
+	let far_room = world.geometry.insert_room_from_positions(&[
+		Vec2::new(4.5, -0.5),
+		Vec2::new(4.5,  0.5),
+		Vec2::new(5.5,  0.5),
+		Vec2::new(5.5, -0.5),
+	]);
+
+	world.player_spawn = Placement { room_id: near_room, position: Vec2::zero(), yaw: 0.0 };
+
+	let near_wall = world.geometry.room_walls(near_room).next().unwrap();
+	let far_wall = world.geometry.room_walls(far_room).next().unwrap();
+	world.geometry.connect_wall(near_wall, far_wall).unwrap();
+
+	(world, near_room, far_room)
+}
+
+#[test]
+fn worm_follows_player_through_aperture() {
+	let (world, _, far_room) = two_connected_rooms();
+	let (source_model, bus, mut model) = test_model(&world);
+	let event_sub = bus.subscribe::<WormEvent>();
+
+	bus.emit(WormCmd::Spawn(Placement { room_id: far_room, position: Vec2::new(5.0, 0.0), yaw: 0.0 }));
+
+	let mut segments_split_across_rooms = false;
+	let mut events = Vec::new();
+
+	for _ in 0..600 {
+		model.step(&source_model, &TickInput::default(), &bus);
+		events.extend(bus.poll_consume(&event_sub));
+
+		if let Some(worm) = model.worms.worms.first() {
+			let head_room = worm.head().room_id;
+			segments_split_across_rooms |= worm.segments.iter().any(|segment| segment.location.room_id != head_room);
+		}
+	}
+
+	assert!(events.contains(&WormEvent::BehaviourChanged(WormBehaviour::Chase)), "{events:?}");
+	assert!(events.contains(&WormEvent::Bit), "{events:?}");
+	assert!(segments_split_across_rooms);
+	assert!(model.player.blood < 100);
+}
+
+#[test]
+fn worm_flees_warded_player() {
+	let (world, near_room, _) = two_connected_rooms();
+	let (source_model, bus, mut model) = test_model(&world);
+	model.player.ward_time_remaining = 100.0;
+
+	bus.emit(WormCmd::Spawn(Placement { room_id: near_room, position: Vec2::new(0.2, 0.2), yaw: 0.0 }));
+
+	for _ in 0..120 {
+		model.step(&source_model, &TickInput::default(), &bus);
+	}
+
+	let worm = &model.worms.worms[0];
+	assert_eq!(worm.behaviour, WormBehaviour::Flee);
+	assert_eq!(model.player.blood, 100);
+
+	let head = worm.head();
+	let fled = head.room_id != model.player.placement.room_id
+		|| (head.position - model.player.placement.position).length() > 0.4;
+	assert!(fled);
+}
@@ -2,11 +2,21 @@ use crate::prelude::*;
 
 use super::{UiPainter, UiPainterWithShared};
 
+use std::rc::Rc;
+use std::cell::Cell;
+
 
 pub struct UiBuilder<'mp, 'ctx, L: UiLayout> {
 	input: &'ctx input::System,
+	actions: &'ctx actions::ActionState,
 	pub input_scale_factor: f32,
 
+	/// Index of the button selected via gamepad/keyboard navigation, if any.
+	pub focused_item: Option<usize>,
+
+	// Shared with builders created by with_layout so items are numbered in the order they're built.
+	item_counter: Rc<Cell<usize>>,
+
 	pub painter: UiPainterWithShared<'mp, 'ctx>,
 	pub font_size: u32,
 
@@ -18,8 +28,12 @@ impl<'mp, 'ctx, L: UiLayout> UiBuilder<'mp, 'ctx, L> {
 		UiBuilder {
 			painter: painter.with_shared(ctx.ui_shared),
 			input: &ctx.input,
+			actions: ctx.actions,
 			input_scale_factor: 1.0,
 
+			focused_item: None,
+			item_counter: Rc::new(Cell::new(0)),
+
 			font_size: 16,
 
 			layout,
@@ -29,8 +43,12 @@ impl<'mp, 'ctx, L: UiLayout> UiBuilder<'mp, 'ctx, L> {
 	pub fn with_layout<'s, L2: UiLayout>(&'s mut self, new_layout: L2) -> UiBuilder<'s, 'ctx, L2> {
 		UiBuilder {
 			input: self.input,
+			actions: self.actions,
 			input_scale_factor: self.input_scale_factor,
 
+			focused_item: self.focused_item,
+			item_counter: self.item_counter.clone(),
+
 			painter: self.painter.painter.with_shared(self.painter.shared),
 			font_size: self.font_size,
 
@@ -38,6 +56,22 @@ impl<'mp, 'ctx, L: UiLayout> UiBuilder<'mp, 'ctx, L> {
 		}
 	}
 
+	/// Number of focusable items built so far.
+	pub fn item_count(&self) -> usize {
+		self.item_counter.get()
+	}
+
+	pub fn label(&mut self, label: &str, color: impl Into<Color>) {
+		let padding = Vec2::new(8.0, 2.0);
+
+		let text_rect = self.painter.text_rect(self.font_size, label);
+
+		let label_size = Vec2::new(text_rect.width(), self.font_size as f32) + padding*2.0;
+		let label_rect = self.layout.allocate(label_size);
+
+		self.painter.text(label_rect.min + padding, self.font_size, label, color);
+	}
+
 	pub fn button(&mut self, label: &str) -> bool {
 		let padding = Vec2::new(8.0, 2.0);
 
@@ -48,12 +82,19 @@ impl<'mp, 'ctx, L: UiLayout> UiBuilder<'mp, 'ctx, L> {
 
 		let text_origin = button_rect.min + padding;
 
-		let is_hovered = self.input.mouse_position_pixels()
+		let item_index = self.item_counter.get();
+		self.item_counter.set(item_index + 1);
+
+		let is_focused = self.focused_item == Some(item_index);
+
+		let is_mouse_hovered = self.input.mouse_position_pixels()
 			.map(|pos| button_rect.contains_point(pos * self.input_scale_factor))
 			.unwrap_or(false);
 
-		let is_pressed = is_hovered
-			&& self.input.button_down(input::MouseButton::Left);
+		let is_hovered = is_mouse_hovered || is_focused;
+
+		let is_pressed = is_mouse_hovered && self.input.button_down(input::MouseButton::Left)
+			|| is_focused && self.actions.is_down(Action::MenuConfirm);
 
 		// TODO(pat.m): style
 		let bg_color = Color::grey_a(0.05, 0.3);
@@ -67,6 +108,35 @@ impl<'mp, 'ctx, L: UiLayout> UiBuilder<'mp, 'ctx, L> {
 		self.painter.rect(button_rect, bg_color);
 		self.painter.text(text_origin, self.font_size, label, text_color);
 
-		is_hovered && self.input.button_just_up(input::MouseButton::Left)
+		is_mouse_hovered && self.input.button_just_up(input::MouseButton::Left)
+			|| is_focused && self.actions.just_down(Action::MenuConfirm)
+	}
+}
+
+
+/// Keeps track of which menu item is selected when navigating without a mouse.
+#[derive(Debug, Default)]
+pub struct MenuFocus {
+	pub focused_item: Option<usize>,
+	pub item_count: usize,
+}
+
+impl MenuFocus {
+	pub fn update(&mut self, actions: &actions::ActionState) {
+		if self.item_count == 0 {
+			self.focused_item = None;
+			return;
+		}
+
+		let up = actions.just_down(Action::MenuUp);
+		let down = actions.just_down(Action::MenuDown);
+
+		self.focused_item = match (self.focused_item, up, down) {
+			(None, true, _) => Some(self.item_count - 1),
+			(None, _, true) => Some(0),
+			(Some(index), true, false) => Some((index + self.item_count - 1) % self.item_count),
+			(Some(index), false, true) => Some((index + 1) % self.item_count),
+			(focused, ..) => focused.map(|index| index.min(self.item_count - 1)),
+		};
 	}
 }
\ No newline at end of file
@@ -75,6 +75,21 @@ impl UiPainter {
 	pub fn rect(&mut self, geom: Aabb2, color: impl Into<Color>) {
 		self.shape_layer.draw_quad(geom, Aabb2::zero(), color);
 	}
+
+	pub fn convex_poly(&mut self, vertices: &[Vec2], color: impl Into<Color>) {
+		self.shape_layer.draw_convex_poly(vertices, color);
+	}
+
+	pub fn line(&mut self, start: Vec2, end: Vec2, width: f32, color: impl Into<Color>) {
+		let length = (end - start).length();
+		if length < 0.001 {
+			return
+		}
+
+		let offset = (end - start).perp() * width / (2.0 * length);
+
+		self.shape_layer.draw_convex_poly(&[start - offset, end - offset, end + offset, start + offset], color);
+	}
 }
 
 
@@ -158,4 +173,19 @@ impl UiPainterLayer {
 		self.vertices.extend_from_slice(&vertices);
 		self.indices.extend(indices);
 	}
+
+	pub fn draw_convex_poly(&mut self, vertices: &[Vec2], color: impl Into<Color>) {
+		if vertices.len() < 3 {
+			return
+		}
+
+		let start_index = self.vertices.len() as u32;
+		let indices = (1..vertices.len() as u32 - 1)
+			.flat_map(|i| [start_index, start_index + i, start_index + i + 1]);
+
+		let color = color.into();
+
+		self.vertices.extend(vertices.iter().map(|&pos| gfx::StandardVertex::new(pos.extend(0.0), Vec2::zero(), color)));
+		self.indices.extend(indices);
+	}
 }
\ No newline at end of file
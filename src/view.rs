@@ -2,4 +2,7 @@ pub mod world_view;
 pub use world_view::*;
 
 pub mod hud_view;
-pub use hud_view::*;
\ No newline at end of file
+pub use hud_view::*;
+
+pub mod automap_view;
+pub use automap_view::*;
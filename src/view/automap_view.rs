@@ -0,0 +1,90 @@
+use crate::prelude::*;
+use model::*;
+
+
+/// Screen units per world unit.
+const AUTOMAP_SCALE: f32 = 24.0;
+
+const ROOM_COLOR: Color = Color::rgb(0.2, 0.2, 0.22);
+const WALL_COLOR: Color = Color::rgb(0.7, 0.7, 0.65);
+const MARKER_COLOR: Color = Color::rgb(0.95, 0.95, 0.9);
+const PLAYER_COLOR: Color = Color::rgb(1.0, 0.3, 0.1);
+
+
+/// Draws explored rooms around the player, unfolded from the room they're in and turned so that they're always facing up.
+pub fn draw_automap(painter: &mut ui::UiPainter, usable_area: Aabb2, model: &Model) {
+	let world = &model.processed_world;
+	let geometry = world.geometry();
+	let player = model.player.placement;
+
+	let extent = usable_area.width().min(usable_area.height()) * 0.4;
+	let map_bounds = Aabb2::from_center_extents(usable_area.center(), extent);
+	let map_center = map_bounds.center();
+
+	painter.rect(map_bounds.grow(4.0), Color::black().with_alpha(0.7));
+
+	let (right, forward) = (player.right(), player.forward());
+	let to_screen = |room_to_map: Mat2x3, position: Vec2| {
+		let offset = room_to_map * position - player.position;
+		map_center + Vec2::new(offset.dot(right), offset.dot(forward)) * AUTOMAP_SCALE
+	};
+
+	let rooms = unfold_explored_rooms(world, &model.progress.state, player.room_id, AUTOMAP_MAX_DEPTH);
+
+	// Furthest first, so closer rooms are drawn over the top
+	for room in rooms.iter().rev() {
+		let vertices: SmallVec<[Vec2; 8]> = geometry.room_vertices(room.room_id)
+			.map(|vertex_id| to_screen(room.room_to_map, geometry.vertices[vertex_id].position))
+			.collect();
+
+		// TODO(pat.m): clip rooms to the edge of the map instead
+		if !vertices.iter().all(|&vertex| map_bounds.contains_point(vertex)) {
+			continue
+		}
+
+		let alpha = room_alpha(room);
+		painter.convex_poly(&vertices, ROOM_COLOR.with_alpha(0.8 * alpha));
+
+		for wall_id in geometry.room_walls(room.room_id) {
+			let (wall_start, wall_end) = geometry.wall_vertices(wall_id);
+
+			// Leave gaps for apertures
+			let segments: SmallVec<[(Vec2, Vec2); 2]> = match world.connection_info(wall_id) {
+				Some(connection) => [(wall_start, connection.aperture_start), (connection.aperture_end, wall_end)].into_iter().collect(),
+				None => [(wall_start, wall_end)].into_iter().collect(),
+			};
+
+			for (start, end) in segments {
+				painter.line(to_screen(room.room_to_map, start), to_screen(room.room_to_map, end), 1.5, WALL_COLOR.with_alpha(alpha));
+			}
+		}
+	}
+
+	for marker in model.progress.state.markers.iter() {
+		let location = world.to_processed_location(marker.location);
+
+		for room in rooms.iter().filter(|room| room.room_id == location.room_id) {
+			let position = to_screen(room.room_to_map, location.position);
+			if map_bounds.contains_point(position) {
+				painter.rect(Aabb2::from_center_extents(position, 1.5), MARKER_COLOR.with_alpha(room_alpha(room)));
+			}
+		}
+	}
+
+	painter.convex_poly(&[
+		map_center + Vec2::new(0.0, 6.0),
+		map_center + Vec2::new(-4.0, -4.0),
+		map_center + Vec2::new(4.0, -4.0),
+	], PLAYER_COLOR);
+}
+
+
+/// Distant rooms, and rooms drawn on top of closer ones, fade out.
+fn room_alpha(room: &AutomapRoom) -> f32 {
+	let distance_fade = 1.0 - room.depth as f32 / (AUTOMAP_MAX_DEPTH + 1) as f32;
+
+	match room.overlapped {
+		true => distance_fade * 0.3,
+		false => distance_fade,
+	}
+}
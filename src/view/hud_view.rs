@@ -17,23 +17,33 @@ impl HudView {
 		})
 	}
 
-	pub fn draw(&mut self, gfx: &mut gfx::System, ui_shared: &mut ui::UiShared, model: &Model) {
+	pub fn draw(&mut self, gfx: &mut gfx::System, ui_shared: &mut ui::UiShared, input_bindings: &actions::InputBindings, source_model: &SourceModel, model: &Model) {
 		let screen_size = gfx.backbuffer_size().to_vec2();
 		let screen_bounds = Aabb2::from_min_size(Vec2::zero(), screen_size/2.0);
 
 		let usable_area = screen_bounds.shrink(16.0);
 
-		if model.hud.in_dialog {
-			self.draw_dialog(usable_area, ui_shared, model);
+		if let Some(dialogue) = &model.hud.dialogue {
+			self.draw_dialog(usable_area, ui_shared, &source_model.dialogues, dialogue);
 		} else {
-			self.draw_playing(usable_area, ui_shared, model);
+			self.draw_playing(usable_area, ui_shared, input_bindings, model);
+			self.draw_inventory(usable_area, ui_shared, &source_model.items, &model.inventory.inventory);
+
+			if model.hud.map_open {
+				super::draw_automap(&mut self.painter, usable_area, model);
+				self.draw_objectives(usable_area, ui_shared, &source_model.world.objectives, &model.objectives.statuses);
+			}
+
+			if let Some(choice) = &model.hud.choice {
+				self.draw_choice(usable_area, ui_shared, choice);
+			}
 		}
 
 		self.painter.submit(gfx, ui_shared, screen_bounds);
 	}
 
-	fn draw_playing(&mut self, usable_area: Aabb2, ui_shared: &mut ui::UiShared, model: &Model) {
-		let Player { blood, salt, .. } = model.player;
+	fn draw_playing(&mut self, usable_area: Aabb2, ui_shared: &mut ui::UiShared, input_bindings: &actions::InputBindings, model: &Model) {
+		let HudModel { blood_display, salt_display, .. } = &model.hud;
 
 		let mut painter = self.painter.with_shared(ui_shared);
 
@@ -41,8 +51,12 @@ impl HudView {
 
 		painter.rect(Aabb2::from_min_size(usable_area.min, Vec2::new(text_width, 32.0)).grow(4.0), Color::black().with_alpha(0.5));
 
-		painter.text(usable_area.min + Vec2::from_y(0.0), 16, format!("Blood: {blood}"), Color::white());
-		painter.text(usable_area.min + Vec2::from_y(16.0), 16, format!("Salt: {salt}"), Color::white());
+		painter.text(usable_area.min + Vec2::from_y(0.0), 16, format!("Blood: {}", blood_display.displayed()), resource_color(blood_display));
+		painter.text(usable_area.min + Vec2::from_y(16.0), 16, format!("Salt: {}", salt_display.displayed()), resource_color(salt_display));
+
+		if model.player.is_warded() {
+			painter.text(usable_area.min + Vec2::from_y(32.0), 16, "Warded", Color::rgb(0.7, 0.8, 1.0));
+		}
 
 		if let Some(hud_text) = &model.hud.hud_text {
 			let fade_in = (hud_text.elapsed_visible_time/HUD_TEXT_FADE_IN_TIME).ease_quad_inout();
@@ -61,47 +75,203 @@ impl HudView {
 		}
 
 
-		if let Some(object) = model.interactions.hovered_object.and_then(|id| model.processed_world.objects.get(id)) {
-			let interact_message = match &object.info {
-				ObjectInfo::Ladder { target_world, .. } => format!("To {target_world}"),
-				_ => format!("Frob '{}'", object.name),
-			};
+		if model.interactions.can_interact() {
+			// TODO(pat.m): interaction icon
+			painter.rect(Aabb2::from_center_extents(usable_area.center(), 8.0), Color::grey(0.5).with_alpha(0.1));
 
-			let text_rect = painter.text_rect(16, &interact_message);
-			let text_size = text_rect.size();
+			let mut text_pos = usable_area.center() + Vec2::from_x(14.0);
 
-			let text_pos = usable_area.center() - text_size.to_0y()/2.0 + Vec2::from_x(14.0);
-			// let text_pos = usable_area.center() - text_size * Vec2::new(0.5, 1.0) - Vec2::from_y(12.0);
+			for AvailableVerb{verb, prompt} in model.interactions.verbs.iter() {
+				let binding = input_bindings.bindings_for(verb.action()).first()
+					.map_or(Cow::from("-"), |binding| binding.name());
 
-			// TODO(pat.m): interaction icon
-			painter.rect(Aabb2::from_center_extents(usable_area.center(), 8.0), Color::grey(0.5).with_alpha(0.1));
+				let message = format!("[{binding}] {prompt}");
 
-			painter.rect(text_rect.translate(text_pos).grow(2.0), Color::black().with_alpha(0.5));
-			painter.text(text_pos, 16, interact_message, Color::grey(0.5));
+				let text_rect = painter.text_rect(16, &message);
+				let line_pos = text_pos - text_rect.size().to_0y()/2.0;
+
+				painter.rect(text_rect.translate(line_pos).grow(2.0), Color::black().with_alpha(0.5));
+				painter.text(line_pos, 16, message, Color::grey(0.5));
+
+				// Stack downwards
+				text_pos.y -= 20.0;
+			}
 		}
 	}
 
-	fn draw_dialog(&mut self, usable_area: Aabb2, ui_shared: &mut ui::UiShared, _model: &Model) {
+	fn draw_inventory(&mut self, usable_area: Aabb2, ui_shared: &mut ui::UiShared, item_defs: &ItemDefs, inventory: &Inventory) {
 		let mut painter = self.painter.with_shared(ui_shared);
 
-		let text = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. Quisque vulputate nunc orci.
-Proin varius a neque vel ultrices. Nulla facilisi. Praesent eget dictum ante.
-In blandit diam quis nibh convallis ultricies. Donec facilisis enim a mauris scelerisque,
-vitae vulputate urna mattis.";
+		let slot_size = 24.0;
+		let slot_spacing = 4.0;
+
+		// Lay out slots right to left from the bottom right corner
+		let mut slot_max = Vec2::new(usable_area.max.x, usable_area.min.y + slot_size);
+
+		for (index, stack) in inventory.stacks.iter().enumerate().rev() {
+			let slot_rect = Aabb2::new(slot_max - Vec2::splat(slot_size), slot_max);
+			slot_max.x -= slot_size + slot_spacing;
+
+			let icon = item_defs.get(&stack.item)
+				.map(|def| def.icon.clone())
+				.unwrap_or_default();
+
+			if index == inventory.selected {
+				painter.rect(slot_rect.grow(2.0), Color::grey(0.8).with_alpha(0.8));
+			}
+
+			painter.rect(slot_rect, Color::black().with_alpha(0.7));
+
+			let glyph_rect = painter.text_rect(16, &icon.glyph);
+			let glyph_pos = slot_rect.center() - glyph_rect.size() / 2.0;
+			painter.text(glyph_pos, 16, &icon.glyph, icon.color);
+
+			if stack.count > 1 {
+				let count = stack.count.to_string();
+				let count_rect = painter.text_rect(8, &count);
+				painter.text(Vec2::new(slot_rect.max.x - count_rect.width() - 2.0, slot_rect.min.y + 2.0), 8, count, Color::white());
+			}
+		}
+
+		if let Some(item) = inventory.selected_item() {
+			let name = item_defs.name(item);
+			let name_rect = painter.text_rect(16, name);
+			let name_pos = Vec2::new(usable_area.max.x - name_rect.width(), usable_area.min.y + slot_size + 8.0);
+
+			painter.rect(name_rect.translate(name_pos).grow(2.0), Color::black().with_alpha(0.5));
+			painter.text(name_pos, 16, name, Color::grey(0.8));
+		}
+	}
+
+	fn draw_objectives(&mut self, usable_area: Aabb2, ui_shared: &mut ui::UiShared, objectives: &[Objective], statuses: &[ObjectiveStatus]) {
+		let mut painter = self.painter.with_shared(ui_shared);
+
+		// Stack downwards from the top left corner, below the frame time
+		let mut line_pos = usable_area.min_max_corner() - Vec2::from_y(40.0);
+
+		for (objective, status) in objectives.iter().zip(statuses) {
+			let (text, color) = match status.completed {
+				true => (format!("{} - done", objective.description), Color::grey(0.5)),
+				false if status.required > 1 => (format!("{} ({}/{})", objective.description, status.current, status.required), Color::grey(0.9)),
+				false => (objective.description.clone(), Color::grey(0.9)),
+			};
+
+			let text_rect = painter.text_rect(16, &text);
+			painter.rect(text_rect.translate(line_pos).grow(2.0), Color::black().with_alpha(0.5));
+			painter.text(line_pos, 16, text, color);
+
+			line_pos.y -= 20.0;
+		}
+	}
+
+	fn draw_choice(&mut self, usable_area: Aabb2, ui_shared: &mut ui::UiShared, choice: &HudChoice) {
+		let mut painter = self.painter.with_shared(ui_shared);
 
 		let font_size = 16;
+		let line_height = font_size as f32 + 4.0;
+
+		let width = std::iter::once(choice.prompt.as_str())
+			.chain(choice.options.iter().map(|option| option.as_str()))
+			.map(|text| painter.text_rect(font_size, text).width())
+			.fold(0.0f32, f32::max);
+
+		let num_lines = choice.options.len() + 1;
+		let size = Vec2::new(width + 16.0, line_height * num_lines as f32);
+		let bounds = Aabb2::from_center_extents(usable_area.center() - Vec2::from_y(size.y / 2.0 + 32.0), size / 2.0);
+
+		painter.rect(bounds.grow(8.0), Color::black().with_alpha(0.8));
 
-		let text_bounds = painter.text_rect(font_size, text);
-		let text_extents = text_bounds.size()/2.0;
-		let center = usable_area.center();
+		// Lines are laid out top to bottom
+		let mut line_pos = Vec2::new(bounds.min.x, bounds.max.y - line_height);
+		painter.text(line_pos, font_size, &choice.prompt, Color::rgb(0.6, 0.1, 0.1));
 
-		let bounds = Aabb2::from_center_extents(center, text_extents + Vec2::splat(16.0));
+		for (index, option) in choice.options.iter().enumerate() {
+			line_pos.y -= line_height;
 
-		painter.rect(bounds, Color::grey(0.02));
-		painter.text(center + text_extents * Vec2::new(-1.0, 1.0) - Vec2::from_y(font_size as f32 - 4.0), font_size, text, Color::rgb(0.6, 0.1, 0.1));
+			let is_selected = index == choice.selected;
+			if is_selected {
+				let option_rect = Aabb2::from_min_size(line_pos, Vec2::new(size.x, line_height)).translate(Vec2::from_y(-2.0));
+				painter.rect(option_rect, Color::grey(0.3).with_alpha(0.8));
+			}
+
+			let color = match is_selected {
+				true => Color::white(),
+				false => Color::grey(0.6),
+			};
+
+			painter.text(line_pos + Vec2::from_x(8.0), font_size, option, color);
+		}
 	}
-}
 
+	fn draw_dialog(&mut self, usable_area: Aabb2, ui_shared: &mut ui::UiShared, dialogues: &DialogueDefs, dialogue: &ActiveDialogue) {
+		let mut painter = self.painter.with_shared(ui_shared);
+
+		let font_size = 16;
+		let line_height = font_size as f32 + 4.0;
+		let padding = 16.0;
+
+		let Some(node) = dialogue.current_node(dialogues) else { return };
+
+		let page = dialogue.current_page(dialogues);
+		let showing_choices = dialogue.showing_choices(dialogues);
+
+		// Size the box for the whole page up front, so it doesn't grow as the text is revealed.
+		let page_size = painter.text_rect(font_size, page).size();
+		let num_choice_lines = match showing_choices {
+			true => dialogue.available_choices.len() as f32 + 0.5,
+			false => 0.0,
+		};
+
+		let content_size = Vec2::new(page_size.x.max(usable_area.width() / 2.0), page_size.y + num_choice_lines * line_height);
+		let bounds = Aabb2::from_min_size(
+			Vec2::new(usable_area.center().x - content_size.x / 2.0 - padding, usable_area.min.y),
+			content_size + Vec2::splat(padding * 2.0),
+		);
 
+		painter.rect(bounds, Color::grey(0.02).with_alpha(0.9));
 
+		let text_top_left = Vec2::new(bounds.min.x + padding, bounds.max.y - padding);
 
+		if let Some(speaker) = &node.speaker {
+			let speaker_rect = painter.text_rect(font_size, speaker);
+			let speaker_pos = Vec2::new(bounds.min.x + padding, bounds.max.y - speaker_rect.height() / 2.0);
+
+			painter.rect(speaker_rect.translate(speaker_pos).grow(4.0), Color::grey(0.1));
+			painter.text(speaker_pos, font_size, speaker, Color::grey(0.8));
+		}
+
+		painter.text(text_top_left - Vec2::from_y(font_size as f32 - 4.0), font_size, dialogue.revealed_text(dialogues), Color::rgb(0.6, 0.1, 0.1));
+
+		if showing_choices {
+			let mut line_pos = text_top_left - Vec2::from_y(page_size.y + line_height * 1.5);
+
+			for (index, &choice_index) in dialogue.available_choices.iter().enumerate() {
+				let is_selected = index == dialogue.selected_choice;
+
+				let (prefix, color) = match is_selected {
+					true => ("> ", Color::white()),
+					false => ("  ", Color::grey(0.5)),
+				};
+
+				painter.text(line_pos, font_size, format!("{prefix}{}", node.choices[choice_index].text), color);
+				line_pos.y -= line_height;
+			}
+
+		} else if dialogue.is_page_revealed(dialogues) {
+			// Prompt to continue
+			let marker_pos = Vec2::new(bounds.max.x - padding, bounds.min.y + padding / 2.0);
+			painter.text(marker_pos, font_size, "v", Color::grey(0.5));
+		}
+	}
+}
+
+
+fn resource_color(display: &ResourceDisplay) -> Color {
+	let flash = display.flash();
+
+	// Flash yellow on gain, red on loss
+	match display.flash_is_gain {
+		true => Color::rgb(1.0, 1.0, 1.0 - 0.4 * flash),
+		false => Color::rgb(1.0, 1.0 - 0.9 * flash, 1.0 - 0.9 * flash),
+	}
+}
@@ -17,18 +17,20 @@ const MAX_VISIBILITY_RECURSION_DEPTH: i32 = 50;
 
 pub struct WorldView {
 	room_renderer: RoomRenderer,
+	sprites: Sprites,
 
 	// Every visible instance of each room
 	visible_rooms: Vec<RoomInstance>,
 
 	message_bus: MessageBus,
-	change_subscription: Subscription<WorldChangedEvent>,
+	change_subscription: Subscription<ProcessedWorldChangedEvent>,
 }
 
 impl WorldView {
 	pub fn new(gfx: &mut gfx::System, processed_world: &ProcessedWorld, message_bus: MessageBus) -> anyhow::Result<Self> {
 		Ok(Self {
 			room_renderer: RoomRenderer::new(gfx, processed_world)?,
+			sprites: Sprites::new(gfx)?,
 			visible_rooms: Vec::new(),
 
 			change_subscription: message_bus.subscribe(),
@@ -37,7 +39,9 @@ impl WorldView {
 	}
 
 	#[instrument(skip_all, name="world_view draw")]
-	pub fn draw(&mut self, gfx: &mut gfx::System, processed_world: &ProcessedWorld, viewer_placement: Placement) {
+	pub fn draw(&mut self, gfx: &mut gfx::System, processed_world: &ProcessedWorld, lights: &LightModel, worms: &WormModel, markers: &[Marker],
+		viewer_placement: Placement)
+	{
 		// Draw room you're in
 		// then for each wall,
 		// 	check if it has a neighbouring room, and if so
@@ -50,6 +54,8 @@ impl WorldView {
 			self.room_renderer.rebuild(gfx, processed_world);
 		}
 
+		self.room_renderer.update_lights(lights);
+
 		self.build_visibility_graph(processed_world, viewer_placement);
 
 		// Draw
@@ -89,6 +95,72 @@ impl WorldView {
 		}
 
 		self.room_renderer.draw(&mut gfx.frame_encoder);
+
+		self.draw_markers(processed_world, markers);
+		self.draw_worms(worms, viewer_placement);
+		self.sprites.draw(gfx);
+	}
+
+	/// Markers are small diamonds flat against whatever they were drawn on, in every visible instance of their room.
+	fn draw_markers(&mut self, processed_world: &ProcessedWorld, markers: &[Marker]) {
+		const MARKER_SIZE: f32 = 0.06;
+		const FLOOR_OFFSET: f32 = 0.005;
+
+		let color = Color::grey(0.9);
+
+		for marker in markers {
+			let location = processed_world.to_processed_location(marker.location);
+
+			for instance in self.visible_rooms.iter().filter(|instance| instance.room_id == location.room_id) {
+				let position = instance.room_to_world * location.position;
+
+				let vertices = match marker.surface {
+					MarkerSurface::Floor => {
+						let center = position.to_xny(instance.height_offset + FLOOR_OFFSET);
+						[
+							center + Vec3::from_x(MARKER_SIZE),
+							center + Vec3::from_z(-MARKER_SIZE),
+							center + Vec3::from_x(-MARKER_SIZE),
+							center + Vec3::from_z(MARKER_SIZE),
+						]
+					}
+
+					MarkerSurface::Wall { normal, height } => {
+						let center = position.to_xny(instance.height_offset + height);
+						let world_normal = instance.room_to_world * (location.position + normal) - position;
+						let along = world_normal.perp().to_x0y() * MARKER_SIZE;
+						let up = Vec3::from_y(MARKER_SIZE);
+
+						[center + along, center + up, center - along, center - up]
+					}
+				};
+
+				self.sprites.add_convex_poly(vertices, color);
+			}
+		}
+	}
+
+	/// Worm segments are drawn in every visible instance of the room they're in, so they can be seen through apertures.
+	// TODO(pat.m): sprites aren't clipped by apertures like rooms are
+	fn draw_worms(&mut self, worms: &WormModel, viewer_placement: Placement) {
+		self.sprites.set_billboard_orientation(Vec3::from_y(1.0), viewer_placement.right().to_x0y());
+
+		for worm in worms.worms.iter() {
+			let num_segments = worm.segments.len() as f32;
+
+			for (index, segment) in worm.segments.iter().enumerate().rev() {
+				let shade = 0.4 - 0.25 * index as f32 / num_segments;
+				let color = Color::rgb(shade, shade * 0.5, shade * 0.6);
+				let size = Vec2::new(segment.radius * 2.0, segment.radius * 2.0);
+
+				for instance in self.visible_rooms.iter().filter(|instance| instance.room_id == segment.location.room_id) {
+					let position = instance.room_to_world * segment.location.position;
+					let height = instance.height_offset + segment.height - segment.radius;
+
+					self.sprites.billboard(position.to_xny(height), size, color);
+				}
+			}
+		}
 	}
 
 	#[instrument(skip_all, name="world_view build_visibility_graph")]
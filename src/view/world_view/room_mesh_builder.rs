@@ -144,8 +144,12 @@ impl RoomMeshBuilder<'_> {
 
 		// Objects
 		self.set_texture_index(0);
-		for object in self.processed_world.objects_in_room(room_id) {
-			self.build_object(object);
+		let processed_world = self.processed_world;
+		for object_id in processed_world.object_ids_for_room(room_id) {
+			let opened = processed_world.runtime_objects().get(object_id)
+				.is_some_and(|(_, state)| state.opened);
+
+			self.build_object(&processed_world.objects[object_id], opened);
 		}
 
 		let num_elements = self.indices.len() as u32 - base_index;
@@ -195,7 +199,7 @@ impl RoomMeshBuilder<'_> {
 		}
 	}
 
-	pub fn build_object(&mut self, object: &Object) {
+	pub fn build_object(&mut self, object: &Object, opened: bool) {
 		match &object.info {
 			ObjectInfo::Debug => {
 				let forward = object.placement.forward().to_x0y() * 0.1;
@@ -238,6 +242,53 @@ impl RoomMeshBuilder<'_> {
 				self.add_convex_untextured(verts, Color::grey(0.02));
 			}
 
+			ObjectInfo::Chest{..} => {
+				let forward = object.placement.forward().to_x0y() * 0.1;
+				let right = object.placement.right().to_x0y() * 0.15;
+				let center = object.placement.position.to_x0y();
+				let up = Vec3::from_y(0.15);
+
+				// Front and top faces
+				let verts = [
+					center + forward - right,
+					center + forward - right + up,
+					center + forward + right + up,
+					center + forward + right,
+				];
+
+				self.add_convex_untextured(verts, Color::rgb(0.3, 0.15, 0.05));
+
+				let verts = [
+					center + forward - right + up,
+					center - forward - right + up,
+					center - forward + right + up,
+					center + forward + right + up,
+				];
+
+				self.add_convex_untextured(verts, Color::rgb(0.25, 0.12, 0.04));
+			}
+
+			ObjectInfo::Door{..} => {
+				let up = Vec3::from_y(0.8);
+				let right = object.placement.right() * DOOR_HALF_WIDTH;
+				let hinge = object.placement.position - right;
+
+				// Opened doors swing back on their hinge
+				let edge = match opened {
+					false => object.placement.position + right,
+					true => hinge + object.placement.forward() * DOOR_HALF_WIDTH * 2.0,
+				};
+
+				let verts = [
+					hinge.to_x0y(),
+					hinge.to_x0y() + up,
+					edge.to_x0y() + up,
+					edge.to_x0y(),
+				];
+
+				self.add_convex_untextured(verts, Color::rgb(0.15, 0.07, 0.02));
+			}
+
 			_ => {}
 		}
 	}
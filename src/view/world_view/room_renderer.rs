@@ -10,7 +10,11 @@ pub struct RoomRenderer {
 
 	vbo: gfx::BufferName,
 	ebo: gfx::BufferName,
-	light_buffer: gfx::BufferName,
+
+	// Every light in every room it reaches, and the light object it came from.
+	// Lights change every frame, so these are uploaded each frame rather than baked into a buffer.
+	baked_lights: Vec<(ObjectId, RoomLight)>,
+	animated_lights: Vec<RoomLight>,
 
 	v_shader: gfx::ShaderHandle,
 	f_shader: gfx::ShaderHandle,
@@ -21,13 +25,12 @@ impl RoomRenderer {
 	pub fn new(gfx: &mut gfx::System, processed_world: &ProcessedWorld) -> anyhow::Result<Self> {
 		let vbo = gfx.core.create_buffer();
 		let ebo = gfx.core.create_buffer();
-		let light_buffer = gfx.core.create_buffer();
 
 		gfx.core.set_debug_label(vbo, "Room vertex buffer");
 		gfx.core.set_debug_label(ebo, "Room index buffer");
-		gfx.core.set_debug_label(light_buffer, "Room light buffer");
 
-		let room_mesh_infos = build_room_buffers(gfx, processed_world, vbo, ebo, light_buffer);
+		let (room_mesh_infos, baked_lights) = build_room_buffers(gfx, processed_world, vbo, ebo);
+		let animated_lights = baked_lights.iter().map(|&(_, light)| light).collect();
 
 
 		Ok(RoomRenderer {
@@ -35,7 +38,8 @@ impl RoomRenderer {
 			instances: Default::default(),
 
 			vbo, ebo,
-			light_buffer,
+			baked_lights,
+			animated_lights,
 
 			v_shader: gfx.resource_manager.load_vertex_shader("shaders/room.vs.glsl"),
 			f_shader: gfx.resource_manager.load_fragment_shader("shaders/room.fs.glsl"),
@@ -54,17 +58,25 @@ impl RoomRenderer {
 	pub fn rebuild(&mut self, gfx: &mut gfx::System, processed_world: &ProcessedWorld) {
 		gfx.core.destroy_buffer(self.vbo);
 		gfx.core.destroy_buffer(self.ebo);
-		gfx.core.destroy_buffer(self.light_buffer);
 
 		self.vbo = gfx.core.create_buffer();
 		self.ebo = gfx.core.create_buffer();
-		self.light_buffer = gfx.core.create_buffer();
 
 		gfx.core.set_debug_label(self.vbo, "Room vertex buffer");
 		gfx.core.set_debug_label(self.ebo, "Room index buffer");
-		gfx.core.set_debug_label(self.light_buffer, "Room light buffer");
 
-		self.room_mesh_infos = build_room_buffers(gfx, processed_world, self.vbo, self.ebo, self.light_buffer);
+		(self.room_mesh_infos, self.baked_lights) = build_room_buffers(gfx, processed_world, self.vbo, self.ebo);
+	}
+
+	/// Applies the current color and power of each light to every room it reaches, including through apertures.
+	pub fn update_lights(&mut self, lights: &LightModel) {
+		self.animated_lights.clear();
+		self.animated_lights.extend(self.baked_lights.iter().map(|&(object_id, room_light)| {
+			match lights.light(object_id) {
+				Some(state) => RoomLight { color: state.color.into(), power: state.power, ..room_light },
+				None => room_light,
+			}
+		}));
 	}
 
 	pub fn add_instance(&mut self, room_id: RoomId, transform: Mat3x4, planes: &[Vec4; 3]) {
@@ -85,6 +97,8 @@ impl RoomRenderer {
 	pub fn draw(&self, encoder: &mut gfx::FrameEncoder) {
 		let index_size = std::mem::size_of::<u32>() as u32;
 
+		let light_upload = encoder.upload(&self.animated_lights);
+
 		let mut group = encoder.command_group(gfx::FrameStage::Main);
 
 		for (room_id, instance_list) in self.instances.iter() {
@@ -104,7 +118,7 @@ impl RoomRenderer {
 				.ssbo(0, self.vbo)
 				.ssbo(1, instance_data_upload)
 				.ssbo(2, &[mesh_info.base_light, mesh_info.num_lights])
-				.ssbo(3, self.light_buffer);
+				.ssbo(3, light_upload);
 		}
 	}
 }
@@ -121,8 +135,8 @@ struct RoomUniforms {
 
 
 
-fn build_room_buffers(gfx: &mut gfx::System, processed_world: &ProcessedWorld,
-	vbo: gfx::BufferName, ebo: gfx::BufferName, light_buffer: gfx::BufferName) -> slotmap::SecondaryMap<RoomId, RoomMeshInfo>
+fn build_room_buffers(gfx: &mut gfx::System, processed_world: &ProcessedWorld, vbo: gfx::BufferName, ebo: gfx::BufferName)
+	-> (slotmap::SecondaryMap<RoomId, RoomMeshInfo>, Vec<(ObjectId, RoomLight)>)
 {
 	use slotmap::SecondaryMap;
 
@@ -155,12 +169,13 @@ fn build_room_buffers(gfx: &mut gfx::System, processed_world: &ProcessedWorld,
 		])
 	}
 
-	let mut room_lights: SecondaryMap<_, Vec<RoomLight>> = SecondaryMap::with_capacity(processed_world.geometry().rooms.len());
+	let mut room_lights: SecondaryMap<_, Vec<(ObjectId, RoomLight)>> = SecondaryMap::with_capacity(processed_world.geometry().rooms.len());
 	let mut room_queue = SmallVec::<[QueueEntry; 16]>::new();
 
 	// Figure out which rooms touched by each light.
-	for object in processed_world.objects.values() {
-		let Some(light) = object.as_light() else { continue };
+	// Unlit lights are included too, so that lighting them doesn't need a rebuild.
+	for (object_id, object) in processed_world.objects.iter() {
+		let Some(light) = object.as_light().filter(|light| light.power > 0.0) else { continue };
 
 		room_queue.clear();
 		room_queue.push(QueueEntry {
@@ -181,7 +196,7 @@ fn build_room_buffers(gfx: &mut gfx::System, processed_world: &ProcessedWorld,
 				let light_list = room_lights.entry(room_entry.room_id)
 					.unwrap().or_default();
 
-				light_list.push(RoomLight {
+				light_list.push((object_id, RoomLight {
 					local_pos,
 					radius: light.radius,
 					color: light.color.into(),
@@ -189,7 +204,7 @@ fn build_room_buffers(gfx: &mut gfx::System, processed_world: &ProcessedWorld,
 
 					plane_0: room_entry.plane_0.to_x0y(),
 					plane_1: room_entry.plane_1.to_x0y(),
-				});
+				}));
 			}
 
 			// Bail if we've hit recursion limit
@@ -258,24 +273,22 @@ fn build_room_buffers(gfx: &mut gfx::System, processed_world: &ProcessedWorld,
 		}
 	}
 
-	// Collect lights for each room into a buffer for upload
-	let mut built_light_buffer = Vec::new();
+	// Collect lights for each room into one list, so each room only needs a range
+	let mut baked_lights = Vec::new();
 	for (room_id, mesh_info) in room_mesh_infos.iter_mut() {
 		let Some(light_list) = room_lights.get(room_id) else { continue };
 
-		mesh_info.base_light = built_light_buffer.len() as u32;
+		mesh_info.base_light = baked_lights.len() as u32;
 		mesh_info.num_lights = light_list.len() as u32;
 
-		built_light_buffer.extend_from_slice(light_list);
+		baked_lights.extend_from_slice(light_list);
 	}
 
-	gfx.core.upload_immutable_buffer_immediate(light_buffer, &built_light_buffer);
-
 	room_builder.upload(gfx, vbo, ebo);
 
 	gfx.core.debug_marker("Uploaded Room Data");
 
-	room_mesh_infos
+	(room_mesh_infos, baked_lights)
 }
 
 